@@ -0,0 +1,80 @@
+// Chunk material, the standard pbr shader with the base color sampled from the block texture array.
+// faces store the layer of their texture and the position inside the face in their uvs,
+// see get_tiled_texture in src/world/block.rs for the encoding.
+#import bevy_pbr::{
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::alpha_discard,
+}
+
+#ifdef PREPASS_PIPELINE
+#import bevy_pbr::{
+    prepass_io::{VertexOutput, FragmentOutput},
+    pbr_deferred_functions::deferred_output,
+}
+#else
+#import bevy_pbr::{
+    forward_io::{VertexOutput, FragmentOutput},
+    pbr_functions::{apply_pbr_lighting, main_pass_post_lighting_processing},
+}
+#endif
+
+struct ChunkTextures {
+    tile_stride: f32,
+    tints: array<vec4<f32>, 4>,
+    ao_brightness: vec4<f32>,
+    fade: f32,
+}
+
+@group(1) @binding(100)
+var<uniform> chunk_textures: ChunkTextures;
+@group(1) @binding(101)
+var block_textures: texture_2d_array<f32>;
+@group(1) @binding(102)
+var block_sampler: sampler;
+
+// the color of the block texture at a tiled uv
+fn block_texture(uv: vec2<f32>) -> vec4<f32> {
+    let layer = floor((uv.x - 1.0) / chunk_textures.tile_stride);
+    let local = vec2<f32>(uv.x - layer * chunk_textures.tile_stride - 1.0, uv.y);
+    // faces start at their bottom edge, the image at its top one. the sampler repeats the
+    // texture across merged faces
+    return textureSample(block_textures, block_sampler, vec2<f32>(local.x, 1.0 - local.y), i32(layer));
+}
+
+// new chunks dissolve in, fragments above the fade threshold of a 4x4 ordered dither are dropped
+fn faded_out(position: vec2<f32>) -> bool {
+    if chunk_textures.fade >= 1.0 {
+        return false;
+    }
+    var bayer = array<f32, 16>(
+        0.0, 8.0, 2.0, 10.0,
+        12.0, 4.0, 14.0, 6.0,
+        3.0, 11.0, 1.0, 9.0,
+        15.0, 7.0, 13.0, 5.0,
+    );
+    let cell = vec2<u32>(position) % 4u;
+    return (bayer[cell.y * 4u + cell.x] + 0.5) / 16.0 > chunk_textures.fade;
+}
+
+@fragment
+fn fragment(
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> FragmentOutput {
+    if faded_out(in.position.xy) {
+        discard;
+    }
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+#ifdef VERTEX_UVS
+    pbr_input.material.base_color *= block_texture(in.uv);
+#endif
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+#ifdef PREPASS_PIPELINE
+    let out = deferred_output(in, pbr_input);
+#else
+    var out: FragmentOutput;
+    out.color = apply_pbr_lighting(pbr_input);
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+#endif
+    return out;
+}
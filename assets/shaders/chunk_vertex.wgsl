@@ -0,0 +1,94 @@
+// Chunk vertex shader for packed vertices, see src/world/client/packed.rs for the layout.
+// it produces the same outputs as the standard vertex shaders, with the uvs in the tiled
+// encoding of get_tiled_texture so chunk.wgsl samples them like the uvs of merged faces.
+#import bevy_pbr::mesh_functions
+#import bevy_render::instance_index::get_instance_index
+
+#ifdef PREPASS_PIPELINE
+#import bevy_pbr::prepass_io::VertexOutput
+#else
+#import bevy_pbr::forward_io::VertexOutput
+#endif
+
+struct ChunkTextures {
+    tile_stride: f32,
+    tints: array<vec4<f32>, 4>,
+    ao_brightness: vec4<f32>,
+    fade: f32,
+}
+
+@group(1) @binding(100)
+var<uniform> chunk_textures: ChunkTextures;
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) packed: vec2<u32>,
+}
+
+// PACKED_NORMALS
+fn packed_normal(index: u32) -> vec3<f32> {
+    let diagonal = 0.70710678;
+    var normals = array<vec3<f32>, 10>(
+        vec3<f32>(1.0, 0.0, 0.0),
+        vec3<f32>(-1.0, 0.0, 0.0),
+        vec3<f32>(0.0, 1.0, 0.0),
+        vec3<f32>(0.0, -1.0, 0.0),
+        vec3<f32>(0.0, 0.0, 1.0),
+        vec3<f32>(0.0, 0.0, -1.0),
+        vec3<f32>(diagonal, 0.0, diagonal),
+        vec3<f32>(-diagonal, 0.0, -diagonal),
+        vec3<f32>(diagonal, 0.0, -diagonal),
+        vec3<f32>(-diagonal, 0.0, diagonal),
+    );
+    return normals[min(index, 9u)];
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let low = vertex.packed.x;
+    let high = vertex.packed.y;
+    // positions and uvs are stored in 1/16 steps
+    let position = vec3<f32>((vec3<u32>(low, low >> 9u, low >> 18u) & vec3<u32>(511u))) / 16.0;
+    let normal = packed_normal((low >> 27u) & 15u);
+    let local_uv = vec2<f32>((vec2<u32>(high, high >> 9u) & vec2<u32>(511u))) / 16.0;
+    let layer = (high >> 18u) & 63u;
+    let ao = (high >> 24u) & 3u;
+    let light = (high >> 26u) & 15u;
+    let tint = chunk_textures.tints[high >> 30u];
+
+    var out: VertexOutput;
+    let model = mesh_functions::get_model_matrix(vertex.instance_index);
+    out.world_position = mesh_functions::mesh_position_local_to_world(model, vec4<f32>(position, 1.0));
+    out.position = mesh_functions::mesh_position_local_to_clip(model, vec4<f32>(position, 1.0));
+
+#ifdef PREPASS_PIPELINE
+#ifdef NORMAL_PREPASS_OR_DEFERRED_PREPASS
+    out.world_normal = mesh_functions::mesh_normal_local_to_world(normal, get_instance_index(vertex.instance_index));
+#endif
+#ifdef DEPTH_CLAMP_ORTHO
+    out.clip_position_unclamped = out.position;
+    out.position.z = min(out.position.z, 1.0);
+#endif
+#ifdef MOTION_VECTOR_PREPASS
+    out.previous_world_position = mesh_functions::mesh_position_local_to_world(
+        mesh_functions::get_previous_model_matrix(vertex.instance_index),
+        vec4<f32>(position, 1.0)
+    );
+#endif
+#else
+    out.world_normal = mesh_functions::mesh_normal_local_to_world(normal, get_instance_index(vertex.instance_index));
+#endif
+
+    out.uv = vec2<f32>(f32(layer) * chunk_textures.tile_stride + 1.0, 0.0) + local_uv;
+    let brightness = chunk_textures.ao_brightness[ao] * f32(light) / 15.0;
+    out.color = vec4<f32>(tint.rgb * brightness, tint.a);
+
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+    out.instance_index = get_instance_index(vertex.instance_index);
+#endif
+#ifdef BASE_INSTANCE_WORKAROUND
+    // see the same workaround in bevy_pbr's mesh.wgsl
+    out.position.x += min(f32(get_instance_index(0u)), 0.0);
+#endif
+    return out;
+}
@@ -0,0 +1,182 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use bevy::{
+    math::IVec3,
+    render::{
+        mesh::{Indices, Mesh, VertexAttributeValues},
+        render_resource::PrimitiveTopology,
+    },
+};
+use voxels::world::{
+    block::{get_texture, quad_vertices, BlockFace},
+    chunk::{Chunk, ChunkView, CHUNK_DEPTH, CHUNK_HEIGHT, CHUNK_WIDTH},
+    client::{
+        greedy_mesh::gen_greedy_mesh,
+        mesh_utils::{ChunkMeshBuilder, MeshPass},
+        packed::ChunkVertexFormat,
+    },
+    element::Element,
+    server::chunk::{generate_chunk, get_surrounding_chunks},
+};
+
+// cargo bench --bench meshing
+// meshes the chunks around the spawn point with every mesher and reports time, vertex counts
+// and how many allocations a pass needs
+
+const ITERATIONS: u32 = 5;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn bench<'a>(name: &str, chunks: &'a [Chunk], mut mesher: impl FnMut(&'a Chunk) -> usize) {
+    let mut vertices = 0;
+    let mut elapsed = Duration::ZERO;
+    let allocations_before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        vertices = chunks.iter().map(&mut mesher).sum::<usize>();
+        elapsed += start.elapsed();
+    }
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations_before;
+    println!(
+        "{:<16} {:>10} vertices {:>10.2?} per pass ({:.2?} per chunk) {:>8} allocations per pass",
+        name,
+        vertices,
+        elapsed / ITERATIONS,
+        elapsed / ITERATIONS / chunks.len() as u32,
+        allocations / ITERATIONS as usize,
+    );
+}
+
+// the old way of meshing a chunk, a Mesh for every face that is copied into one mesh afterwards.
+// kept as the baseline for ChunkMeshBuilder
+fn per_quad_mesh(view: &ChunkView) -> Mesh {
+    let directions = [
+        (BlockFace::East, IVec3::X),
+        (BlockFace::North, IVec3::Z),
+        (BlockFace::Top, IVec3::Y),
+        (BlockFace::Bottom, IVec3::NEG_Y),
+        (BlockFace::South, IVec3::NEG_Z),
+        (BlockFace::West, IVec3::NEG_X),
+    ];
+    let mut quads: Vec<Mesh> = Vec::new();
+    for x in 0..CHUNK_WIDTH {
+        for y in 0..CHUNK_HEIGHT {
+            for z in 0..CHUNK_DEPTH {
+                let block = view.chunk.blocks[x][y][z];
+                if block.element == Element::Air {
+                    continue;
+                }
+                let position = IVec3::new(x as i32, y as i32, z as i32);
+                let offset = (view.chunk.world_origin() + position).as_vec3();
+                for (side, direction) in directions {
+                    if view.element(position + direction) != Element::Air {
+                        continue;
+                    }
+                    let (corners, normal) = quad_vertices(1.0, side, offset);
+                    let uvs = get_texture(block.texture(side).atlas_rect());
+                    quads.push(
+                        Mesh::new(PrimitiveTopology::TriangleList)
+                            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, corners.to_vec())
+                            .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![normal; 4])
+                            .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs.to_vec()),
+                    );
+                }
+            }
+        }
+    }
+
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+    for (quad, offset) in quads.iter().zip((0..).step_by(4)) {
+        if let Some(VertexAttributeValues::Float32x3(values)) =
+            quad.attribute(Mesh::ATTRIBUTE_POSITION)
+        {
+            positions.extend_from_slice(values);
+        }
+        if let Some(VertexAttributeValues::Float32x3(values)) =
+            quad.attribute(Mesh::ATTRIBUTE_NORMAL)
+        {
+            normals.extend_from_slice(values);
+        }
+        if let Some(VertexAttributeValues::Float32x2(values)) = quad.attribute(Mesh::ATTRIBUTE_UV_0)
+        {
+            uvs.extend_from_slice(values);
+        }
+        indices.extend([
+            offset,
+            offset + 1,
+            offset + 2,
+            offset + 2,
+            offset + 3,
+            offset,
+        ]);
+    }
+    Mesh::new(PrimitiveTopology::TriangleList)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_indices(Some(Indices::U32(indices)))
+}
+
+fn main() {
+    let chunks: Vec<Chunk> = get_surrounding_chunks(0, 0, 0, 2)
+        .into_iter()
+        .map(|position| generate_chunk(position.x, position.y, position.z))
+        .collect();
+    println!("{} chunks, {} passes", chunks.len(), ITERATIONS);
+
+    let chunk_map: HashMap<IVec3, &Chunk> = chunks
+        .iter()
+        .map(|chunk| (chunk.position(), chunk))
+        .collect();
+
+    bench("per quad", &chunks, |chunk| {
+        per_quad_mesh(&ChunkView::new(chunk, &chunk_map)).count_vertices()
+    });
+    bench("builder", &chunks, |chunk| {
+        let mut builder = ChunkMeshBuilder::new(ChunkVertexFormat::Standard);
+        builder.add_chunk(1.0, &ChunkView::new(chunk, &chunk_map), MeshPass::Opaque);
+        builder.build().count_vertices()
+    });
+    let mut builder = ChunkMeshBuilder::new(ChunkVertexFormat::Standard);
+    bench("reused builder", &chunks, |chunk| {
+        builder.add_chunk(1.0, &ChunkView::new(chunk, &chunk_map), MeshPass::Opaque);
+        builder.build().count_vertices()
+    });
+    bench("greedy", &chunks, |chunk| {
+        gen_greedy_mesh(1.0, &ChunkView::new(chunk, &chunk_map), MeshPass::Opaque).count_vertices()
+    });
+    let mut builder = ChunkMeshBuilder::new(ChunkVertexFormat::Standard);
+    bench("reused greedy", &chunks, |chunk| {
+        builder.add_greedy_chunk(1.0, &ChunkView::new(chunk, &chunk_map), MeshPass::Opaque);
+        builder.build().count_vertices()
+    });
+}
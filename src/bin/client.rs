@@ -13,16 +13,16 @@ use bevy_renet::{
 };
 use bevy_screen_diagnostics::{ScreenDiagnosticsPlugin, ScreenFrameDiagnosticsPlugin};
 use renet_visualizer::{RenetClientVisualizer, RenetVisualizerStyle};
-use std::{collections::HashMap, net::UdpSocket, time::SystemTime};
+use std::{collections::HashMap, net::UdpSocket, process::ExitCode, time::SystemTime};
 
 use voxels::{
     app_state::plugin::AppStatePlugin,
     command_system::plugin::CommandPlugin,
     debug_menu::plugin::DebugPlugin,
     main_menu::plugin::MainMenuPlugin,
-    net::{connection_config, ServerChannel, ServerMessages, PROTOCOL_ID},
-    player::client::plugin::PlayerClientPlugin,
-    world::client::plugin::ClientWorldPlugin,
+    net::{connection_config, ServerChannel, ServerMessage, PROTOCOL_ID},
+    player::client::{handshake::ClientProfile, plugin::PlayerClientPlugin},
+    world::client::{packed::ChunkVertexFormat, plugin::ClientWorldPlugin},
 };
 
 #[derive(Component)]
@@ -48,7 +48,34 @@ struct CurrentClientId(u64);
 #[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Connected;
 
-fn main() {
+struct ClientArgs {
+    name: Option<String>,
+    vertex_format: ChunkVertexFormat,
+}
+
+fn parse_args(args: Vec<String>) -> Option<ClientArgs> {
+    let mut args = args.into_iter();
+    let mut client_args = ClientArgs {
+        name: None,
+        vertex_format: ChunkVertexFormat::default(),
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--vertex-format" => {
+                client_args.vertex_format = ChunkVertexFormat::from_string(&args.next()?)?
+            }
+            _ if arg.starts_with("--") || client_args.name.is_some() => return None,
+            _ => client_args.name = Some(arg),
+        }
+    }
+    Some(client_args)
+}
+
+fn main() -> ExitCode {
+    let Some(args) = parse_args(std::env::args().skip(1).collect()) else {
+        eprintln!("usage: client [name] [--vertex-format <packed|standard>]");
+        return ExitCode::FAILURE;
+    };
     let mut app = App::new();
     let client = RenetClient::new(connection_config());
     let server_addr = "127.0.0.1:5000".parse().unwrap();
@@ -74,6 +101,8 @@ fn main() {
         .add_plugins(ScreenFrameDiagnosticsPlugin)
         .add_plugins(AppStatePlugin)
         .add_plugins(PlayerClientPlugin)
+        // standard meshes work where the chunk shader does not
+        .insert_resource(args.vertex_format)
         .add_plugins(ClientWorldPlugin)
         .add_plugins(DebugPlugin)
         .add_plugins(MainMenuPlugin)
@@ -82,6 +111,13 @@ fn main() {
     app.insert_resource(client);
     app.insert_resource(transport);
     app.insert_resource(CurrentClientId(client_id));
+    app.insert_resource(match args.name {
+        Some(name) => ClientProfile {
+            name,
+            ..Default::default()
+        },
+        None => ClientProfile::for_client(client_id),
+    });
     app.insert_resource(ClientLobby::default());
     app.insert_resource(NetworkMapping::default());
     app.insert_resource(RenetClientVisualizer::<200>::new(
@@ -90,6 +126,7 @@ fn main() {
     app.add_systems(Update, panic_on_error_system);
     app.add_systems(Update, (client_sync_players,).in_set(Connected));
     app.run();
+    ExitCode::SUCCESS
 }
 
 // If any error is found we just panic
@@ -110,9 +147,15 @@ fn client_sync_players(
 ) {
     let client_id = client_id.0;
     while let Some(message) = client.receive_message(ServerChannel::ServerMessages) {
-        let server_message = bincode::deserialize(&message).unwrap();
+        let server_message = match ServerMessage::decode(&message) {
+            Ok(server_message) => server_message,
+            Err(error) => {
+                warn!("could not read a server message: {}", error);
+                continue;
+            }
+        };
         match server_message {
-            ServerMessages::PlayerCreate {
+            ServerMessage::PlayerCreate {
                 id,
                 translation,
                 entity,
@@ -136,7 +179,7 @@ fn client_sync_players(
                 lobby.players.insert(id, player_info);
                 network_mapping.0.insert(entity, client_entity.id());
             }
-            ServerMessages::PlayerRemove { id } => {
+            ServerMessage::PlayerRemove { id } => {
                 println!("Player {} disconnected.", id);
                 if let Some(PlayerInfo {
                     server_entity,
@@ -147,6 +190,7 @@ fn client_sync_players(
                     network_mapping.0.remove(&server_entity);
                 }
             }
+            _ => {}
         }
     }
 }
@@ -0,0 +1,425 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use bevy::{
+    prelude::*,
+    render::mesh::{Indices, VertexAttributeValues},
+};
+use serde_json::json;
+use voxels::world::{
+    atlas::{load_block_textures, pack_block_atlas},
+    block::Block,
+    chunk::{world_to_chunk_position, Chunk, ChunkView, CHUNK_DEPTH, CHUNK_HEIGHT, CHUNK_WIDTH},
+    client::{
+        mesh_utils::{ChunkMeshBuilder, MeshPass},
+        packed::ChunkVertexFormat,
+    },
+    element::Element,
+    server::chunk::{generate_chunk, get_surrounding_chunks},
+    vox::{load_vox, vox_to_blocks, VoxMapping, VOX_MAPPING_FILE},
+};
+
+// Offline export of world geometry to glTF or OBJ.
+// runs without a window or GPU, the chunks are generated (or built from a .vox file) and meshed
+// with the same functions the client uses, the block atlas is packed and written next to the output as its texture.
+// every mesh pass gets its own primitive (glTF) or group (OBJ) and a material with the alpha mode the client draws it with.
+//
+// cargo run --bin export -- <output.gltf|output.obj> [--center <chunk x> <chunk z>] [--radius <chunks>] [--vox <file>]
+
+const ASSETS_PATH: &str = "assets";
+const ATLAS_FILE_NAME: &str = "blockatlas.png";
+
+struct ExportArgs {
+    output: PathBuf,
+    center: IVec2,
+    radius: i32,
+    vox: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1).collect()) {
+        Some(args) => args,
+        None => {
+            eprintln!(
+                "usage: export <output.gltf|output.obj> [--center <chunk x> <chunk z>] [--radius <chunks>] [--vox <file>]"
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let chunks = match &args.vox {
+        Some(path) => match load_vox(path) {
+            Ok(vox) => chunks_from_blocks(vox_to_blocks(
+                &vox,
+                &VoxMapping::load_or_default(VOX_MAPPING_FILE),
+            )),
+            Err(error) => {
+                eprintln!("could not load {}: {}", path.display(), error);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => get_surrounding_chunks(args.center.x, 0, args.center.y, args.radius)
+            .into_iter()
+            .map(|position| generate_chunk(position.x, position.y, position.z))
+            .collect(),
+    };
+    println!("meshing {} chunks", chunks.len());
+
+    let chunk_map: HashMap<IVec3, &Chunk> = chunks
+        .iter()
+        .map(|chunk| (chunk.position(), chunk))
+        .collect();
+    let meshes: Vec<(MeshPass, Mesh)> = MeshPass::ALL
+        .into_iter()
+        .map(|pass| {
+            let mut mesh_builder = ChunkMeshBuilder::new(ChunkVertexFormat::Standard);
+            for chunk in chunks.iter() {
+                mesh_builder.add_chunk(1.0, &ChunkView::new(chunk, &chunk_map), pass);
+            }
+            (pass, mesh_builder.build())
+        })
+        .collect();
+    let passes = match meshes
+        .iter()
+        .map(|(pass, mesh)| Ok((*pass, mesh_data(mesh)?)))
+        .collect::<io::Result<Vec<_>>>()
+    {
+        // passes without faces are left out, empty accessors are not valid gltf
+        Ok(passes) => passes
+            .into_iter()
+            .filter(|(_, data)| !data.indices.is_empty())
+            .collect::<Vec<_>>(),
+        Err(error) => {
+            eprintln!("could not read the meshes: {}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Some(directory) = args.output.parent() {
+        if let Err(error) = fs::create_dir_all(directory) {
+            eprintln!("could not create {}: {}", directory.display(), error);
+            return ExitCode::FAILURE;
+        }
+    }
+    let result = match args
+        .output
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        Some("gltf") => write_gltf(&args.output, &passes),
+        Some("obj") => write_obj(&args.output, &passes),
+        _ => {
+            eprintln!("output must end in .gltf or .obj");
+            return ExitCode::FAILURE;
+        }
+    };
+    let result = result.and_then(|_| write_atlas(&args.output));
+
+    match result {
+        Ok(()) => {
+            println!(
+                "wrote {} vertices to {}",
+                passes
+                    .iter()
+                    .map(|(_, data)| data.positions.len())
+                    .sum::<usize>(),
+                args.output.display()
+            );
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("could not write {}: {}", args.output.display(), error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: Vec<String>) -> Option<ExportArgs> {
+    let mut args = args.into_iter();
+    let mut export_args = ExportArgs {
+        output: PathBuf::from(args.next()?),
+        center: IVec2::ZERO,
+        radius: 1,
+        vox: None,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--center" => {
+                export_args.center =
+                    IVec2::new(args.next()?.parse().ok()?, args.next()?.parse().ok()?)
+            }
+            "--radius" => export_args.radius = args.next()?.parse().ok()?,
+            "--vox" => export_args.vox = Some(PathBuf::from(args.next()?)),
+            _ => return None,
+        }
+    }
+    Some(export_args)
+}
+
+fn chunks_from_blocks(blocks: Vec<(IVec3, Element)>) -> Vec<Chunk> {
+    let mut chunks: HashMap<IVec3, Chunk> = HashMap::new();
+    for (position, element) in blocks {
+        let (chunk_position, [x, y, z]) = world_to_chunk_position(position);
+        let chunk = chunks.entry(chunk_position).or_insert_with(|| Chunk {
+            chunk_x: chunk_position.x as f32,
+            chunk_y: chunk_position.y as f32,
+            chunk_z: chunk_position.z as f32,
+            blocks: [[[Block::default(); CHUNK_WIDTH]; CHUNK_HEIGHT]; CHUNK_DEPTH],
+        });
+        chunk.blocks[x][y][z] = Block::new(element);
+    }
+    chunks.into_values().collect()
+}
+
+fn material_name(pass: MeshPass) -> &'static str {
+    match pass {
+        MeshPass::Opaque => "opaque",
+        MeshPass::Cutout => "cutout",
+        MeshPass::Transparent => "transparent",
+    }
+}
+
+struct MeshData<'a> {
+    positions: &'a [[f32; 3]],
+    normals: &'a [[f32; 3]],
+    uvs: &'a [[f32; 2]],
+    colors: &'a [[f32; 4]],
+    indices: &'a [u32],
+}
+
+fn mesh_data(mesh: &Mesh) -> io::Result<MeshData<'_>> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "mesh is missing attributes");
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return Err(invalid());
+    };
+    let Some(VertexAttributeValues::Float32x3(normals)) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+    else {
+        return Err(invalid());
+    };
+    let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute(Mesh::ATTRIBUTE_UV_0) else {
+        return Err(invalid());
+    };
+    let Some(VertexAttributeValues::Float32x4(colors)) = mesh.attribute(Mesh::ATTRIBUTE_COLOR)
+    else {
+        return Err(invalid());
+    };
+    let Some(Indices::U32(indices)) = mesh.indices() else {
+        return Err(invalid());
+    };
+    Ok(MeshData {
+        positions,
+        normals,
+        uvs,
+        colors,
+        indices,
+    })
+}
+
+fn write_obj(path: &Path, passes: &[(MeshPass, MeshData)]) -> io::Result<()> {
+    let material_path = path.with_extension("mtl");
+    let material_file_name = material_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("blocks.mtl");
+
+    let mut material = BufWriter::new(fs::File::create(&material_path)?);
+    for (pass, _) in passes {
+        writeln!(material, "newmtl {}", material_name(*pass))?;
+        writeln!(material, "Kd 1.0 1.0 1.0")?;
+        writeln!(material, "map_Kd {}", ATLAS_FILE_NAME)?;
+        // obj has no alpha modes, cutout and transparent blocks take their opacity from the atlas
+        if *pass != MeshPass::Opaque {
+            writeln!(material, "map_d {}", ATLAS_FILE_NAME)?;
+        }
+    }
+    material.flush()?;
+
+    let mut obj = BufWriter::new(fs::File::create(path)?);
+    writeln!(obj, "mtllib {}", material_file_name)?;
+    // obj indices are 1 based and count every vertex before them in the file
+    let mut first_index = 1;
+    for (pass, data) in passes {
+        writeln!(obj, "g {}", material_name(*pass))?;
+        writeln!(obj, "usemtl {}", material_name(*pass))?;
+        for [x, y, z] in data.positions {
+            writeln!(obj, "v {} {} {}", x, y, z)?;
+        }
+        for [x, y, z] in data.normals {
+            writeln!(obj, "vn {} {} {}", x, y, z)?;
+        }
+        // obj texture coordinates start at the bottom of the image
+        for [u, v] in data.uvs {
+            writeln!(obj, "vt {} {}", u, 1.0 - v)?;
+        }
+        for triangle in data.indices.chunks_exact(3) {
+            // position, uv and normal share the same index
+            let [a, b, c] = [
+                triangle[0] + first_index,
+                triangle[1] + first_index,
+                triangle[2] + first_index,
+            ];
+            writeln!(obj, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?;
+        }
+        first_index += data.positions.len() as u32;
+    }
+    obj.flush()
+}
+
+fn write_gltf(path: &Path, passes: &[(MeshPass, MeshData)]) -> io::Result<()> {
+    let buffer_path = path.with_extension("bin");
+    let buffer_file_name = buffer_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("blocks.bin");
+
+    const ARRAY_BUFFER: u32 = 34962;
+    const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+    const FLOAT: u32 = 5126;
+    const UNSIGNED_INT: u32 = 5125;
+    const NEAREST: u32 = 9728;
+
+    // a single buffer holding positions, normals, uvs, ambient occlusion colors and indices one after another,
+    // once for every pass. each pass is a primitive of the one mesh with its own material
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut views = Vec::new();
+    let mut accessors = Vec::new();
+    let mut primitives = Vec::new();
+    let mut materials = Vec::new();
+    for (pass, data) in passes {
+        let mut push_accessor = |bytes: Vec<u8>, target: u32, mut accessor: serde_json::Value| {
+            accessor["bufferView"] = json!(views.len());
+            views.push(json!({
+                "buffer": 0,
+                "byteOffset": buffer.len(),
+                "byteLength": bytes.len(),
+                "target": target,
+            }));
+            buffer.extend(bytes);
+            accessors.push(accessor);
+            accessors.len() - 1
+        };
+
+        // position accessors are required to have bounds
+        let (min, max) = data.positions.iter().fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), position| {
+                (
+                    min.min(Vec3::from(*position)),
+                    max.max(Vec3::from(*position)),
+                )
+            },
+        );
+        let positions = push_accessor(
+            data.positions
+                .iter()
+                .flatten()
+                .flat_map(|f| f.to_le_bytes())
+                .collect(),
+            ARRAY_BUFFER,
+            json!({
+                "componentType": FLOAT,
+                "count": data.positions.len(),
+                "type": "VEC3",
+                "min": min.to_array(),
+                "max": max.to_array(),
+            }),
+        );
+        let normals = push_accessor(
+            data.normals
+                .iter()
+                .flatten()
+                .flat_map(|f| f.to_le_bytes())
+                .collect(),
+            ARRAY_BUFFER,
+            json!({ "componentType": FLOAT, "count": data.normals.len(), "type": "VEC3" }),
+        );
+        let uvs = push_accessor(
+            data.uvs
+                .iter()
+                .flatten()
+                .flat_map(|f| f.to_le_bytes())
+                .collect(),
+            ARRAY_BUFFER,
+            json!({ "componentType": FLOAT, "count": data.uvs.len(), "type": "VEC2" }),
+        );
+        let colors = push_accessor(
+            data.colors
+                .iter()
+                .flatten()
+                .flat_map(|f| f.to_le_bytes())
+                .collect(),
+            ARRAY_BUFFER,
+            json!({ "componentType": FLOAT, "count": data.colors.len(), "type": "VEC4" }),
+        );
+        let indices = push_accessor(
+            data.indices.iter().flat_map(|i| i.to_le_bytes()).collect(),
+            ELEMENT_ARRAY_BUFFER,
+            json!({ "componentType": UNSIGNED_INT, "count": data.indices.len(), "type": "SCALAR" }),
+        );
+
+        primitives.push(json!({
+            "attributes": { "POSITION": positions, "NORMAL": normals, "TEXCOORD_0": uvs, "COLOR_0": colors },
+            "indices": indices,
+            "material": materials.len(),
+        }));
+        // the same alpha modes the client draws the passes with
+        let mut material = json!({
+            "name": material_name(*pass),
+            "pbrMetallicRoughness": {
+                "baseColorTexture": { "index": 0 },
+                "metallicFactor": 0.0,
+                "roughnessFactor": 1.0,
+            },
+        });
+        match pass {
+            MeshPass::Opaque => material["alphaMode"] = json!("OPAQUE"),
+            MeshPass::Cutout => {
+                material["alphaMode"] = json!("MASK");
+                material["alphaCutoff"] = json!(0.5);
+            }
+            MeshPass::Transparent => material["alphaMode"] = json!("BLEND"),
+        }
+        materials.push(material);
+    }
+
+    let gltf = json!({
+        "asset": { "version": "2.0", "generator": "voxels export" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "mesh": 0, "name": "world" }],
+        "meshes": [{ "primitives": primitives }],
+        "materials": materials,
+        "textures": [{ "sampler": 0, "source": 0 }],
+        "samplers": [{ "magFilter": NEAREST, "minFilter": NEAREST }],
+        "images": [{ "uri": ATLAS_FILE_NAME }],
+        "buffers": [{ "uri": buffer_file_name, "byteLength": buffer.len() }],
+        "bufferViews": views,
+        "accessors": accessors,
+    });
+
+    fs::write(&buffer_path, &buffer)?;
+    fs::write(path, serde_json::to_vec_pretty(&gltf)?)
+}
+
+fn write_atlas(output: &Path) -> io::Result<()> {
+    let directory = output.parent().unwrap_or(Path::new(""));
+    let textures = load_block_textures(Path::new(ASSETS_PATH));
+    let mut atlas = pack_block_atlas(&textures.iter().map(Option::as_ref).collect::<Vec<_>>());
+    // the png only holds the full size level, viewers build their own mips
+    atlas
+        .data
+        .truncate((atlas.width() * atlas.height() * 4) as usize);
+    atlas.texture_descriptor.mip_level_count = 1;
+    atlas
+        .try_into_dynamic()
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?
+        .save(directory.join(ATLAS_FILE_NAME))
+        .map_err(|error| io::Error::other(error.to_string()))
+}
@@ -4,7 +4,7 @@ use bevy_renet::renet::transport::{NetcodeServerTransport, ServerAuthentication,
 use bevy_renet::transport::NetcodeServerPlugin;
 use std::{net::UdpSocket, time::SystemTime};
 use voxels::app_state::state::AppState;
-use voxels::command_system::events::CommandDispatchEvent;
+use voxels::command_system::plugin::CommandServerPlugin;
 use voxels::net::{connection_config, PROTOCOL_ID};
 use voxels::player::server::plugin::PlayerServerPlugin;
 use voxels::world::server::plugin::ServerWorldPlugin;
@@ -19,7 +19,7 @@ fn main() {
     app.add_plugins(MinimalPlugins);
     app.add_plugins(InputPlugin);
     app.add_plugins(RenetServerPlugin);
-    app.add_event::<CommandDispatchEvent>();
+    app.add_plugins(CommandServerPlugin);
     app.add_plugins(NetcodeServerPlugin);
     app.insert_resource(server_transport.0);
     app.insert_resource(server_transport.1);
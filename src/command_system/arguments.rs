@@ -0,0 +1,24 @@
+use bevy::prelude::*;
+
+// helpers for parsing command arguments that are shared between commands
+
+pub fn parse_position(parts: &[&str]) -> Option<IVec3> {
+    match parts {
+        [x, y, z] => Some(IVec3::new(
+            x.parse().ok()?,
+            y.parse().ok()?,
+            z.parse().ok()?,
+        )),
+        _ => None,
+    }
+}
+
+// file names typed into commands are joined onto a directory, only allow plain names
+// so a command can not read or write outside of that directory.
+pub fn parse_file_name(name: &str) -> Option<&str> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    valid.then_some(name)
+}
@@ -0,0 +1,271 @@
+use std::{collections::HashMap, path::Path};
+
+use bevy::{math::I64Vec3, prelude::*};
+use bevy_renet::renet::ClientId;
+
+use crate::{
+    net::ClientPlayer,
+    world::{
+        chunk::{get_block, Chunk},
+        server::{
+            chunk::LoadedChunks,
+            events::{BlockEditEvent, EditSource},
+        },
+    },
+};
+
+use super::{
+    arguments::{parse_file_name, parse_position},
+    events::CommandDispatchEvent,
+    schematic::{Axis, Schematic, SCHEMATIC_DIRECTORY, SCHEMATIC_EXTENSION},
+};
+
+// copying or editing more than this many blocks at once would stall the server
+pub const MAX_SELECTION_VOLUME: i32 = 256 * 256 * 256;
+
+// The size of the region between two inclusive corners, None if it holds more than
+// MAX_SELECTION_VOLUME blocks. the corners come from commands, so the size is worked out in i64
+// where corners at opposite ends of the i32 range can not overflow.
+pub fn selection_size(min: IVec3, max: IVec3) -> Option<IVec3> {
+    let size = max.as_i64vec3() - min.as_i64vec3() + I64Vec3::ONE;
+    let volume = size.x.checked_mul(size.y)?.checked_mul(size.z)?;
+    (size.min_element() > 0 && volume <= MAX_SELECTION_VOLUME as i64).then(|| size.as_ivec3())
+}
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Selection {
+    pub first: Option<IVec3>,
+    pub second: Option<IVec3>,
+}
+
+impl Selection {
+    // inclusive minimum and maximum corner, once both positions are set
+    pub fn bounds(&self) -> Option<(IVec3, IVec3)> {
+        let (first, second) = (self.first?, self.second?);
+        Some((first.min(second), first.max(second)))
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Selections(pub HashMap<ClientId, Selection>);
+
+#[derive(Resource, Default)]
+pub struct Clipboards(pub HashMap<ClientId, Schematic>);
+
+// the block the player of the client that sent a command is standing in
+pub(crate) fn player_block_position(
+    player_query: &Query<(&Transform, &ClientPlayer)>,
+    client_id: ClientId,
+) -> Option<IVec3> {
+    player_query
+        .iter()
+        .find(|(_, player)| player.id == client_id)
+        .map(|(transform, _)| transform.translation.floor().as_ivec3())
+}
+
+// /pos1 [x y z] and /pos2 [x y z], without a position the player's position is used
+pub fn selection_command(
+    mut selections: ResMut<Selections>,
+    player_query: Query<(&Transform, &ClientPlayer)>,
+    mut command_dispatch_event_reader: EventReader<CommandDispatchEvent>,
+) {
+    for event in command_dispatch_event_reader.read() {
+        let parts: Vec<&str> = event.command.split_whitespace().collect();
+        let Some(client_id) = event.client_id else {
+            continue;
+        };
+        if parts.is_empty() || (parts[0] != "/pos1" && parts[0] != "/pos2") {
+            continue;
+        }
+        let position = match parts.len() {
+            1 => player_block_position(&player_query, client_id),
+            4 => parse_position(&parts[1..4]),
+            _ => None,
+        };
+        let Some(position) = position else {
+            warn!("usage: {} [x y z]", parts[0]);
+            continue;
+        };
+        let selection = selections.0.entry(client_id).or_default();
+        if parts[0] == "/pos1" {
+            selection.first = Some(position);
+        } else {
+            selection.second = Some(position);
+        }
+        info!("{} set {} to {}", client_id, parts[0], position);
+    }
+}
+
+// /copy, copies the selection relative to the player into the player's clipboard
+pub fn copy_command(
+    selections: Res<Selections>,
+    mut clipboards: ResMut<Clipboards>,
+    player_query: Query<(&Transform, &ClientPlayer)>,
+    loaded_chunks: Res<LoadedChunks>,
+    chunk_query: Query<&Chunk>,
+    mut command_dispatch_event_reader: EventReader<CommandDispatchEvent>,
+) {
+    for event in command_dispatch_event_reader.read() {
+        let parts: Vec<&str> = event.command.split_whitespace().collect();
+        let Some(client_id) = event.client_id else {
+            continue;
+        };
+        if parts.len() != 1 || parts[0] != "/copy" {
+            continue;
+        }
+        let Some((min, max)) = selections
+            .0
+            .get(&client_id)
+            .and_then(|selection| selection.bounds())
+        else {
+            warn!("set both /pos1 and /pos2 before copying");
+            continue;
+        };
+        let Some(origin) = player_block_position(&player_query, client_id) else {
+            continue;
+        };
+        // the clipboard stores offsets from the player, they have to fit an i32 as well
+        let offset_fits = |corner: IVec3| {
+            let offset = corner.as_i64vec3() - origin.as_i64vec3();
+            offset.abs().max_element() <= i32::MAX as i64
+        };
+        if selection_size(min, max).is_none() || !offset_fits(min) || !offset_fits(max) {
+            warn!("selection from {} to {} is too large to copy", min, max);
+            continue;
+        }
+        let chunks = loaded_chunks.in_region(min, max, &chunk_query);
+        let mut schematic = Schematic::default();
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    let position = IVec3::new(x, y, z);
+                    schematic
+                        .blocks
+                        .push((position - origin, get_block(&chunks, position)));
+                }
+            }
+        }
+        info!("copied {} blocks", schematic.blocks.len());
+        clipboards.0.insert(client_id, schematic);
+    }
+}
+
+// /rotate <degrees>, rotates the clipboard around the vertical axis in steps of 90 degrees
+pub fn rotate_command(
+    mut clipboards: ResMut<Clipboards>,
+    mut command_dispatch_event_reader: EventReader<CommandDispatchEvent>,
+) {
+    for event in command_dispatch_event_reader.read() {
+        let parts: Vec<&str> = event.command.split_whitespace().collect();
+        let Some(client_id) = event.client_id else {
+            continue;
+        };
+        if parts.len() != 2 || parts[0] != "/rotate" {
+            continue;
+        }
+        let Some(degrees) = parts[1].parse::<i32>().ok().filter(|d| d % 90 == 0) else {
+            warn!("usage: /rotate <90|180|270>");
+            continue;
+        };
+        if let Some(clipboard) = clipboards.0.get_mut(&client_id) {
+            clipboard.rotate(degrees / 90);
+        }
+    }
+}
+
+// /flip <x|y|z>, mirrors the clipboard along an axis through the copy origin
+pub fn flip_command(
+    mut clipboards: ResMut<Clipboards>,
+    mut command_dispatch_event_reader: EventReader<CommandDispatchEvent>,
+) {
+    for event in command_dispatch_event_reader.read() {
+        let parts: Vec<&str> = event.command.split_whitespace().collect();
+        let Some(client_id) = event.client_id else {
+            continue;
+        };
+        if parts.len() != 2 || parts[0] != "/flip" {
+            continue;
+        }
+        let Some(axis) = Axis::from_string(parts[1]) else {
+            warn!("usage: /flip <x|y|z>");
+            continue;
+        };
+        if let Some(clipboard) = clipboards.0.get_mut(&client_id) {
+            clipboard.mirror(axis);
+        }
+    }
+}
+
+// /paste, places the clipboard relative to the player
+pub fn paste_command(
+    clipboards: Res<Clipboards>,
+    player_query: Query<(&Transform, &ClientPlayer)>,
+    mut command_dispatch_event_reader: EventReader<CommandDispatchEvent>,
+    mut block_edit_event_writer: EventWriter<BlockEditEvent>,
+) {
+    for event in command_dispatch_event_reader.read() {
+        let parts: Vec<&str> = event.command.split_whitespace().collect();
+        let Some(client_id) = event.client_id else {
+            continue;
+        };
+        if parts.len() != 1 || parts[0] != "/paste" {
+            continue;
+        }
+        let Some(clipboard) = clipboards.0.get(&client_id) else {
+            warn!("clipboard is empty, /copy or /schematic load first");
+            continue;
+        };
+        let Some(origin) = player_block_position(&player_query, client_id) else {
+            continue;
+        };
+        block_edit_event_writer.send(BlockEditEvent {
+            changes: clipboard
+                .blocks
+                .iter()
+                .map(|(offset, element)| (origin + *offset, *element))
+                .collect(),
+            source: EditSource::from_client(event.client_id),
+        });
+    }
+}
+
+// /schematic save <name> and /schematic load <name>, stored in schematics/<name>.schem
+pub fn schematic_command(
+    mut clipboards: ResMut<Clipboards>,
+    mut command_dispatch_event_reader: EventReader<CommandDispatchEvent>,
+) {
+    for event in command_dispatch_event_reader.read() {
+        let parts: Vec<&str> = event.command.split_whitespace().collect();
+        let Some(client_id) = event.client_id else {
+            continue;
+        };
+        if parts.len() != 3 || parts[0] != "/schematic" {
+            continue;
+        }
+        let Some(name) = parse_file_name(parts[2]) else {
+            warn!("usage: /schematic <save|load> <name>");
+            continue;
+        };
+        let path = Path::new(SCHEMATIC_DIRECTORY).join(format!("{}.{}", name, SCHEMATIC_EXTENSION));
+        match parts[1] {
+            "save" => {
+                let Some(clipboard) = clipboards.0.get(&client_id) else {
+                    warn!("clipboard is empty, nothing to save");
+                    continue;
+                };
+                match clipboard.save(&path) {
+                    Ok(()) => info!("saved clipboard to {}", path.display()),
+                    Err(error) => warn!("could not save {}: {}", path.display(), error),
+                }
+            }
+            "load" => match Schematic::load(&path) {
+                Ok(schematic) => {
+                    info!("loaded {} into the clipboard", path.display());
+                    clipboards.0.insert(client_id, schematic);
+                }
+                Err(error) => warn!("could not load {}: {}", path.display(), error),
+            },
+            _ => warn!("usage: /schematic <save|load> <name>"),
+        }
+    }
+}
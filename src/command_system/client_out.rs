@@ -0,0 +1,16 @@
+use bevy::prelude::*;
+use bevy_renet::renet::RenetClient;
+
+use crate::net::{send_to_server, ClientMessage};
+
+use super::events::CommandDispatchEvent;
+
+// commands are executed by the server, the client only forwards what was typed
+pub fn send_command_to_server(
+    mut command_dispatch_event_reader: EventReader<CommandDispatchEvent>,
+    mut client: ResMut<RenetClient>,
+) {
+    for event in command_dispatch_event_reader.read() {
+        send_to_server(&mut client, ClientMessage::Command(event.command.clone()));
+    }
+}
@@ -73,6 +73,7 @@ pub fn update_command_interface(
             KeyCode::Return | KeyCode::NumpadEnter => {
                 command_dispatch_event_writer.send(CommandDispatchEvent {
                     command: string.to_string(),
+                    client_id: None,
                 });
                 next_app_state.set(AppState::Game);
                 command_history.commands.insert(1, string.to_string());
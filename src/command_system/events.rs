@@ -1,6 +1,9 @@
 use bevy::prelude::*;
+use bevy_renet::renet::ClientId;
 
 #[derive(Event)]
 pub struct CommandDispatchEvent {
     pub command: String,
+    // set on the server to the client that sent the command
+    pub client_id: Option<ClientId>,
 }
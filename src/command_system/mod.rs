@@ -1,3 +1,8 @@
+pub(crate) mod arguments;
+pub mod builder_commands;
+mod client_out;
 mod command_interface;
 pub mod events;
 pub mod plugin;
+pub mod schematic;
+mod server_in;
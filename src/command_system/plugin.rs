@@ -3,14 +3,20 @@ use bevy::{
     ecs::schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter, OnExit},
 };
 
-use crate::app_state::state::AppState;
+use crate::{app_state::state::AppState, player::client::handshake::handshake_accepted};
 
 use super::{
+    builder_commands::{
+        copy_command, flip_command, paste_command, rotate_command, schematic_command,
+        selection_command, Clipboards, Selections,
+    },
+    client_out::send_command_to_server,
     command_interface::{
         despawn_command_interface, spawn_command_interface, update_command_interface,
         CommandHistory, CommandHistoryIndex,
     },
     events::CommandDispatchEvent,
+    server_in::receive_client_commands,
 };
 
 pub struct CommandPlugin;
@@ -27,6 +33,24 @@ impl Plugin for CommandPlugin {
         .add_systems(
             Update,
             update_command_interface.run_if(in_state(AppState::Command)),
-        );
+        )
+        .add_systems(Update, send_command_to_server.run_if(handshake_accepted));
+    }
+}
+
+pub struct CommandServerPlugin;
+
+impl Plugin for CommandServerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Selections>()
+            .init_resource::<Clipboards>()
+            .add_event::<CommandDispatchEvent>()
+            .add_systems(Update, receive_client_commands)
+            .add_systems(Update, selection_command)
+            .add_systems(Update, copy_command)
+            .add_systems(Update, rotate_command)
+            .add_systems(Update, flip_command)
+            .add_systems(Update, paste_command)
+            .add_systems(Update, schematic_command);
     }
 }
@@ -0,0 +1,141 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use bevy::prelude::*;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+use crate::world::element::Element;
+
+pub const SCHEMATIC_DIRECTORY: &str = "schematics";
+pub const SCHEMATIC_EXTENSION: &str = "schem";
+
+// bumped whenever the serialized layout of Schematic changes
+const SCHEMATIC_VERSION: u32 = 1;
+const SCHEMATIC_MAGIC: &[u8; 4] = b"VXSC";
+
+#[derive(Debug)]
+pub enum SchematicError {
+    Io(io::Error),
+    Encoding(bincode::Error),
+    InvalidHeader,
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for SchematicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchematicError::Io(error) => write!(f, "io error: {}", error),
+            SchematicError::Encoding(error) => write!(f, "encoding error: {}", error),
+            SchematicError::InvalidHeader => write!(f, "not a schematic file"),
+            SchematicError::UnsupportedVersion(version) => {
+                write!(f, "unsupported schematic version {}", version)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchematicError {}
+
+impl From<io::Error> for SchematicError {
+    fn from(error: io::Error) -> Self {
+        SchematicError::Io(error)
+    }
+}
+
+impl From<bincode::Error> for SchematicError {
+    fn from(error: bincode::Error) -> Self {
+        SchematicError::Encoding(error)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    pub fn from_string(s: &str) -> Option<Axis> {
+        match s {
+            "x" => Some(Axis::X),
+            "y" => Some(Axis::Y),
+            "z" => Some(Axis::Z),
+            _ => None,
+        }
+    }
+}
+
+// A copied region of blocks.
+// offsets are relative to where the player stood when the region was copied, pasting
+// places them relative to where the player stands now. air is kept so pasting clears the area.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Schematic {
+    pub blocks: Vec<(IVec3, Element)>,
+}
+
+impl Schematic {
+    // rotates around the vertical axis through the copy origin, clockwise when looking down
+    pub fn rotate(&mut self, quarter_turns: i32) {
+        let quarter_turns = quarter_turns.rem_euclid(4);
+        for (offset, _) in self.blocks.iter_mut() {
+            for _ in 0..quarter_turns {
+                *offset = IVec3::new(-offset.z, offset.y, offset.x);
+            }
+        }
+    }
+
+    pub fn mirror(&mut self, axis: Axis) {
+        for (offset, _) in self.blocks.iter_mut() {
+            match axis {
+                Axis::X => offset.x = -offset.x,
+                Axis::Y => offset.y = -offset.y,
+                Axis::Z => offset.z = -offset.z,
+            }
+        }
+    }
+
+    pub fn write(&self, writer: impl Write) -> Result<(), SchematicError> {
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        encoder.write_all(SCHEMATIC_MAGIC)?;
+        encoder.write_all(&SCHEMATIC_VERSION.to_le_bytes())?;
+        bincode::serialize_into(&mut encoder, self)?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    pub fn read(reader: impl Read) -> Result<Self, SchematicError> {
+        let mut decoder = GzDecoder::new(reader);
+        let mut magic = [0u8; 4];
+        decoder.read_exact(&mut magic)?;
+        if &magic != SCHEMATIC_MAGIC {
+            return Err(SchematicError::InvalidHeader);
+        }
+        let mut version = [0u8; 4];
+        decoder.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != SCHEMATIC_VERSION {
+            return Err(SchematicError::UnsupportedVersion(version));
+        }
+        Ok(bincode::deserialize_from(&mut decoder)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SchematicError> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SchematicError> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+}
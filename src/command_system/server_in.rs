@@ -0,0 +1,30 @@
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+
+use crate::{
+    net::{ClientChannel, ClientMessage},
+    player::server::handshake::ConnectedPlayers,
+};
+
+use super::events::CommandDispatchEvent;
+
+///
+/// listens for commands sent by clients
+/// fires off the server side CommandDispatchEvent
+///
+pub fn receive_client_commands(
+    mut server: ResMut<RenetServer>,
+    connected_players: Res<ConnectedPlayers>,
+    mut command_dispatch_event_writer: EventWriter<CommandDispatchEvent>,
+) {
+    for client_id in connected_players.ids() {
+        while let Some(message) = server.receive_message(client_id, ClientChannel::Command) {
+            if let Ok(ClientMessage::Command(command)) = ClientMessage::decode(&message) {
+                command_dispatch_event_writer.send(CommandDispatchEvent {
+                    command,
+                    client_id: Some(client_id),
+                });
+            };
+        }
+    }
+}
@@ -1,12 +1,18 @@
 use bevy::prelude::*;
 
-use crate::player::client::events::PlayerMoveEvent;
+use crate::{
+    player::client::events::PlayerMoveEvent,
+    world::{client::visibility::ChunkVisibilityStats, wire::ChunkTransferStats},
+};
 
 #[derive(Component)]
 pub struct CoordinateDisplay {}
 
 #[derive(Component)]
-pub struct ChunkRegistryDisplay {}
+pub struct ChunkVisibilityDisplay {}
+
+#[derive(Component)]
+pub struct ChunkTransferDisplay {}
 
 pub fn get_font(asset_server: Res<AssetServer>) -> Handle<Font> {
     asset_server.load("fonts/FiraSans-Bold.ttf")
@@ -33,42 +39,89 @@ pub fn spawn_coordinate_display(mut commands: Commands, asset_server: Res<AssetS
     ));
 }
 
-// pub fn spawn_chunk_registry_display(mut commands: Commands, asset_server: Res<AssetServer>) {
-//     commands.spawn((
-//         TextBundle {
-//             style: Style {
-//                 position_type: PositionType::Absolute,
-//                 right: Val::Px(10.0),
-//                 ..default()
-//             },
-//             text: Text {
-//                 sections: vec![TextSection::new(
-//                     "chunk registry",
-//                     TextStyle {
-//                         font: get_font(asset_server),
-//                         font_size: 32.0,
-//                         color: Color::rgb(3.0 / 255.0, 252.0 / 255.0, 169.0 / 255.0),
-//                     },
-//                 )],
-//                 alignment: TextAlignment::Center,
-//                 ..default()
-//             },
-//             ..default()
-//         },
-//         ChunkRegistryDisplay {},
-//     ));
-// }
+pub fn spawn_chunk_visibility_display(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.0),
+                ..default()
+            },
+            text: Text {
+                sections: vec![TextSection::new(
+                    "chunks",
+                    TextStyle {
+                        font: get_font(asset_server),
+                        font_size: 32.0,
+                        color: Color::rgb(3.0 / 255.0, 252.0 / 255.0, 169.0 / 255.0),
+                    },
+                )],
+                alignment: TextAlignment::Center,
+                ..default()
+            },
+            ..default()
+        },
+        ChunkVisibilityDisplay {},
+    ));
+}
+
+pub fn chunk_visibility_listener(
+    stats: Res<ChunkVisibilityStats>,
+    mut chunk_visibility_display_query: Query<&mut Text, With<ChunkVisibilityDisplay>>,
+) {
+    if !stats.is_changed() {
+        return;
+    }
+    for mut text in &mut chunk_visibility_display_query {
+        text.sections[0].value = format!(
+            "chunks loaded: {} drawn: {} outside view: {} occluded: {}",
+            stats.loaded, stats.visible, stats.outside_frustum, stats.occluded
+        )
+    }
+}
+
+pub fn spawn_chunk_transfer_display(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.0),
+                top: Val::Px(40.0),
+                ..default()
+            },
+            text: Text {
+                sections: vec![TextSection::new(
+                    "chunk transfer",
+                    TextStyle {
+                        font: get_font(asset_server),
+                        font_size: 32.0,
+                        color: Color::rgb(3.0 / 255.0, 252.0 / 255.0, 169.0 / 255.0),
+                    },
+                )],
+                alignment: TextAlignment::Center,
+                ..default()
+            },
+            ..default()
+        },
+        ChunkTransferDisplay {},
+    ));
+}
 
-// pub fn chunk_created_listener(
-//     mut chunk_created_event_reader: EventReader<ChunkCreatedEvent>,
-//     mut chunk_registry_display_query: Query<&mut Text, With<ChunkRegistryDisplay>>,
-// ) {
-//     for event in chunk_created_event_reader.read() {
-//         for mut text in &mut chunk_registry_display_query {
-//             text.sections[0].value = format!("chunk registry count: {}", event.registry_size)
-//         }
-//     }
-// }
+pub fn chunk_transfer_listener(
+    stats: Res<ChunkTransferStats>,
+    mut chunk_transfer_display_query: Query<&mut Text, With<ChunkTransferDisplay>>,
+) {
+    if !stats.is_changed() {
+        return;
+    }
+    for mut text in &mut chunk_transfer_display_query {
+        text.sections[0].value = format!(
+            "chunks received: {} KB saved: {}",
+            stats.chunks,
+            stats.saved_bytes() / 1024
+        )
+    }
+}
 
 pub fn player_move_event_listener(
     mut player_move_event_reader: EventReader<PlayerMoveEvent>,
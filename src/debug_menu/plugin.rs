@@ -1,16 +1,27 @@
 use bevy::prelude::*;
 
-use super::debug_ui::{player_move_event_listener, spawn_coordinate_display};
+use crate::world::{client::visibility::ChunkVisibilityStats, wire::ChunkTransferStats};
+
+use super::debug_ui::{
+    chunk_transfer_listener, chunk_visibility_listener, player_move_event_listener,
+    spawn_chunk_transfer_display, spawn_chunk_visibility_display, spawn_coordinate_display,
+};
 
 pub struct DebugPlugin;
 
 impl Plugin for DebugPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, spawn_coordinate_display)
-            // These systems will need to be concerned with the players currently rendered chunks
-            // so instead of chunk registry it might be something like client chunk registry or rendered chunk registry.
-            // .add_systems(Startup, spawn_chunk_registry_display)
-            // .add_systems(Update, chunk_created_listener)
+            .add_systems(Startup, spawn_chunk_visibility_display)
+            .add_systems(Startup, spawn_chunk_transfer_display)
+            .add_systems(
+                Update,
+                chunk_visibility_listener.run_if(resource_exists::<ChunkVisibilityStats>()),
+            )
+            .add_systems(
+                Update,
+                chunk_transfer_listener.run_if(resource_exists::<ChunkTransferStats>()),
+            )
             .add_systems(Update, player_move_event_listener);
     }
 }
@@ -1,38 +1,70 @@
-use std::time::Duration;
+use std::{fmt, time::Duration};
 
 use bevy::prelude::*;
-use bevy_renet::renet::{ChannelConfig, ClientId, ConnectionConfig, SendType};
+use bevy_renet::renet::{
+    ChannelConfig, ClientId, ConnectionConfig, RenetClient, RenetServer, SendType,
+};
 use serde::{Deserialize, Serialize};
 
-use crate::world::chunk::Chunk;
+use crate::world::wire::ChunkPayload;
 
 pub const PRIVATE_KEY: &[u8; bevy_renet::renet::transport::NETCODE_KEY_BYTES] =
     b"an example very very secret key."; // 32-bytes
+
+// identifies the game to netcode, the version of the messages is checked by the handshake
 pub const PROTOCOL_ID: u64 = 7;
 
+// bumped whenever ClientMessage or ServerMessage (or anything they contain) changes
+pub const PROTOCOL_VERSION: u16 = 3;
+
+// every message starts with the protocol version it was encoded with, little endian
+const HEADER_SIZE: usize = 2;
+
+pub const MAX_NAME_LENGTH: usize = 16;
+
+// the server side player entity of a connected client
 #[derive(Debug, Component)]
-pub struct Player {
+pub struct ClientPlayer {
     pub id: ClientId,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct PlayerDirection(pub Vec3);
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ServerChunk(Chunk);
-
 pub enum ClientChannel {
     Input,
     Command,
+    Handshake,
 }
 pub enum ServerChannel {
     PlayerSyncLocation,
     ServerMessages,
     Chunks,
+    Handshake,
+}
+
+// everything a client sends to the server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ClientMessage {
+    // the first message of every client, nothing else is accepted before the server answers it
+    Hello {
+        version: u16,
+        name: String,
+        view_distance: i32,
+    },
+    // the direction the player moves in
+    Move(Vec3),
+    Command(String),
 }
 
-#[derive(Debug, Serialize, Deserialize, Component)]
-pub enum ServerMessages {
+// everything the server sends to a client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ServerMessage {
+    // the handshake was accepted, with the view distance the server will stream
+    Welcome {
+        view_distance: i32,
+    },
+    // the handshake was refused, the client is disconnected shortly after
+    Rejected {
+        reason: String,
+    },
     PlayerCreate {
         entity: Entity,
         id: ClientId,
@@ -41,6 +73,146 @@ pub enum ServerMessages {
     PlayerRemove {
         id: ClientId,
     },
+    PlayerPosition(Vec3),
+    Chunk(ChunkPayload),
+    // chunks that left the view distance of the client, it can forget them
+    UnloadChunks(Vec<IVec3>),
+}
+
+#[derive(Debug)]
+pub enum ProtocolError {
+    Encoding(bincode::Error),
+    MissingHeader,
+    VersionMismatch { ours: u16, theirs: u16 },
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::Encoding(error) => write!(f, "encoding error: {}", error),
+            ProtocolError::MissingHeader => write!(f, "message without a protocol header"),
+            ProtocolError::VersionMismatch { ours, theirs } => write!(
+                f,
+                "the other side speaks protocol version {}, this build speaks version {}",
+                theirs, ours
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+impl From<bincode::Error> for ProtocolError {
+    fn from(error: bincode::Error) -> Self {
+        ProtocolError::Encoding(error)
+    }
+}
+
+fn encode<T: Serialize>(message: &T) -> Result<Vec<u8>, ProtocolError> {
+    let mut bytes = PROTOCOL_VERSION.to_le_bytes().to_vec();
+    bincode::serialize_into(&mut bytes, message)?;
+    Ok(bytes)
+}
+
+fn decode<T: for<'de> Deserialize<'de>>(bytes: &[u8]) -> Result<T, ProtocolError> {
+    let (Some(header), Some(body)) = (bytes.get(..HEADER_SIZE), bytes.get(HEADER_SIZE..)) else {
+        return Err(ProtocolError::MissingHeader);
+    };
+    let version = u16::from_le_bytes([header[0], header[1]]);
+    if version != PROTOCOL_VERSION {
+        return Err(ProtocolError::VersionMismatch {
+            ours: PROTOCOL_VERSION,
+            theirs: version,
+        });
+    }
+    Ok(bincode::deserialize(body)?)
+}
+
+impl ClientMessage {
+    pub fn channel(&self) -> ClientChannel {
+        match self {
+            ClientMessage::Hello { .. } => ClientChannel::Handshake,
+            ClientMessage::Move(_) => ClientChannel::Input,
+            ClientMessage::Command(_) => ClientChannel::Command,
+        }
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, ProtocolError> {
+        encode(self)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, ProtocolError> {
+        decode(bytes)
+    }
+}
+
+impl ServerMessage {
+    pub fn channel(&self) -> ServerChannel {
+        match self {
+            ServerMessage::Welcome { .. } | ServerMessage::Rejected { .. } => {
+                ServerChannel::Handshake
+            }
+            ServerMessage::PlayerCreate { .. } | ServerMessage::PlayerRemove { .. } => {
+                ServerChannel::ServerMessages
+            }
+            ServerMessage::PlayerPosition(_) => ServerChannel::PlayerSyncLocation,
+            ServerMessage::Chunk(_) | ServerMessage::UnloadChunks(_) => ServerChannel::Chunks,
+        }
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, ProtocolError> {
+        encode(self)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, ProtocolError> {
+        decode(bytes)
+    }
+}
+
+pub fn send_to_server(client: &mut RenetClient, message: ClientMessage) {
+    match message.encode() {
+        Ok(bytes) => client.send_message(message.channel(), bytes),
+        Err(error) => warn!("could not send {:?}: {}", message, error),
+    }
+}
+
+pub fn send_to_client(server: &mut RenetServer, client_id: ClientId, message: &ServerMessage) {
+    match message.encode() {
+        Ok(bytes) => server.send_message(client_id, message.channel(), bytes),
+        Err(error) => warn!("could not send a message to {}: {}", client_id, error),
+    }
+}
+
+// the answer to a hello: the view distance the client gets, or why it is refused
+pub fn accept_hello(
+    version: u16,
+    name: &str,
+    view_distance: i32,
+    max_view_distance: i32,
+    name_taken: bool,
+) -> Result<i32, String> {
+    if version != PROTOCOL_VERSION {
+        return Err(format!(
+            "the client speaks protocol version {}, the server version {}",
+            version, PROTOCOL_VERSION
+        ));
+    }
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!(
+            "player names are 1 to {} characters long",
+            MAX_NAME_LENGTH
+        ));
+    }
+    if !name
+        .chars()
+        .all(|character| character.is_alphanumeric() || character == '_')
+    {
+        return Err("player names only contain letters, digits and _".to_string());
+    }
+    if name_taken {
+        return Err(format!("{} is already playing", name));
+    }
+    Ok(view_distance.clamp(1, max_view_distance.max(1)))
 }
 
 impl From<ClientChannel> for u8 {
@@ -48,6 +220,7 @@ impl From<ClientChannel> for u8 {
         match channel_id {
             ClientChannel::Input => 0,
             ClientChannel::Command => 1,
+            ClientChannel::Handshake => 2,
         }
     }
 }
@@ -69,6 +242,13 @@ impl ClientChannel {
                     resend_time: Duration::ZERO,
                 },
             },
+            ChannelConfig {
+                channel_id: Self::Handshake.into(),
+                max_memory_usage_bytes: 1024 * 1024,
+                send_type: SendType::ReliableOrdered {
+                    resend_time: Duration::from_millis(200),
+                },
+            },
         ]
     }
 }
@@ -79,6 +259,7 @@ impl From<ServerChannel> for u8 {
             ServerChannel::PlayerSyncLocation => 0,
             ServerChannel::ServerMessages => 1,
             ServerChannel::Chunks => 2,
+            ServerChannel::Handshake => 3,
         }
     }
 }
@@ -107,6 +288,13 @@ impl ServerChannel {
                     resend_time: Duration::ZERO,
                 },
             },
+            ChannelConfig {
+                channel_id: Self::Handshake.into(),
+                max_memory_usage_bytes: 1024 * 1024,
+                send_type: SendType::ReliableOrdered {
+                    resend_time: Duration::from_millis(200),
+                },
+            },
         ]
     }
 }
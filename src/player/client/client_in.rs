@@ -1,7 +1,10 @@
 use bevy::prelude::*;
 use bevy_renet::renet::RenetClient;
 
-use crate::{net::ServerChannel, player::lib::Player};
+use crate::{
+    net::{ServerChannel, ServerMessage},
+    player::lib::Player,
+};
 
 pub fn server_update_player(
     // might want this here, not sure.
@@ -11,8 +14,8 @@ pub fn server_update_player(
 ) {
     for mut transform in transform_query.iter_mut() {
         while let Some(server_message) = client.receive_message(ServerChannel::PlayerSyncLocation) {
-            if let Ok(server_dictate_player_position) =
-                bincode::deserialize::<Vec3>(&server_message)
+            if let Ok(ServerMessage::PlayerPosition(server_dictate_player_position)) =
+                ServerMessage::decode(&server_message)
             {
                 println!("server: {}", server_dictate_player_position);
                 println!("client: {}", transform.translation);
@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 use bevy_renet::renet::RenetClient;
 
-use crate::net::ClientChannel;
+use crate::net::{send_to_server, ClientMessage};
 
 use super::events::PlayerMoveEvent;
 
@@ -11,12 +11,7 @@ pub fn send_player_movement(
 ) {
     for event in player_move_event_reader.read() {
         let player_input = get_player_move_direction(event);
-        if let Ok(player_input_message) = bincode::serialize(&player_input) {
-            // temporarily disabled to experiment with physics
-            client.send_message(ClientChannel::Input, player_input_message);
-        } else {
-            warn!("could not serialize player_input_message");
-        }
+        send_to_server(&mut client, ClientMessage::Move(player_input));
     }
 }
 
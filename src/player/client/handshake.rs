@@ -0,0 +1,100 @@
+use bevy::{app::AppExit, prelude::*};
+use bevy_renet::renet::RenetClient;
+
+use crate::{
+    net::{send_to_server, ClientMessage, ServerChannel, ServerMessage, PROTOCOL_VERSION},
+    world::chunk::{ChunkRadius, DEFAULT_VIEW_DISTANCE},
+};
+
+// who the client says it is in its hello
+#[derive(Resource, Debug, Clone)]
+pub struct ClientProfile {
+    pub name: String,
+    pub view_distance: i32,
+}
+
+impl Default for ClientProfile {
+    fn default() -> Self {
+        Self {
+            name: "player".to_string(),
+            view_distance: DEFAULT_VIEW_DISTANCE,
+        }
+    }
+}
+
+impl ClientProfile {
+    // the server turns away a second player with the same name, players that did not pick one
+    // are told apart by their client id
+    pub fn for_client(client_id: u64) -> Self {
+        Self {
+            name: format!("player_{}", client_id % 1_000_000_000),
+            ..default()
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Default)]
+pub enum HandshakeState {
+    #[default]
+    Connecting,
+    Sent,
+    Accepted,
+    Rejected(String),
+}
+
+// run condition for systems that talk to the server, which ignores clients it has not accepted
+pub fn handshake_accepted(handshake_state: Option<Res<HandshakeState>>) -> bool {
+    handshake_state.is_some_and(|state| *state == HandshakeState::Accepted)
+}
+
+pub fn send_hello(
+    profile: Res<ClientProfile>,
+    mut client: ResMut<RenetClient>,
+    mut handshake_state: ResMut<HandshakeState>,
+) {
+    if *handshake_state != HandshakeState::Connecting || !client.is_connected() {
+        return;
+    }
+    send_to_server(
+        &mut client,
+        ClientMessage::Hello {
+            version: PROTOCOL_VERSION,
+            name: profile.name.clone(),
+            view_distance: profile.view_distance,
+        },
+    );
+    *handshake_state = HandshakeState::Sent;
+}
+
+pub fn receive_handshake_reply(
+    mut client: ResMut<RenetClient>,
+    mut handshake_state: ResMut<HandshakeState>,
+    mut chunk_radius: ResMut<ChunkRadius>,
+    mut app_exit_writer: EventWriter<AppExit>,
+) {
+    while let Some(message) = client.receive_message(ServerChannel::Handshake) {
+        let reason = match ServerMessage::decode(&message) {
+            Ok(ServerMessage::Welcome { view_distance }) => {
+                info!(
+                    "joined the server with a view distance of {}",
+                    view_distance
+                );
+                chunk_radius.radius = view_distance;
+                *handshake_state = HandshakeState::Accepted;
+                continue;
+            }
+            Ok(ServerMessage::Rejected { reason }) => reason,
+            Ok(message) => {
+                warn!("unexpected handshake message {:?}", message);
+                continue;
+            }
+            // a server with another protocol version can not be understood, not even its rejection
+            Err(error) => error.to_string(),
+        };
+        error!("the server refused the connection: {}", reason);
+        *handshake_state = HandshakeState::Rejected(reason);
+        client.disconnect();
+        app_exit_writer.send(AppExit);
+        return;
+    }
+}
@@ -2,5 +2,6 @@ mod client_in;
 mod client_out;
 mod controls;
 pub mod events;
+pub mod handshake;
 pub mod plugin;
 mod setup;
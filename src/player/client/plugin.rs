@@ -18,6 +18,9 @@ use super::{
     client_out::send_player_movement,
     controls::{client_player_move, grab_cursor, initial_grab_cursor, player_look, release_cursor},
     events::PlayerMoveEvent,
+    handshake::{
+        handshake_accepted, receive_handshake_reply, send_hello, ClientProfile, HandshakeState,
+    },
     setup::setup_client_player,
 };
 
@@ -32,6 +35,8 @@ impl Plugin for PlayerClientPlugin {
             .insert_resource(client)
             .add_event::<PlayerMoveEvent>()
             .add_event::<PlayerSpawnEvent>()
+            .init_resource::<ClientProfile>()
+            .init_resource::<HandshakeState>()
             // removed until server recieves updates from client about speed or teleportation.
             // .add_systems(Update, speed_command)
             // .add_systems(Update, teleport_command)
@@ -42,7 +47,8 @@ impl Plugin for PlayerClientPlugin {
                 (client_player_move, player_look).run_if(in_state(AppState::Game)),
             )
             .add_systems(Update, server_update_player)
-            .add_systems(Update, send_player_movement)
+            .add_systems(Update, (send_hello, receive_handshake_reply).chain())
+            .add_systems(Update, send_player_movement.run_if(handshake_accepted))
             .add_systems(OnEnter(AppState::Game), grab_cursor)
             .add_systems(OnExit(AppState::Game), release_cursor);
     }
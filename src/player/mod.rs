@@ -1,4 +1,4 @@
 pub mod client;
-pub(crate) mod events;
+pub mod events;
 pub(crate) mod lib;
 pub mod server;
@@ -1,17 +1,20 @@
 use bevy::prelude::*;
+use bevy_renet::renet::ClientId;
 
-use crate::player::{
-    client::events::PlayerMoveEvent,
-    lib::{MovementSettings, Player},
+use crate::{
+    net::ClientPlayer,
+    player::{client::events::PlayerMoveEvent, lib::MovementSettings},
 };
 
 #[derive(Event)]
 pub struct ClientSentMoveEvent {
+    pub client_id: ClientId,
     pub direction: Vec3,
 }
 
 #[derive(Event)]
 pub struct DictatePlayerPositionEvent {
+    pub client_id: ClientId,
     pub position: Vec3,
 }
 
@@ -21,14 +24,17 @@ pub struct PlayerSyncLocationTimer(pub Timer);
 pub fn client_sent_move_event_handler(
     time: Res<Time>,
     settings: Res<MovementSettings>,
-    mut transform_query: Query<&mut Transform, With<Player>>,
+    mut transform_query: Query<(&ClientPlayer, &mut Transform)>,
     mut client_sent_move_event_reader: EventReader<ClientSentMoveEvent>,
     mut player_move_event_writer: EventWriter<PlayerMoveEvent>,
     mut dictate_player_position_event_writer: EventWriter<DictatePlayerPositionEvent>,
     mut timer: ResMut<PlayerSyncLocationTimer>,
 ) {
     for event in client_sent_move_event_reader.read() {
-        for mut transform in transform_query.iter_mut() {
+        for (player, mut transform) in transform_query.iter_mut() {
+            if player.id != event.client_id {
+                continue;
+            }
             let mut player_move_event = PlayerMoveEvent {
                 starting_position: transform.translation,
                 final_position: transform.translation,
@@ -42,6 +48,7 @@ pub fn client_sent_move_event_handler(
             if timer.0.finished() {
                 println!("server position: {}", transform.translation);
                 let dictate_position = DictatePlayerPositionEvent {
+                    client_id: event.client_id,
                     position: transform.translation,
                 };
                 dictate_player_position_event_writer.send(dictate_position);
@@ -0,0 +1,151 @@
+use std::{collections::HashMap, time::Duration};
+
+use bevy::prelude::*;
+use bevy_renet::renet::{ClientId, RenetServer, ServerEvent};
+
+use crate::{
+    net::{
+        accept_hello, send_to_client, ClientChannel, ClientMessage, ClientPlayer, ServerMessage,
+    },
+    player::events::PlayerSpawnEvent,
+    world::chunk::ChunkRadius,
+};
+
+use super::setup::spawn_server_player;
+
+// how long a rejected client stays connected so the reason reaches it
+pub const REJECT_GRACE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerProfile {
+    pub name: String,
+    pub view_distance: i32,
+}
+
+// the clients that finished the handshake, only they are sent the world and listened to
+#[derive(Resource, Default)]
+pub struct ConnectedPlayers(pub HashMap<ClientId, PlayerProfile>);
+
+impl ConnectedPlayers {
+    pub fn ids(&self) -> Vec<ClientId> {
+        self.0.keys().copied().collect()
+    }
+
+    pub fn contains(&self, client_id: ClientId) -> bool {
+        self.0.contains_key(&client_id)
+    }
+}
+
+// rejected clients and the time left until they are disconnected
+#[derive(Resource, Default)]
+pub struct PendingRejections(pub HashMap<ClientId, Timer>);
+
+fn reject(
+    server: &mut RenetServer,
+    pending_rejections: &mut PendingRejections,
+    client_id: ClientId,
+    reason: String,
+) {
+    warn!("rejected client {}: {}", client_id, reason);
+    send_to_client(server, client_id, &ServerMessage::Rejected { reason });
+    pending_rejections
+        .0
+        .insert(client_id, Timer::new(REJECT_GRACE, TimerMode::Once));
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn receive_handshakes(
+    mut commands: Commands,
+    mut server: ResMut<RenetServer>,
+    mut server_events: EventReader<ServerEvent>,
+    chunk_radius: Res<ChunkRadius>,
+    mut connected_players: ResMut<ConnectedPlayers>,
+    mut pending_rejections: ResMut<PendingRejections>,
+    mut player_spawned_event_writer: EventWriter<PlayerSpawnEvent>,
+    player_query: Query<(Entity, &ClientPlayer)>,
+) {
+    for event in server_events.read() {
+        if let ServerEvent::ClientDisconnected { client_id, reason } = event {
+            if let Some(profile) = connected_players.0.remove(client_id) {
+                info!("{} left: {}", profile.name, reason);
+            }
+            pending_rejections.0.remove(client_id);
+            // a client that comes back gets a new player, and with it the whole world again
+            for (entity, player) in player_query.iter() {
+                if player.id == *client_id {
+                    commands.entity(entity).despawn();
+                }
+            }
+        }
+    }
+
+    for client_id in server.clients_id() {
+        while let Some(message) = server.receive_message(client_id, ClientChannel::Handshake) {
+            if connected_players.contains(client_id)
+                || pending_rejections.0.contains_key(&client_id)
+            {
+                continue;
+            }
+            let (version, name, view_distance) = match ClientMessage::decode(&message) {
+                Ok(ClientMessage::Hello {
+                    version,
+                    name,
+                    view_distance,
+                }) => (version, name, view_distance),
+                Ok(message) => {
+                    let reason = format!("expected a hello, got {:?}", message);
+                    reject(&mut server, &mut pending_rejections, client_id, reason);
+                    continue;
+                }
+                Err(error) => {
+                    let reason = error.to_string();
+                    reject(&mut server, &mut pending_rejections, client_id, reason);
+                    continue;
+                }
+            };
+            let name_taken = connected_players
+                .0
+                .values()
+                .any(|profile| profile.name == name);
+            match accept_hello(
+                version,
+                &name,
+                view_distance,
+                chunk_radius.radius,
+                name_taken,
+            ) {
+                Ok(view_distance) => {
+                    info!("{} joined with a view distance of {}", name, view_distance);
+                    send_to_client(
+                        &mut server,
+                        client_id,
+                        &ServerMessage::Welcome { view_distance },
+                    );
+                    connected_players.0.insert(
+                        client_id,
+                        PlayerProfile {
+                            name,
+                            view_distance,
+                        },
+                    );
+                    spawn_server_player(&mut commands, &mut player_spawned_event_writer, client_id);
+                }
+                Err(reason) => reject(&mut server, &mut pending_rejections, client_id, reason),
+            }
+        }
+    }
+}
+
+pub fn disconnect_rejected_clients(
+    time: Res<Time>,
+    mut server: ResMut<RenetServer>,
+    mut pending_rejections: ResMut<PendingRejections>,
+) {
+    pending_rejections.0.retain(|client_id, timer| {
+        if !timer.tick(time.delta()).finished() {
+            return true;
+        }
+        server.disconnect(*client_id);
+        false
+    });
+}
@@ -1,4 +1,5 @@
 mod events;
+pub mod handshake;
 pub mod plugin;
 mod server_in;
 mod server_out;
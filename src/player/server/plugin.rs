@@ -1,7 +1,8 @@
 use std::time::Duration;
 
 use bevy::{
-    app::{App, FixedUpdate, Plugin, Startup, Update},
+    app::{App, FixedUpdate, Plugin, Update},
+    ecs::schedule::IntoSystemConfigs,
     time::{Timer, TimerMode},
 };
 use bevy_renet::renet::RenetClient;
@@ -20,9 +21,11 @@ use super::{
         client_sent_move_event_handler, ClientSentMoveEvent, DictatePlayerPositionEvent,
         PlayerSyncLocationTimer,
     },
+    handshake::{
+        disconnect_rejected_clients, receive_handshakes, ConnectedPlayers, PendingRejections,
+    },
     server_in::client_move_player,
     server_out::dictate_player_position,
-    setup::setup_server_player,
 };
 
 pub struct PlayerServerPlugin;
@@ -34,6 +37,8 @@ impl Plugin for PlayerServerPlugin {
         app.init_resource::<InputState>()
             .add_event::<ClientSentMoveEvent>()
             .add_event::<DictatePlayerPositionEvent>()
+            .init_resource::<ConnectedPlayers>()
+            .init_resource::<PendingRejections>()
             .init_resource::<MovementSettings>()
             .insert_resource(PlayerSyncLocationTimer(Timer::new(
                 Duration::from_secs(3),
@@ -42,11 +47,14 @@ impl Plugin for PlayerServerPlugin {
             .insert_resource(client)
             .add_event::<PlayerSpawnEvent>()
             .add_event::<PlayerMoveEvent>()
-            .add_systems(Startup, setup_server_player)
             // something happening here with updates and fixed updates causing systems to miss events that are fired
             // this combination appears to work but it definitely means i am not understanding something
             .add_systems(FixedUpdate, client_sent_move_event_handler)
             .add_systems(FixedUpdate, client_move_player)
-            .add_systems(Update, dictate_player_position);
+            .add_systems(Update, dictate_player_position)
+            .add_systems(
+                Update,
+                (receive_handshakes, disconnect_rejected_clients).chain(),
+            );
     }
 }
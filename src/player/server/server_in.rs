@@ -1,7 +1,9 @@
 use bevy::prelude::*;
 use bevy_renet::renet::RenetServer;
 
-use crate::net::{ClientChannel, PlayerDirection};
+use crate::net::{ClientChannel, ClientMessage};
+
+use super::handshake::ConnectedPlayers;
 
 use super::events::ClientSentMoveEvent;
 
@@ -11,14 +13,16 @@ use super::events::ClientSentMoveEvent;
 ///
 pub fn client_move_player(
     mut server: ResMut<RenetServer>,
+    connected_players: Res<ConnectedPlayers>,
     mut server_event_writer: EventWriter<ClientSentMoveEvent>,
 ) {
-    for client_id in server.clients_id() {
+    for client_id in connected_players.ids() {
         while let Some(message) = server.receive_message(client_id, ClientChannel::Input) {
-            if let Ok(player_move) = bincode::deserialize::<PlayerDirection>(&message) {
+            if let Ok(ClientMessage::Move(direction)) = ClientMessage::decode(&message) {
                 //this is where the event is fired
                 server_event_writer.send(ClientSentMoveEvent {
-                    direction: player_move.0,
+                    client_id,
+                    direction,
                 });
             };
         }
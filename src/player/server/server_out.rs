@@ -1,21 +1,27 @@
 use bevy::prelude::*;
 use bevy_renet::renet::RenetServer;
 
-use crate::net::ServerChannel;
+use crate::net::{send_to_client, ServerMessage};
+
+use super::handshake::ConnectedPlayers;
 
 use super::events::DictatePlayerPositionEvent;
 
 pub fn dictate_player_position(
     mut dictate_player_position_event_reader: EventReader<DictatePlayerPositionEvent>,
     mut server: ResMut<RenetServer>,
+    connected_players: Res<ConnectedPlayers>,
 ) {
     for event in dictate_player_position_event_reader.read() {
         println!("recieved event");
-        for client_id in server.clients_id() {
-            println!("client calculated player direction: {}", event.position);
-            if let Ok(message) = bincode::serialize::<Vec3>(&event.position) {
-                server.send_message(client_id, ServerChannel::PlayerSyncLocation, message)
-            };
+        if !connected_players.contains(event.client_id) {
+            continue;
         }
+        println!("client calculated player direction: {}", event.position);
+        send_to_client(
+            &mut server,
+            event.client_id,
+            &ServerMessage::PlayerPosition(event.position),
+        );
     }
 }
@@ -1,21 +1,31 @@
 use bevy::prelude::*;
+use bevy_renet::renet::ClientId;
 
-use crate::player::{events::PlayerSpawnEvent, lib::Player};
+use crate::{
+    net::ClientPlayer,
+    player::{events::PlayerSpawnEvent, lib::Player},
+    world::server::interest::ChunkInterest,
+};
 
-pub fn setup_server_player(
-    mut commands: Commands,
-    mut player_spawned_event_writer: EventWriter<PlayerSpawnEvent>,
-) {
-    let translation = Vec3::new(0.0, 74.0, 0.0);
-    let player = commands.spawn((
-        Player {},
-        Transform {
-            translation,
-            ..Default::default()
-        },
-    ));
+pub const SPAWN_POSITION: Vec3 = Vec3::new(0.0, 74.0, 0.0);
+
+// every accepted client gets its own player, the chunks around it are streamed to that client
+pub fn spawn_server_player(
+    commands: &mut Commands,
+    player_spawned_event_writer: &mut EventWriter<PlayerSpawnEvent>,
+    client_id: ClientId,
+) -> Entity {
+    let player = commands
+        .spawn((
+            Player {},
+            ClientPlayer { id: client_id },
+            ChunkInterest::default(),
+            Transform::from_translation(SPAWN_POSITION),
+        ))
+        .id();
     player_spawned_event_writer.send(PlayerSpawnEvent {
-        position: translation,
-        entity_id: player.id(),
+        position: SPAWN_POSITION,
+        entity_id: player,
     });
+    player
 }
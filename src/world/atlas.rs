@@ -0,0 +1,321 @@
+use std::{fs, path::Path, sync::OnceLock};
+
+use bevy::{
+    prelude::*,
+    render::{
+        render_resource::{
+            Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension,
+        },
+        texture::{CompressedImageFormats, ImageSampler, ImageType},
+    },
+};
+
+use super::block::BlockTexture;
+
+// Builds the block atlas and the block texture array out of the single block textures.
+// in the atlas every texture gets a cell of the grid, in the order of BlockTexture::ALL, with a
+// gutter of its own border pixels around it. the array holds the textures as they are, one per layer.
+// textures are scaled to the size of the first one so they all fit the same cells (or layers),
+// textures that could not be loaded show up as a magenta checkerboard.
+// both come with mip levels so distant faces don't shimmer.
+
+// relative to the assets folder
+pub const BLOCK_TEXTURE_DIRECTORY: &str = "textures/blocks";
+
+// the atlas is the smallest square grid that fits every block texture
+pub const ATLAS_GRID_SIZE: u32 = atlas_grid_size(BlockTexture::ALL.len());
+
+const fn atlas_grid_size(textures: usize) -> u32 {
+    let mut size = 1;
+    while size * size < textures {
+        size += 1;
+    }
+    size as u32
+}
+
+// every texture is surrounded by a gutter of copies of its border pixels, 1/16 of the texture wide
+// on each side, so filtering and smaller mip levels never pull in the neighbouring texture
+pub const ATLAS_GUTTER_FRACTION: f32 = 1.0 / 16.0;
+
+// The uv rect of every texture in the block atlas, in the order of BlockTexture::ALL and without
+// its gutter. pack_block_atlas places the textures by these rects and meshes read them through
+// BlockTexture::atlas_rect. they only depend on the grid, not on the size of the textures, so
+// chunks meshed before the textures are loaded still line up with the final atlas.
+pub fn block_atlas_rects() -> &'static [Rect] {
+    static RECTS: OnceLock<Vec<Rect>> = OnceLock::new();
+    RECTS.get_or_init(|| {
+        let cell_size = 1.0 / ATLAS_GRID_SIZE as f32;
+        let gutter = cell_size * ATLAS_GUTTER_FRACTION / (1.0 + 2.0 * ATLAS_GUTTER_FRACTION);
+        (0..BlockTexture::ALL.len() as u32)
+            .map(|index| {
+                let cell = UVec2::new(index % ATLAS_GRID_SIZE, index / ATLAS_GRID_SIZE);
+                let min = cell.as_vec2() * cell_size;
+                Rect::from_corners(
+                    min + Vec2::splat(gutter),
+                    min + Vec2::splat(cell_size - gutter),
+                )
+            })
+            .collect()
+    })
+}
+
+struct Pixels {
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+impl Pixels {
+    fn from_image(image: &Image) -> Option<Self> {
+        let rgba = image.clone().try_into_dynamic().ok()?.to_rgba8();
+        Some(Self {
+            width: rgba.width(),
+            height: rgba.height(),
+            rgba: rgba.into_raw(),
+        })
+    }
+
+    fn missing() -> Self {
+        let mut rgba = Vec::new();
+        for y in 0..2 {
+            for x in 0..2 {
+                rgba.extend(if (x + y) % 2 == 0 {
+                    [255, 0, 255, 255]
+                } else {
+                    [0, 0, 0, 255]
+                });
+            }
+        }
+        Self {
+            width: 2,
+            height: 2,
+            rgba,
+        }
+    }
+
+    // nearest neighbour lookup of the pixel at x, y of a size by size version of the texture
+    fn sample(&self, x: u32, y: u32, size: u32) -> &[u8] {
+        let index = ((y * self.height / size) * self.width + x * self.width / size) as usize * 4;
+        &self.rgba[index..index + 4]
+    }
+}
+
+fn load_pixels(textures: &[Option<&Image>]) -> Vec<Option<Pixels>> {
+    (0..BlockTexture::ALL.len())
+        .map(|index| {
+            textures
+                .get(index)
+                .copied()
+                .flatten()
+                .and_then(Pixels::from_image)
+        })
+        .collect()
+}
+
+fn first_width(loaded: &[Option<Pixels>]) -> Option<u32> {
+    loaded.iter().flatten().map(|texture| texture.width).next()
+}
+
+// textures holds one image (or None if it is missing) for every entry of BlockTexture::ALL
+pub fn pack_block_atlas(textures: &[Option<&Image>]) -> Image {
+    let loaded = load_pixels(textures);
+
+    // the texture size is rounded up so the gutter is a whole number of pixels
+    let gutter_steps = (1.0 / ATLAS_GUTTER_FRACTION) as u32;
+    let first_width = first_width(&loaded).unwrap_or(gutter_steps);
+    let tile_size = first_width.div_ceil(gutter_steps).max(1) * gutter_steps;
+    let gutter = tile_size / gutter_steps;
+    let atlas_size = ATLAS_GRID_SIZE * (tile_size + 2 * gutter);
+    let textures: Vec<Pixels> = loaded
+        .into_iter()
+        .map(|texture| texture.unwrap_or_else(Pixels::missing))
+        .collect();
+    let tiles = AtlasTiles {
+        textures: &textures,
+        tile_size,
+        atlas_size,
+    };
+
+    let mut atlas = Image::new(
+        Extent3d {
+            width: atlas_size,
+            height: atlas_size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        tiles.level(atlas_size),
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    // a texel of the last level is at most as wide as the gutter is on both sides together,
+    // so sampling at the edge of a rect reaches at most half a texel into the gutter
+    let levels = (2 * gutter).ilog2() + 1;
+    for level in 1..levels {
+        atlas.data.extend(tiles.level(atlas_size >> level));
+    }
+    atlas.texture_descriptor.mip_level_count = levels;
+    atlas
+}
+
+struct AtlasTiles<'a> {
+    textures: &'a [Pixels],
+    tile_size: u32,
+    atlas_size: u32,
+}
+
+impl AtlasTiles<'_> {
+    // One mip level of the atlas, size texels wide. a texel belongs to the texture whose cell its
+    // center lies in and averages the full size pixels it covers, where pixels in the gutter repeat
+    // the closest border pixel of that texture. so no texel ever mixes two textures.
+    fn level(&self, size: u32) -> Vec<u8> {
+        let rects = block_atlas_rects();
+        let cells: Vec<Rect> = rects
+            .iter()
+            .map(|rect| rect.inset(rect.width() * ATLAS_GUTTER_FRACTION))
+            .collect();
+        // the full size pixels a texel covers along one axis
+        let span = |texel: u32| {
+            let start = texel * self.atlas_size / size;
+            start..((texel + 1) * self.atlas_size / size).max(start + 1)
+        };
+
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let (xs, ys) = (span(x), span(y));
+                let center = Vec2::new((xs.start + xs.end) as f32, (ys.start + ys.end) as f32)
+                    / (2 * self.atlas_size) as f32;
+                let Some(index) = cells.iter().position(|cell| cell.contains(center)) else {
+                    data.extend([0; 4]);
+                    continue;
+                };
+                let pixels = ys.flat_map(|y| xs.clone().map(move |x| (x, y)));
+                data.extend(average(
+                    pixels.map(|(x, y)| self.sample(index, rects[index], x, y)),
+                ));
+            }
+        }
+        data
+    }
+
+    // the pixel of texture index at x, y of the full size atlas, clamped into its rect
+    fn sample(&self, index: usize, rect: Rect, x: u32, y: u32) -> &[u8] {
+        let uv = (Vec2::new(x as f32, y as f32) + 0.5) / self.atlas_size as f32;
+        let local = ((uv.clamp(rect.min, rect.max) - rect.min) / rect.size()
+            * self.tile_size as f32)
+            .as_uvec2()
+            .min(UVec2::splat(self.tile_size - 1));
+        self.textures[index].sample(local.x, local.y, self.tile_size)
+    }
+}
+
+// the mean of some rgba pixels, the colour weighted by alpha so see through pixels don't darken
+// the edges of cutout textures
+fn average<'a>(pixels: impl Iterator<Item = &'a [u8]>) -> [u8; 4] {
+    let mut sum = [0u32; 4];
+    let mut weighted = [0u32; 3];
+    let mut count = 0;
+    for pixel in pixels {
+        for channel in 0..4 {
+            sum[channel] += pixel[channel] as u32;
+        }
+        for channel in 0..3 {
+            weighted[channel] += pixel[channel] as u32 * pixel[3] as u32;
+        }
+        count += 1;
+    }
+    let mut average = sum.map(|channel| (channel / count.max(1)) as u8);
+    // fully see through pixels keep their plain average
+    for (channel, weighted) in weighted.into_iter().enumerate() {
+        if let Some(colour) = weighted.checked_div(sum[3]) {
+            average[channel] = colour as u8;
+        }
+    }
+    average
+}
+
+// the same textures as pack_block_atlas, layer i of the array holds BlockTexture::ALL[i]
+pub fn pack_block_texture_array(textures: &[Option<&Image>]) -> Image {
+    let loaded = load_pixels(textures);
+    let size = first_width(&loaded).unwrap_or(16);
+    let layers = loaded.len() as u32;
+
+    // a full chain of mip levels, the layers are separate textures so nothing can bleed
+    let levels = size.ilog2() + 1;
+    let extent = Extent3d {
+        width: size,
+        height: size,
+        depth_or_array_layers: layers,
+    };
+
+    // the levels of a layer follow each other, like the gpu upload expects them
+    let mut data = Vec::with_capacity((size * size * layers * 4) as usize * 4 / 3);
+    for texture in loaded {
+        let texture = texture.unwrap_or_else(Pixels::missing);
+        let mut level = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                level.extend_from_slice(texture.sample(x, y, size));
+            }
+        }
+        data.extend_from_slice(&level);
+        for mip in 1..levels {
+            level = half_size(&level, size >> (mip - 1));
+            data.extend_from_slice(&level);
+        }
+    }
+
+    let mut array = Image::new(
+        extent,
+        TextureDimension::D2,
+        vec![0; (size * size * layers * 4) as usize],
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    // Image::new only takes the first level of every layer
+    array.data = data;
+    array.texture_descriptor.mip_level_count = levels;
+    // an array even if there is only one layer, the shader samples a texture_2d_array
+    array.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::D2Array),
+        ..default()
+    });
+    array
+}
+
+// the next mip level of a size by size image, every texel averages the (up to) 2x2 texels it covers
+fn half_size(level: &[u8], size: u32) -> Vec<u8> {
+    let half = (size / 2).max(1);
+    let mut data = Vec::with_capacity((half * half * 4) as usize);
+    for y in 0..half {
+        for x in 0..half {
+            let pixels = (2 * y..(2 * y + 2).min(size))
+                .flat_map(|y| (2 * x..(2 * x + 2).min(size)).map(move |x| (x, y)))
+                .map(|(x, y)| {
+                    let index = ((y * size + x) * 4) as usize;
+                    &level[index..index + 4]
+                });
+            data.extend(average(pixels));
+        }
+    }
+    data
+}
+
+// reads the block textures straight from disk, for tools that run without the asset server
+pub fn load_block_textures(assets: &Path) -> Vec<Option<Image>> {
+    BlockTexture::ALL
+        .iter()
+        .map(|texture| {
+            let path = assets
+                .join(BLOCK_TEXTURE_DIRECTORY)
+                .join(texture.file_name());
+            let bytes = fs::read(&path).ok()?;
+            Image::from_buffer(
+                &bytes,
+                ImageType::Extension("png"),
+                CompressedImageFormats::NONE,
+                true,
+                ImageSampler::Default,
+            )
+            .ok()
+        })
+        .collect()
+}
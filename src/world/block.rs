@@ -1,11 +1,10 @@
-use std::ops::Index;
-
-use bevy::{prelude::*, render::render_resource::PrimitiveTopology};
+use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use super::element::Element;
+use super::{atlas::block_atlas_rects, element::Element};
 
-#[derive(Clone, Copy)]
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
 pub enum BlockFace {
     North,
     South,
@@ -15,16 +14,48 @@ pub enum BlockFace {
     Bottom,
 }
 
+impl BlockFace {
+    pub const ALL: [BlockFace; 6] = [
+        BlockFace::North,
+        BlockFace::South,
+        BlockFace::East,
+        BlockFace::West,
+        BlockFace::Top,
+        BlockFace::Bottom,
+    ];
+
+    // the direction the face points in, towards the neighbour it is drawn against
+    pub fn direction(&self) -> IVec3 {
+        match self {
+            BlockFace::North => IVec3::Z,
+            BlockFace::South => IVec3::NEG_Z,
+            BlockFace::East => IVec3::X,
+            BlockFace::West => IVec3::NEG_X,
+            BlockFace::Top => IVec3::Y,
+            BlockFace::Bottom => IVec3::NEG_Y,
+        }
+    }
+
+    pub fn opposite(&self) -> BlockFace {
+        match self {
+            BlockFace::North => BlockFace::South,
+            BlockFace::South => BlockFace::North,
+            BlockFace::East => BlockFace::West,
+            BlockFace::West => BlockFace::East,
+            BlockFace::Top => BlockFace::Bottom,
+            BlockFace::Bottom => BlockFace::Top,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Component, Clone, Copy, Debug, PartialEq)]
 pub struct Block {
-    pub uv_mapping: UVMapping,
     pub element: Element,
 }
 
 impl Default for Block {
     fn default() -> Self {
         Self {
-            uv_mapping: UVMapping([0.0, 0.0]),
             element: Element::Air,
         }
     }
@@ -32,193 +63,235 @@ impl Default for Block {
 
 impl Block {
     pub fn new(element: Element) -> Self {
-        let uv_mapping = match element {
-            Element::Air => UVMapping([3.0, 6.0]),
-            Element::Stone => UVMapping([0.0, 1.0]),
-            Element::Dirt => UVMapping([0.0, 2.0]),
-            Element::Grass => UVMapping([2.0, 8.0]),
-        };
+        Self { element }
+    }
 
-        Self {
-            uv_mapping,
-            element,
+    // the textures are part of the block definition and not sent with every block
+    pub fn textures(&self) -> BlockTextures {
+        match self.element {
+            // air is never meshed
+            Element::Air | Element::Stone => BlockTextures::all(BlockTexture::Stone),
+            Element::Dirt => BlockTextures::all(BlockTexture::Dirt),
+            Element::Grass => BlockTextures::top_bottom_sides(
+                BlockTexture::GrassTop,
+                BlockTexture::Dirt,
+                BlockTexture::GrassSide,
+            ),
+            Element::Glass => BlockTextures::all(BlockTexture::Glass),
+            Element::Leaves => BlockTextures::all(BlockTexture::Leaves),
+            Element::Water => BlockTextures::all(BlockTexture::Water),
+            Element::StoneSlab | Element::StoneStairs => BlockTextures::all(BlockTexture::Stone),
+            Element::Fence => BlockTextures::all(BlockTexture::Planks),
+            Element::Flower => BlockTextures::all(BlockTexture::Flower),
+            Element::Torch => BlockTextures::all(BlockTexture::Torch),
         }
     }
-}
-
-#[derive(Serialize, Deserialize, Component, Copy, Debug, PartialEq)]
-pub struct UVMapping(pub [f32; 2]);
 
-impl Clone for UVMapping {
-    fn clone(&self) -> Self {
-        *self
+    pub fn texture(&self, side: BlockFace) -> BlockTexture {
+        self.textures().face(side)
     }
 }
 
-impl Index<usize> for UVMapping {
-    type Output = f32;
+// the texture of every face of a block
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockTextures {
+    pub north: BlockTexture,
+    pub south: BlockTexture,
+    pub east: BlockTexture,
+    pub west: BlockTexture,
+    pub top: BlockTexture,
+    pub bottom: BlockTexture,
+}
 
-    fn index(&self, index: usize) -> &Self::Output {
-        &self.0[index]
+impl BlockTextures {
+    pub fn all(texture: BlockTexture) -> Self {
+        Self::top_bottom_sides(texture, texture, texture)
     }
-}
 
-pub fn create_quad(scale: f32, side: BlockFace, offset: Vec3, uv_mapping: UVMapping) -> Mesh {
-    let result = match side {
-        BlockFace::North => create_north_quad(scale, offset, uv_mapping),
-        BlockFace::South => create_south_quad(scale, offset, uv_mapping),
-        BlockFace::West => create_west_quad(scale, offset, uv_mapping),
-        BlockFace::East => create_east_quad(scale, offset, uv_mapping),
-        BlockFace::Top => create_top_quad(scale, offset, uv_mapping),
-        BlockFace::Bottom => create_bottom_quad(scale, offset, uv_mapping),
-    };
+    pub fn top_bottom_sides(top: BlockTexture, bottom: BlockTexture, sides: BlockTexture) -> Self {
+        Self {
+            north: sides,
+            south: sides,
+            east: sides,
+            west: sides,
+            top,
+            bottom,
+        }
+    }
 
-    Mesh::new(PrimitiveTopology::TriangleList)
-        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, result.0)
-        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, result.1)
-        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, result.2)
+    pub fn face(&self, side: BlockFace) -> BlockTexture {
+        match side {
+            BlockFace::North => self.north,
+            BlockFace::South => self.south,
+            BlockFace::East => self.east,
+            BlockFace::West => self.west,
+            BlockFace::Top => self.top,
+            BlockFace::Bottom => self.bottom,
+        }
+    }
 }
 
-pub fn create_north_quad(
-    scale: f32,
-    offset: Vec3,
-    uv_mapping: UVMapping,
-) -> (Vec<Vec3>, Vec<Vec3>, Vec<Vec2>) {
-    let vertices = vec![
-        Vec3::new(0.0, 0.0, scale) + offset,
-        Vec3::new(scale, 0.0, scale) + offset,
-        Vec3::new(scale, scale, scale) + offset,
-        Vec3::new(0.0, scale, scale) + offset,
-    ];
-    let normals = vec![
-        Vec3::new(0.0, 0.0, 1.0),
-        Vec3::new(0.0, 0.0, 1.0),
-        Vec3::new(0.0, 0.0, 1.0),
-        Vec3::new(0.0, 0.0, 1.0),
-    ];
-    let uvs: Vec<Vec2> = get_texture(uv_mapping[0], uv_mapping[1]);
-    (vertices, normals, uvs)
+// One image in assets/textures/blocks for every texture a block can use. the client loads the images
+// into a texture array, the export tool packs them into the block atlas, both in the order of
+// BlockTexture::ALL, so a new texture only needs a variant here and its file.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockTexture {
+    Stone,
+    Dirt,
+    GrassTop,
+    GrassSide,
+    Glass,
+    Leaves,
+    Water,
+    Planks,
+    Flower,
+    Torch,
 }
 
-pub fn create_south_quad(
-    scale: f32,
-    offset: Vec3,
-    uv_mapping: UVMapping,
-) -> (Vec<Vec3>, Vec<Vec3>, Vec<Vec2>) {
-    let vertices = vec![
-        Vec3::new(scale, 0.0, 0.0) + offset,
-        Vec3::new(0.0, 0.0, 0.0) + offset,
-        Vec3::new(0.0, scale, 0.0) + offset,
-        Vec3::new(scale, scale, 0.0) + offset,
+impl BlockTexture {
+    pub const ALL: [BlockTexture; 10] = [
+        BlockTexture::Stone,
+        BlockTexture::Dirt,
+        BlockTexture::GrassTop,
+        BlockTexture::GrassSide,
+        BlockTexture::Glass,
+        BlockTexture::Leaves,
+        BlockTexture::Water,
+        BlockTexture::Planks,
+        BlockTexture::Flower,
+        BlockTexture::Torch,
     ];
-    let normals = vec![
-        Vec3::new(0.0, 0.0, -1.0),
-        Vec3::new(0.0, 0.0, -1.0),
-        Vec3::new(0.0, 0.0, -1.0),
-        Vec3::new(0.0, 0.0, -1.0),
-    ];
-    let uvs: Vec<Vec2> = get_texture(uv_mapping[0], uv_mapping[1]);
-    (vertices, normals, uvs)
-}
 
-pub fn create_west_quad(
-    scale: f32,
-    offset: Vec3,
-    uv_mapping: UVMapping,
-) -> (Vec<Vec3>, Vec<Vec3>, Vec<Vec2>) {
-    let vertices = vec![
-        Vec3::new(0.0, 0.0, 0.0) + offset,
-        Vec3::new(0.0, 0.0, scale) + offset,
-        Vec3::new(0.0, scale, scale) + offset,
-        Vec3::new(0.0, scale, 0.0) + offset,
-    ];
-    let normals = vec![
-        Vec3::new(-1.0, 0.0, 0.0),
-        Vec3::new(-1.0, 0.0, 0.0),
-        Vec3::new(-1.0, 0.0, 0.0),
-        Vec3::new(-1.0, 0.0, 0.0),
-    ];
-    let uvs: Vec<Vec2> = get_texture(uv_mapping[0], uv_mapping[1]);
-    (vertices, normals, uvs)
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            BlockTexture::Stone => "stone.png",
+            BlockTexture::Dirt => "dirt.png",
+            BlockTexture::GrassTop => "grass_top.png",
+            BlockTexture::GrassSide => "grass_side.png",
+            BlockTexture::Glass => "glass.png",
+            BlockTexture::Leaves => "leaves.png",
+            BlockTexture::Water => "water.png",
+            BlockTexture::Planks => "planks.png",
+            BlockTexture::Flower => "flower.png",
+            BlockTexture::Torch => "torch.png",
+        }
+    }
+
+    // the layer of the block texture array holding the texture
+    pub fn layer(&self) -> u32 {
+        *self as u32
+    }
+
+    // the uv rect pack_block_atlas puts the texture at in the block atlas
+    pub fn atlas_rect(&self) -> Rect {
+        block_atlas_rects()[*self as usize]
+    }
 }
 
-pub fn create_east_quad(
-    scale: f32,
-    offset: Vec3,
-    uv_mapping: UVMapping,
-) -> (Vec<Vec3>, Vec<Vec3>, Vec<Vec2>) {
-    let vertices = vec![
-        Vec3::new(scale, 0.0, scale) + offset,
-        Vec3::new(scale, 0.0, 0.0) + offset,
-        Vec3::new(scale, scale, 0.0) + offset,
-        Vec3::new(scale, scale, scale) + offset,
-    ];
-    let normals = vec![
-        Vec3::new(1.0, 0.0, 0.0),
-        Vec3::new(1.0, 0.0, 0.0),
-        Vec3::new(1.0, 0.0, 0.0),
-        Vec3::new(1.0, 0.0, 0.0),
-    ];
-    let uvs: Vec<Vec2> = get_texture(uv_mapping[0], uv_mapping[1]);
-    (vertices, normals, uvs)
+// the corners of one face of the block at offset, in the order the uvs of get_texture are laid out,
+// together with the normal of the face
+pub fn quad_vertices(scale: f32, side: BlockFace, offset: Vec3) -> ([Vec3; 4], Vec3) {
+    let (corners, normal) = match side {
+        BlockFace::North => (
+            [
+                [0.0, 0.0, 1.0],
+                [1.0, 0.0, 1.0],
+                [1.0, 1.0, 1.0],
+                [0.0, 1.0, 1.0],
+            ],
+            Vec3::new(0.0, 0.0, 1.0),
+        ),
+        BlockFace::South => (
+            [
+                [1.0, 0.0, 0.0],
+                [0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [1.0, 1.0, 0.0],
+            ],
+            Vec3::new(0.0, 0.0, -1.0),
+        ),
+        BlockFace::West => (
+            [
+                [0.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0],
+                [0.0, 1.0, 1.0],
+                [0.0, 1.0, 0.0],
+            ],
+            Vec3::new(-1.0, 0.0, 0.0),
+        ),
+        BlockFace::East => (
+            [
+                [1.0, 0.0, 1.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 1.0, 0.0],
+                [1.0, 1.0, 1.0],
+            ],
+            Vec3::new(1.0, 0.0, 0.0),
+        ),
+        BlockFace::Top => (
+            [
+                [0.0, 1.0, 1.0],
+                [1.0, 1.0, 1.0],
+                [1.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0],
+            ],
+            Vec3::new(0.0, 1.0, 0.0),
+        ),
+        BlockFace::Bottom => (
+            [
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 0.0, 1.0],
+                [0.0, 0.0, 1.0],
+            ],
+            Vec3::new(0.0, -1.0, 0.0),
+        ),
+    };
+    (
+        corners.map(|corner| Vec3::from(corner) * scale + offset),
+        normal,
+    )
 }
 
-pub fn create_top_quad(
-    scale: f32,
-    offset: Vec3,
-    uv_mapping: UVMapping,
-) -> (Vec<Vec3>, Vec<Vec3>, Vec<Vec2>) {
-    let vertices = vec![
-        Vec3::new(0.0, scale, scale) + offset,
-        Vec3::new(scale, scale, scale) + offset,
-        Vec3::new(scale, scale, 0.0) + offset,
-        Vec3::new(0.0, scale, 0.0) + offset,
-    ];
-    let normals = vec![
-        Vec3::new(0.0, -1.0, 0.0),
-        Vec3::new(0.0, -1.0, 0.0),
-        Vec3::new(0.0, -1.0, 0.0),
-        Vec3::new(0.0, -1.0, 0.0),
-    ];
-    let uvs: Vec<Vec2> = get_texture(uv_mapping[0], uv_mapping[1]);
-    (vertices, normals, uvs)
+// the atlas uvs of the corners of a face that shows the whole texture at rect, see
+// BlockTexture::atlas_rect
+pub fn get_texture(rect: Rect) -> [Vec2; 4] {
+    // v grows downwards in the image while the faces start at their bottom edge
+    let (top, bottom) = (rect.min.y, rect.max.y);
+    [
+        Vec2::new(rect.min.x, bottom),
+        Vec2::new(rect.max.x, bottom),
+        Vec2::new(rect.max.x, top),
+        Vec2::new(rect.min.x, top),
+    ]
 }
 
-pub fn create_bottom_quad(
-    scale: f32,
-    offset: Vec3,
-    uv_mapping: UVMapping,
-) -> (Vec<Vec3>, Vec<Vec3>, Vec<Vec2>) {
-    let vertices = vec![
-        Vec3::new(0.0, 0.0, 0.0) + offset,
-        Vec3::new(scale, 0.0, 0.0) + offset,
-        Vec3::new(scale, 0.0, scale) + offset,
-        Vec3::new(0.0, 0.0, scale) + offset,
-    ];
-    let normals = vec![
-        Vec3::new(0.0, 1.0, 0.0),
-        Vec3::new(0.0, 1.0, 0.0),
-        Vec3::new(0.0, 1.0, 0.0),
-        Vec3::new(0.0, 1.0, 0.0),
-    ];
+// Chunks are drawn with a texture array that holds every block texture in its own layer, so the
+// sampler can repeat a texture across merged faces without pulling in its neighbours. the standard
+// vertex attributes have no room for the layer, tiled uvs store it together with the position
+// inside the face as (layer * TILE_STRIDE + 1 + x, y), and the chunk shader splits them again.
+// the + 1 keeps the layer from flipping to its neighbour when interpolation lands just below a
+// whole number.
+// faces can not be larger than a chunk, so x stays well below TILE_STRIDE - 1.
+pub const TILE_STRIDE: f32 = 32.0;
 
-    let uvs: Vec<Vec2> = get_texture(uv_mapping[0], uv_mapping[1]);
-    (vertices, normals, uvs)
+// the uvs for a face that repeats the texture in layer width by height times
+pub fn get_tiled_texture(layer: u32, width: f32, height: f32) -> [Vec2; 4] {
+    let origin = Vec2::new(layer as f32 * TILE_STRIDE + 1.0, 0.0);
+    [
+        origin,
+        origin + Vec2::new(width, 0.0),
+        origin + Vec2::new(width, height),
+        origin + Vec2::new(0.0, height),
+    ]
 }
 
-// this function takes the row and column index to identify its texture location in the block atlas
-pub fn get_texture(row: f32, column: f32) -> Vec<Vec2> {
-    let grid_size = 16.0;
-    let mut uvs: Vec<Vec2> = Vec::new();
-
-    let left = column / grid_size;
-    let right = (column + 1.0) / grid_size;
-    let bottom = row / grid_size;
-    let top = (row + 1.0) / grid_size;
-    uvs.push(Vec2::new(left, bottom));
-    uvs.push(Vec2::new(right, bottom));
-    uvs.push(Vec2::new(right, top));
-    uvs.push(Vec2::new(left, top));
-
-    uvs
+// cpu side version of the decoding done in assets/shaders/chunk.wgsl, the layer and the point of
+// the texture with 0, 0 at the bottom left corner of the face. the shader flips v when sampling,
+// like get_texture does
+pub fn decode_tiled_uv(uv: Vec2) -> (u32, Vec2) {
+    let layer = ((uv.x - 1.0) / TILE_STRIDE).floor();
+    let local = Vec2::new(uv.x - layer * TILE_STRIDE - 1.0, uv.y);
+    (layer as u32, local.fract())
 }
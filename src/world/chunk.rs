@@ -1,21 +1,28 @@
 use bevy::{
-    ecs::{
-        component::Component,
-        event::{EventReader, EventWriter},
-        system::Resource,
-    },
-    math::Vec3,
+    ecs::{component::Component, system::Resource},
+    math::{IVec3, Vec3},
 };
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
-use crate::player::client::events::PlayerMoveEvent;
-
-use super::{block::Block, server::events::ChunkEnterEvent};
+use super::{block::Block, element::Element};
 
 pub const CHUNK_WIDTH: usize = 16;
 pub const CHUNK_HEIGHT: usize = 16;
 pub const CHUNK_DEPTH: usize = 16;
 pub const CHUNK_VERT: i32 = 16;
+pub const CHUNK_SIZE: IVec3 =
+    IVec3::new(CHUNK_WIDTH as i32, CHUNK_HEIGHT as i32, CHUNK_DEPTH as i32);
+
+// offsets of the six chunks that share a face with a chunk
+pub const CHUNK_NEIGHBOURS: [IVec3; 6] = [
+    IVec3::X,
+    IVec3::NEG_X,
+    IVec3::Y,
+    IVec3::NEG_Y,
+    IVec3::Z,
+    IVec3::NEG_Z,
+];
 
 #[derive(Serialize, Deserialize, Component, Clone, Copy, PartialEq, Debug)]
 pub struct Chunk {
@@ -25,34 +32,136 @@ pub struct Chunk {
     pub blocks: [[[Block; CHUNK_WIDTH]; CHUNK_HEIGHT]; CHUNK_DEPTH],
 }
 
-pub fn player_move_event_listener(
-    mut player_move_event_reader: EventReader<PlayerMoveEvent>,
-    mut enter_chunk_event_writer: EventWriter<ChunkEnterEvent>,
-) {
-    for event in player_move_event_reader.read() {
-        let starting_chunk_x = (event.starting_position.x / 16.0).floor() as i32;
-        let starting_chunk_y = (event.starting_position.y / 16.0).floor() as i32;
-        let starting_chunk_z = (event.starting_position.z / 16.0).floor() as i32;
-
-        let final_chunk_x = (event.final_position.x / 16.0).floor() as i32;
-        let final_chunk_y = (event.final_position.y / 16.0).floor() as i32;
-        let final_chunk_z = (event.final_position.z / 16.0).floor() as i32;
-
-        if starting_chunk_x != final_chunk_x
-            || starting_chunk_y != final_chunk_y
-            || starting_chunk_z != final_chunk_z
-        {
-            enter_chunk_event_writer.send(ChunkEnterEvent {
-                chunk_coords: Vec3::new(
-                    final_chunk_x as f32,
-                    final_chunk_y as f32,
-                    final_chunk_z as f32,
-                ),
-            });
+impl Chunk {
+    pub fn position(&self) -> IVec3 {
+        IVec3::new(
+            self.chunk_x as i32,
+            self.chunk_y as i32,
+            self.chunk_z as i32,
+        )
+    }
+
+    // world position of the block at local index [0][0][0]
+    pub fn world_origin(&self) -> IVec3 {
+        chunk_origin(self.position())
+    }
+}
+
+pub fn chunk_origin(chunk_position: IVec3) -> IVec3 {
+    chunk_position * CHUNK_SIZE
+}
+
+// offsets of the 26 chunks that share a face, edge or corner with a chunk
+pub fn chunk_neighbourhood() -> impl Iterator<Item = IVec3> {
+    (-1..=1)
+        .flat_map(|x| (-1..=1).flat_map(move |y| (-1..=1).map(move |z| IVec3::new(x, y, z))))
+        .filter(|offset| *offset != IVec3::ZERO)
+}
+
+// offsets of the neighbouring chunks that read the block at local position when they are meshed,
+// only blocks on the border of a chunk are seen by its neighbours
+pub fn border_neighbours(local: IVec3) -> impl Iterator<Item = IVec3> {
+    let low = local.cmpeq(IVec3::ZERO);
+    let high = local.cmpeq(CHUNK_SIZE - IVec3::ONE);
+    chunk_neighbourhood().filter(move |offset| {
+        let reaches = |axis: usize| match offset[axis] {
+            -1 => low.test(axis),
+            1 => high.test(axis),
+            _ => true,
+        };
+        reaches(0) && reaches(1) && reaches(2)
+    })
+}
+
+// the neighbours that have to be meshed again because a block they can see changed between old and new
+pub fn changed_border_neighbours(old: &Chunk, new: &Chunk) -> HashSet<IVec3> {
+    let mut neighbours = HashSet::new();
+    for x in 0..CHUNK_WIDTH {
+        for y in 0..CHUNK_HEIGHT {
+            for z in 0..CHUNK_DEPTH {
+                if old.blocks[x][y][z] != new.blocks[x][y][z] {
+                    neighbours.extend(border_neighbours(IVec3::new(x as i32, y as i32, z as i32)));
+                }
+            }
+        }
+    }
+    neighbours
+}
+
+// a chunk padded with the blocks of the chunks around it, used by the meshers so faces
+// between two chunks are culled like faces inside a chunk and ambient occlusion reaches across them.
+// neighbours that are not loaded read as air, so their border faces are still drawn.
+#[derive(Clone, Copy)]
+pub struct ChunkView<'a> {
+    pub chunk: &'a Chunk,
+    // indexed by neighbour_index, the chunk itself sits in the middle
+    neighbours: [Option<&'a Chunk>; 27],
+}
+
+fn neighbour_index(offset: IVec3) -> usize {
+    ((offset.x + 1) * 9 + (offset.y + 1) * 3 + offset.z + 1) as usize
+}
+
+impl<'a> ChunkView<'a> {
+    pub fn new(chunk: &'a Chunk, chunks: &HashMap<IVec3, &'a Chunk>) -> Self {
+        let mut view = Self::without_neighbours(chunk);
+        let position = chunk.position();
+        for offset in chunk_neighbourhood() {
+            view.neighbours[neighbour_index(offset)] = chunks.get(&(position + offset)).copied();
+        }
+        view
+    }
+
+    pub fn without_neighbours(chunk: &'a Chunk) -> Self {
+        let mut neighbours = [None; 27];
+        neighbours[neighbour_index(IVec3::ZERO)] = Some(chunk);
+        Self { chunk, neighbours }
+    }
+
+    // position is local to the chunk and may lie one block outside of it
+    pub fn element(&self, position: IVec3) -> Element {
+        let offset = IVec3::select(position.cmplt(IVec3::ZERO), IVec3::NEG_ONE, IVec3::ZERO)
+            + IVec3::select(position.cmpge(CHUNK_SIZE), IVec3::ONE, IVec3::ZERO);
+        let local = position - offset * CHUNK_SIZE;
+        if local.cmplt(IVec3::ZERO).any() || local.cmpge(CHUNK_SIZE).any() {
+            return Element::Air;
+        }
+        match self.neighbours[neighbour_index(offset)] {
+            Some(chunk) => {
+                chunk.blocks[local.x as usize][local.y as usize][local.z as usize].element
+            }
+            None => Element::Air,
         }
     }
+
+    pub fn is_opaque(&self, position: IVec3) -> bool {
+        self.element(position).is_opaque()
+    }
 }
 
+// splits a world block position into the chunk that owns it and the local index inside that chunk
+pub fn world_to_chunk_position(world_position: IVec3) -> (IVec3, [usize; 3]) {
+    let chunk_position = world_position.div_euclid(CHUNK_SIZE);
+    let local = world_position.rem_euclid(CHUNK_SIZE);
+    (
+        chunk_position,
+        [local.x as usize, local.y as usize, local.z as usize],
+    )
+}
+
+// reads a block out of a set of loaded chunks, positions outside of them read as air
+pub fn get_block(chunks: &HashMap<IVec3, &Chunk>, world_position: IVec3) -> Element {
+    let (chunk_position, [x, y, z]) = world_to_chunk_position(world_position);
+    chunks
+        .get(&chunk_position)
+        .map(|chunk| chunk.blocks[x][y][z].element)
+        .unwrap_or(Element::Air)
+}
+
+// how many chunks around its player a client sees unless it asks for less. the server does not
+// hand out more, and the level of detail rings are spread over it, see lod.rs
+pub const DEFAULT_VIEW_DISTANCE: i32 = 8;
+
 #[derive(Resource)]
 pub struct ChunkRadius {
     pub radius: i32,
@@ -63,7 +172,8 @@ pub struct ChunkQueue {
     pub chunks: Vec<Vec3>,
 }
 
-#[derive(Resource)]
+// every chunk that was queued for generation and not unloaded since
+#[derive(Resource, Default)]
 pub struct ChunkRegistry {
-    pub chunks: Vec<Vec3>,
+    pub chunks: HashSet<IVec3>,
 }
@@ -0,0 +1,100 @@
+use bevy::{
+    asset::LoadState,
+    prelude::*,
+    render::texture::{ImageAddressMode, ImageFilterMode, ImageSampler, ImageSamplerDescriptor},
+};
+
+use crate::world::{
+    atlas::{pack_block_atlas, pack_block_texture_array, BLOCK_TEXTURE_DIRECTORY},
+    block::BlockTexture,
+};
+
+use super::material::{AtlasMaterials, BlockMaterials, ChunkMaterial};
+
+// the block textures that are still being loaded, removed once the texture array is built
+#[derive(Resource)]
+pub struct BlockTextureHandles(Vec<Handle<Image>>);
+
+// The texture array and atlas handles exist from the start so chunks can be spawned right away,
+// the images behind them are replaced once the block textures are loaded.
+pub fn load_block_textures(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<ChunkMaterial>>,
+    mut standard_materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let handles = BlockTexture::ALL
+        .iter()
+        .map(|texture| {
+            asset_server.load(format!(
+                "{}/{}",
+                BLOCK_TEXTURE_DIRECTORY,
+                texture.file_name()
+            ))
+        })
+        .collect();
+    commands.insert_resource(BlockTextureHandles(handles));
+    let textures = images.add(block_texture_array(&[]));
+    commands.insert_resource(BlockMaterials::new(textures, &mut materials));
+    let atlas = images.add(block_atlas(&[]));
+    commands.insert_resource(AtlasMaterials::new(atlas, &mut standard_materials));
+}
+
+pub fn build_block_texture_array(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    block_materials: Res<BlockMaterials>,
+    atlas_materials: Res<AtlasMaterials>,
+    block_textures: Res<BlockTextureHandles>,
+) {
+    let finished = block_textures.0.iter().all(|handle| {
+        matches!(
+            asset_server.load_state(handle),
+            LoadState::Loaded | LoadState::Failed
+        )
+    });
+    if !finished {
+        return;
+    }
+    for (handle, texture) in block_textures.0.iter().zip(BlockTexture::ALL) {
+        if asset_server.load_state(handle) == LoadState::Failed {
+            warn!("could not load block texture {}", texture.file_name());
+        }
+    }
+    let textures: Vec<Option<&Image>> = block_textures
+        .0
+        .iter()
+        .map(|handle| images.get(handle))
+        .collect();
+    let array = block_texture_array(&textures);
+    let atlas = block_atlas(&textures);
+    images.insert(block_materials.textures.clone(), array);
+    images.insert(atlas_materials.atlas.clone(), atlas);
+    commands.remove_resource::<BlockTextureHandles>();
+}
+
+// blocks are pixel art, filtering would blur the texels up close. far away faces blend between
+// the mip levels instead of shimmering. merged faces repeat their texture
+fn block_texture_array(textures: &[Option<&Image>]) -> Image {
+    let mut array = pack_block_texture_array(textures);
+    array.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        mipmap_filter: ImageFilterMode::Linear,
+        ..ImageSamplerDescriptor::nearest()
+    });
+    array
+}
+
+// atlas uvs stay within their tile, the gutters keep neighbouring tiles from bleeding in, also in
+// the smaller mip levels
+fn block_atlas(textures: &[Option<&Image>]) -> Image {
+    let mut atlas = pack_block_atlas(textures);
+    atlas.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        mipmap_filter: ImageFilterMode::Linear,
+        ..ImageSamplerDescriptor::nearest()
+    });
+    atlas
+}
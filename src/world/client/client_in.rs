@@ -1,18 +1,55 @@
+use std::collections::HashSet;
+
 use bevy::prelude::*;
 use bevy_renet::renet::RenetClient;
 
-use crate::{net::ServerChannel, world::chunk::Chunk};
+use crate::{
+    net::{ServerChannel, ServerMessage},
+    world::{
+        chunk::Chunk,
+        wire::{raw_chunk_size, ChunkTransferStats},
+    },
+};
 
-use super::events::RenderChunk;
+use super::events::{RenderChunk, UnloadChunk};
 
 pub fn get_chunk_from_server(
     mut client: ResMut<RenetClient>,
     mut render_chunk_writer: EventWriter<RenderChunk>,
+    mut unload_chunk_writer: EventWriter<UnloadChunk>,
+    mut stats: ResMut<ChunkTransferStats>,
 ) {
+    // a chunk can be unloaded and sent again within one frame, only the last message about a
+    // position is passed on since the two events are read separately
+    let mut chunks: Vec<Chunk> = Vec::new();
+    let mut unloaded: HashSet<IVec3> = HashSet::new();
     while let Some(server_message) = client.receive_message(ServerChannel::Chunks) {
-        if let Ok(chunk) = bincode::deserialize::<Chunk>(&server_message) {
-            render_chunk_writer.send(RenderChunk { chunk });
-            println!("chunk recieved!");
+        let payload = match ServerMessage::decode(&server_message) {
+            Ok(ServerMessage::Chunk(payload)) => payload,
+            Ok(ServerMessage::UnloadChunks(positions)) => {
+                chunks.retain(|chunk| !positions.contains(&chunk.position()));
+                unloaded.extend(positions);
+                continue;
+            }
+            _ => continue,
+        };
+        match payload.decode() {
+            Ok(chunk) => {
+                stats.record(
+                    payload.is_uniform(),
+                    raw_chunk_size(&chunk),
+                    server_message.len() as u64,
+                );
+                unloaded.remove(&chunk.position());
+                chunks.push(chunk);
+            }
+            Err(error) => warn!("could not decode chunk {}: {}", payload.position(), error),
         }
     }
+    unload_chunk_writer.send_batch(
+        unloaded
+            .into_iter()
+            .map(|position| UnloadChunk { position }),
+    );
+    render_chunk_writer.send_batch(chunks.into_iter().map(|chunk| RenderChunk { chunk }));
 }
@@ -0,0 +1,69 @@
+use bevy::prelude::*;
+
+use super::{
+    material::BlockMaterials,
+    material::ChunkMaterial,
+    mesh_utils::MeshPass,
+    remesh::{CutoutMesh, TransparentMesh},
+};
+
+// Chunks do not pop in when their first mesh is ready, they dissolve in over FADE_DURATION. a fading
+// chunk steps through the fade materials of BlockMaterials and gets the shared materials back once
+// it is fully drawn. meshes of chunks that are already shown are replaced without a fade.
+
+pub const FADE_DURATION: f32 = 0.4;
+pub const FADE_STEPS: usize = 8;
+
+// how much of the surface the material of a step draws
+pub fn fade_amount(step: usize) -> f32 {
+    (step + 1) as f32 / (FADE_STEPS + 1) as f32
+}
+
+// the step a chunk that started fading elapsed seconds ago is at, none once it is done
+pub fn fade_step(elapsed: f32) -> Option<usize> {
+    let step = (elapsed / FADE_DURATION * FADE_STEPS as f32).max(0.0) as usize;
+    (step < FADE_STEPS).then_some(step)
+}
+
+#[derive(Component, Default)]
+pub struct ChunkFadeIn {
+    pub elapsed: f32,
+    pub step: usize,
+}
+
+pub fn fade_in_chunks(
+    mut commands: Commands,
+    time: Res<Time>,
+    block_materials: Res<BlockMaterials>,
+    mut chunk_query: Query<(
+        Entity,
+        &mut ChunkFadeIn,
+        &mut Handle<ChunkMaterial>,
+        Option<&CutoutMesh>,
+        Option<&TransparentMesh>,
+    )>,
+    mut material_query: Query<&mut Handle<ChunkMaterial>, Without<ChunkFadeIn>>,
+) {
+    for (entity, mut fade, mut material, cutout, transparent) in chunk_query.iter_mut() {
+        fade.elapsed += time.delta_seconds();
+        let step = fade_step(fade.elapsed);
+        if step == Some(fade.step) {
+            continue;
+        }
+        match step {
+            Some(step) => fade.step = step,
+            None => {
+                commands.entity(entity).remove::<ChunkFadeIn>();
+            }
+        }
+        *material = block_materials.get(MeshPass::Opaque, step).clone();
+        for (pass, child) in [
+            (MeshPass::Cutout, cutout.map(|child| child.0)),
+            (MeshPass::Transparent, transparent.map(|child| child.0)),
+        ] {
+            if let Some(mut child) = child.and_then(|child| material_query.get_mut(child).ok()) {
+                *child = block_materials.get(pass, step).clone();
+            }
+        }
+    }
+}
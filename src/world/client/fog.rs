@@ -0,0 +1,123 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_atmosphere::prelude::*;
+
+use crate::{
+    player::lib::Player,
+    world::chunk::{ChunkRadius, CHUNK_WIDTH},
+};
+
+// Distance fog that hides the edge of the loaded chunks. it ends where the view radius does and
+// takes its color from the sky at the horizon in the direction the camera looks, so terrain at the
+// edge blends into the skybox behind it at every time of day.
+
+// where the fog starts, as a part of the distance it ends at
+pub const FOG_START: f32 = 0.6;
+
+// the loaded chunks reach at least radius chunks away in every direction, the fog is fully opaque there
+pub fn fog_distances(radius: i32) -> (f32, f32) {
+    let end = (radius.max(1) * CHUNK_WIDTH as i32) as f32;
+    (end * FOG_START, end)
+}
+
+// distances along a ray to where it enters and leaves a sphere around the origin, rsi in the
+// shader. a ray that misses the sphere gets (1e5, -1e5)
+fn ray_sphere(direction: Vec3, origin: Vec3, radius: f32) -> Vec2 {
+    let a = direction.dot(direction);
+    let b = 2.0 * direction.dot(origin);
+    let c = origin.dot(origin) - radius * radius;
+    let d = b * b - 4.0 * a * c;
+    if d < 0.0 {
+        return Vec2::new(1e5, -1e5);
+    }
+    Vec2::new((-b - d.sqrt()) / (2.0 * a), (-b + d.sqrt()) / (2.0 * a))
+}
+
+// the color of the sky in direction, a copy of render_nishita in bevy_atmosphere's nishita.wgsl
+pub fn sky_color(nishita: &Nishita, direction: Vec3) -> Color {
+    const PRIMARY_STEPS: usize = 16;
+    const SECONDARY_STEPS: usize = 8;
+    let ray = direction.normalize();
+    let sun = nishita.sun_position.normalize();
+    let origin = nishita.ray_origin;
+
+    let atmosphere = ray_sphere(ray, origin, nishita.atmosphere_radius);
+    if atmosphere.x > atmosphere.y {
+        return Color::BLACK;
+    }
+    let end = atmosphere
+        .y
+        .min(ray_sphere(ray, origin, nishita.planet_radius).x);
+    let step = (end - atmosphere.x) / PRIMARY_STEPS as f32;
+
+    let mu = ray.dot(sun);
+    let g = nishita.mie_direction;
+    let phase_rayleigh = 3.0 / (16.0 * PI) * (1.0 + mu * mu);
+    let phase_mie = 3.0 / (8.0 * PI) * ((1.0 - g * g) * (mu * mu + 1.0))
+        / ((1.0 + g * g - 2.0 * mu * g).powf(1.5) * (2.0 + g * g));
+
+    let (mut total_rayleigh, mut total_mie) = (Vec3::ZERO, Vec3::ZERO);
+    let (mut depth_rayleigh, mut depth_mie) = (0.0, 0.0);
+    for i in 0..PRIMARY_STEPS {
+        let position = origin + ray * (step * (i as f32 + 0.5));
+        let height = position.length() - nishita.planet_radius;
+        let step_rayleigh = (-height / nishita.rayleigh_scale_height).exp() * step;
+        let step_mie = (-height / nishita.mie_scale_height).exp() * step;
+        depth_rayleigh += step_rayleigh;
+        depth_mie += step_mie;
+
+        // the light from the sun that reaches this point
+        let sun_step =
+            ray_sphere(sun, position, nishita.atmosphere_radius).y / SECONDARY_STEPS as f32;
+        let (mut sun_rayleigh, mut sun_mie) = (0.0, 0.0);
+        for j in 0..SECONDARY_STEPS {
+            let sample = position + sun * (sun_step * (j as f32 + 0.5));
+            let height = sample.length() - nishita.planet_radius;
+            sun_rayleigh += (-height / nishita.rayleigh_scale_height).exp() * sun_step;
+            sun_mie += (-height / nishita.mie_scale_height).exp() * sun_step;
+        }
+
+        let attenuation = (-(nishita.mie_coefficient * (depth_mie + sun_mie)
+            + nishita.rayleigh_coefficient * (depth_rayleigh + sun_rayleigh)))
+            .exp();
+        total_rayleigh += step_rayleigh * attenuation;
+        total_mie += step_mie * attenuation;
+    }
+
+    let color = nishita.sun_intensity
+        * (phase_rayleigh * nishita.rayleigh_coefficient * total_rayleigh
+            + phase_mie * nishita.mie_coefficient * total_mie);
+    Color::rgb_linear(color.x, color.y, color.z)
+}
+
+// the sky just above the horizon where the camera looks, straight up or down looks north
+pub fn horizon_color(nishita: &Nishita, forward: Vec3) -> Color {
+    let horizontal = Vec3::new(forward.x, 0.0, forward.z)
+        .try_normalize()
+        .unwrap_or(Vec3::NEG_Z);
+    sky_color(nishita, horizontal + Vec3::new(0.0, 0.05, 0.0))
+}
+
+pub fn update_fog(
+    mut commands: Commands,
+    atmosphere: Atmosphere<Nishita>,
+    chunk_radius: Res<ChunkRadius>,
+    mut camera_query: Query<(Entity, &Transform, Option<&mut FogSettings>), With<Player>>,
+) {
+    let Ok((entity, transform, fog)) = camera_query.get_single_mut() else {
+        return;
+    };
+    let (start, end) = fog_distances(chunk_radius.radius);
+    let color = horizon_color(&atmosphere, transform.forward());
+    let Some(mut fog) = fog else {
+        commands.entity(entity).insert(FogSettings {
+            color,
+            falloff: FogFalloff::Linear { start, end },
+            ..default()
+        });
+        return;
+    };
+    fog.color = color;
+    fog.falloff = FogFalloff::Linear { start, end };
+}
@@ -0,0 +1,261 @@
+use bevy::math::{IVec3, Vec2, Vec3};
+use bevy::render::mesh::Mesh;
+
+use crate::world::block::{BlockFace, BlockTexture};
+use crate::world::chunk::{ChunkView, CHUNK_SIZE};
+use crate::world::element::Element;
+
+use super::mesh_utils::{face_ao, ChunkMeshBuilder, MeshPass, QuadTexture};
+use super::packed::ChunkVertexFormat;
+
+// Greedy meshing merges neighbouring faces that point the same way and use the same texture
+// into a single quad, a flat grass plane becomes one quad per chunk instead of 256.
+// the merged quads use tiled uvs (see get_tiled_texture) so they have to be drawn with the chunk material.
+// which faces are visible is decided exactly like add_chunk does, blocks with a model are added unmerged.
+// faces only merge when their ambient occlusion is the same and does not change along the direction
+// they are merged in, so the interpolated vertex colors of a merged quad match the single faces.
+
+// how a face is laid out: the direction it faces and the axes the texture's u and v run along,
+// negative axes are walked backwards. this matches the vertex order of quad_vertices.
+struct FaceLayout {
+    side: BlockFace,
+    normal: IVec3,
+    u: IVec3,
+    v: IVec3,
+}
+
+const FACE_LAYOUTS: [FaceLayout; 6] = [
+    FaceLayout {
+        side: BlockFace::North,
+        normal: IVec3::Z,
+        u: IVec3::X,
+        v: IVec3::Y,
+    },
+    FaceLayout {
+        side: BlockFace::South,
+        normal: IVec3::NEG_Z,
+        u: IVec3::NEG_X,
+        v: IVec3::Y,
+    },
+    FaceLayout {
+        side: BlockFace::East,
+        normal: IVec3::X,
+        u: IVec3::NEG_Z,
+        v: IVec3::Y,
+    },
+    FaceLayout {
+        side: BlockFace::West,
+        normal: IVec3::NEG_X,
+        u: IVec3::Z,
+        v: IVec3::Y,
+    },
+    FaceLayout {
+        side: BlockFace::Top,
+        normal: IVec3::Y,
+        u: IVec3::X,
+        v: IVec3::NEG_Z,
+    },
+    FaceLayout {
+        side: BlockFace::Bottom,
+        normal: IVec3::NEG_Y,
+        u: IVec3::X,
+        v: IVec3::Z,
+    },
+];
+
+fn axis_index(axis: IVec3) -> usize {
+    let axis = axis.abs();
+    if axis.x != 0 {
+        0
+    } else if axis.y != 0 {
+        1
+    } else {
+        2
+    }
+}
+
+// the corners of a single block face relative to the block, in vertex order
+fn layout_corners(layout: &FaceLayout) -> [IVec3; 4] {
+    // every axis the face or its texture runs backwards along starts on the far side of the block
+    let start = IVec3::select(
+        layout.normal.cmpgt(IVec3::ZERO)
+            | layout.u.cmplt(IVec3::ZERO)
+            | layout.v.cmplt(IVec3::ZERO),
+        IVec3::ONE,
+        IVec3::ZERO,
+    );
+    [
+        start,
+        start + layout.u,
+        start + layout.u + layout.v,
+        start + layout.v,
+    ]
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Face {
+    element: Element,
+    texture: BlockTexture,
+    ao: [u8; 4],
+}
+
+impl Face {
+    // the occlusion does not change along u (or v), so faces next to each other in that direction
+    // can share vertices
+    fn flat_along_u(&self) -> bool {
+        self.ao[0] == self.ao[1] && self.ao[3] == self.ao[2]
+    }
+
+    fn flat_along_v(&self) -> bool {
+        self.ao[0] == self.ao[3] && self.ao[1] == self.ao[2]
+    }
+}
+
+// the face of the block at position, if that face is visible and belongs to pass
+fn visible_face(
+    view: &ChunkView,
+    pass: MeshPass,
+    position: IVec3,
+    layout: &FaceLayout,
+    corners: [IVec3; 4],
+) -> Option<Face> {
+    let normal = layout.normal;
+    let block = view.chunk.blocks[position.x as usize][position.y as usize][position.z as usize];
+    if MeshPass::of(block.element) != Some(pass)
+        || block.element.model().is_some()
+        || !block
+            .element
+            .shows_face_against(view.element(position + normal), layout.side)
+    {
+        return None;
+    }
+    Some(Face {
+        element: block.element,
+        texture: block.texture(layout.side),
+        ao: face_ao(view, position, normal, corners),
+    })
+}
+
+pub fn gen_greedy_mesh(scale: f32, view: &ChunkView, pass: MeshPass) -> Mesh {
+    let mut builder = ChunkMeshBuilder::new(ChunkVertexFormat::Standard);
+    builder.add_greedy_chunk(scale, view, pass);
+    builder.build()
+}
+
+impl ChunkMeshBuilder {
+    pub fn add_greedy_chunk(&mut self, scale: f32, view: &ChunkView, pass: MeshPass) {
+        let chunk_origin = view.chunk.world_origin().as_vec3();
+        self.origin = chunk_origin;
+        let mut mask: Vec<Option<Face>> = Vec::new();
+
+        for layout in FACE_LAYOUTS.iter() {
+            let normal_axis = axis_index(layout.normal);
+            let u_axis = axis_index(layout.u);
+            let v_axis = axis_index(layout.v);
+            let u_len = CHUNK_SIZE[u_axis];
+            let v_len = CHUNK_SIZE[v_axis];
+            let corners = layout_corners(layout);
+
+            // walks the mask in u and v from the corner the texture starts at
+            let start = |axis: IVec3, len: i32| axis.min(IVec3::ZERO).abs() * (len - 1);
+            let mask_origin = start(layout.u, u_len) + start(layout.v, v_len);
+            let block_at = |slice: i32, u: i32, v: i32| {
+                mask_origin + layout.normal.abs() * slice + layout.u * u + layout.v * v
+            };
+
+            for slice in 0..CHUNK_SIZE[normal_axis] {
+                mask.clear();
+                for v in 0..v_len {
+                    for u in 0..u_len {
+                        mask.push(visible_face(
+                            view,
+                            pass,
+                            block_at(slice, u, v),
+                            layout,
+                            corners,
+                        ));
+                    }
+                }
+
+                for v in 0..v_len {
+                    let mut u = 0;
+                    while u < u_len {
+                        let Some(face) = mask[(v * u_len + u) as usize] else {
+                            u += 1;
+                            continue;
+                        };
+                        let mut width = 1;
+                        while face.flat_along_u()
+                            && u + width < u_len
+                            && mask[(v * u_len + u + width) as usize] == Some(face)
+                        {
+                            width += 1;
+                        }
+                        let mut height = 1;
+                        'grow: while face.flat_along_v() && v + height < v_len {
+                            for du in 0..width {
+                                if mask[((v + height) * u_len + u + du) as usize] != Some(face) {
+                                    break 'grow;
+                                }
+                            }
+                            height += 1;
+                        }
+                        for dv in 0..height {
+                            for du in 0..width {
+                                mask[((v + dv) * u_len + u + du) as usize] = None;
+                            }
+                        }
+
+                        self.push_greedy_quad(
+                            layout,
+                            chunk_origin + block_at(slice, u, v).as_vec3(),
+                            Vec2::new(width as f32, height as f32),
+                            scale,
+                            face,
+                        );
+                        u += width;
+                    }
+                }
+            }
+        }
+
+        // models are not merged, every one of them repeats its textures once
+        self.add_models(scale, view, pass, true);
+    }
+
+    // corner is the position of the block the texture starts in
+    fn push_greedy_quad(
+        &mut self,
+        layout: &FaceLayout,
+        corner: Vec3,
+        size: Vec2,
+        scale: f32,
+        face: Face,
+    ) {
+        let u = layout.u.as_vec3();
+        let v = layout.v.as_vec3();
+        let normal = layout.normal.as_vec3();
+        let origin = corner + layout_corners(layout)[0].as_vec3() * scale;
+        self.push_quad(
+            [
+                origin,
+                origin + u * size.x * scale,
+                origin + (u * size.x + v * size.y) * scale,
+                origin + v * size.y * scale,
+            ],
+            normal,
+            QuadTexture {
+                texture: face.texture,
+                corners: [
+                    Vec2::ZERO,
+                    Vec2::new(size.x, 0.0),
+                    size,
+                    Vec2::new(0.0, size.y),
+                ],
+                tiled: true,
+            },
+            face.ao,
+            face.element,
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use bevy::prelude::*;
+
+use crate::{
+    player::lib::Player,
+    world::{
+        block::Block,
+        chunk::{
+            chunk_neighbourhood, Chunk, ChunkRadius, CHUNK_DEPTH, CHUNK_HEIGHT, CHUNK_SIZE,
+            CHUNK_WIDTH, DEFAULT_VIEW_DISTANCE,
+        },
+        element::Element,
+    },
+};
+
+use super::remesh::DirtyChunks;
+
+// Level of detail for distant chunks.
+// a chunk at level n is meshed from a copy where every 2^n blocks wide cube is filled with a single
+// element, so the greedy mesher can merge a lot more faces. chunks only cull their border faces
+// against neighbours of the same level, the faces towards a neighbour of another level are always
+// drawn and work as skirts that close the gaps between the two different surfaces.
+
+pub const MAX_LOD_LEVEL: u32 = 3;
+
+// chunks this close to the player are always drawn at full detail
+pub const FULL_DETAIL_RADIUS: i32 = 4;
+
+// how far (in chunks, measured from the chunk column the player is in) each level reaches,
+// chunks beyond the last ring are drawn at MAX_LOD_LEVEL
+#[derive(Resource, Debug, PartialEq, Eq)]
+pub struct LodSettings {
+    pub ring_radii: [i32; MAX_LOD_LEVEL as usize],
+}
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        Self::for_view_distance(DEFAULT_VIEW_DISTANCE)
+    }
+}
+
+impl LodSettings {
+    // the inner half of the view is drawn at full detail and the coarser levels share the rest,
+    // a short view distance is all full detail
+    pub fn for_view_distance(view_distance: i32) -> Self {
+        let full_detail = (view_distance / 2).max(FULL_DETAIL_RADIUS);
+        let rest = (view_distance - full_detail).max(0);
+        Self {
+            ring_radii: std::array::from_fn(|level| {
+                full_detail + rest * level as i32 / MAX_LOD_LEVEL as i32
+            }),
+        }
+    }
+
+    pub fn level(&self, camera_column: IVec2, chunk_position: IVec3) -> u32 {
+        let distance_squared = (chunk_position.xz() - camera_column).length_squared();
+        self.ring_radii
+            .iter()
+            .position(|radius| distance_squared <= radius.pow(2))
+            .unwrap_or(MAX_LOD_LEVEL as usize) as u32
+    }
+}
+
+// the level a chunk entity was last meshed at
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ChunkLod(pub u32);
+
+pub fn camera_column(translation: Vec3) -> IVec2 {
+    (translation / CHUNK_SIZE.as_vec3()).floor().as_ivec3().xz()
+}
+
+// every cube of 2^level blocks becomes its most common element, or air if less than half of it is filled
+pub fn downsample_chunk(chunk: &Chunk, level: u32) -> Chunk {
+    let step = 1 << level.min(MAX_LOD_LEVEL);
+    if step == 1 {
+        return *chunk;
+    }
+    let mut downsampled = *chunk;
+    let mut counts: Vec<(Element, usize)> = Vec::new();
+    for x in (0..CHUNK_WIDTH).step_by(step) {
+        for y in (0..CHUNK_HEIGHT).step_by(step) {
+            for z in (0..CHUNK_DEPTH).step_by(step) {
+                counts.clear();
+                let mut filled = 0;
+                for block in cube(x, y, z, step).map(|[x, y, z]| chunk.blocks[x][y][z]) {
+                    if block.element == Element::Air {
+                        continue;
+                    }
+                    filled += 1;
+                    match counts
+                        .iter_mut()
+                        .find(|(element, _)| *element == block.element)
+                    {
+                        Some((_, count)) => *count += 1,
+                        None => counts.push((block.element, 1)),
+                    }
+                }
+                let element = match counts.iter().max_by_key(|(_, count)| *count) {
+                    Some((element, _)) if filled * 2 >= step.pow(3) => *element,
+                    _ => Element::Air,
+                };
+                for [x, y, z] in cube(x, y, z, step) {
+                    downsampled.blocks[x][y][z] = Block::new(element);
+                }
+            }
+        }
+    }
+    downsampled
+}
+
+fn cube(x: usize, y: usize, z: usize, step: usize) -> impl Iterator<Item = [usize; 3]> {
+    (x..x + step)
+        .flat_map(move |x| (y..y + step).flat_map(move |y| (z..z + step).map(move |z| [x, y, z])))
+}
+
+// the server tells the client its view distance in the handshake, the rings follow it
+pub fn follow_view_distance(chunk_radius: Res<ChunkRadius>, mut lod_settings: ResMut<LodSettings>) {
+    if chunk_radius.is_changed() {
+        lod_settings.set_if_neq(LodSettings::for_view_distance(chunk_radius.radius));
+    }
+}
+
+// when the player moves into another chunk column the chunks whose level changed are meshed again,
+// together with their neighbours whose seams towards them change
+pub fn update_chunk_lods(
+    lod_settings: Res<LodSettings>,
+    player_query: Query<&Transform, With<Player>>,
+    chunk_query: Query<(&Chunk, &ChunkLod)>,
+    mut dirty_chunks: ResMut<DirtyChunks>,
+    mut last_column: Local<Option<IVec2>>,
+) {
+    let Ok(transform) = player_query.get_single() else {
+        return;
+    };
+    let column = camera_column(transform.translation);
+    if *last_column == Some(column) && !lod_settings.is_changed() {
+        return;
+    }
+    *last_column = Some(column);
+    for (chunk, lod) in chunk_query.iter() {
+        let position = chunk.position();
+        if lod_settings.level(column, position) != lod.0 {
+            dirty_chunks.0.insert(position);
+            dirty_chunks
+                .0
+                .extend(chunk_neighbourhood().map(|offset| position + offset));
+        }
+    }
+}
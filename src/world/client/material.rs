@@ -0,0 +1,209 @@
+use bevy::{
+    pbr::{
+        ExtendedMaterial, MaterialExtension, MaterialExtensionKey, MaterialExtensionPipeline,
+        MESH_SHADER_HANDLE, PREPASS_SHADER_HANDLE,
+    },
+    prelude::*,
+    render::{
+        mesh::MeshVertexBufferLayout,
+        render_resource::{
+            AsBindGroup, RenderPipelineDescriptor, ShaderDefVal, ShaderRef,
+            SpecializedMeshPipelineError,
+        },
+    },
+};
+
+use crate::world::{block::TILE_STRIDE, element::TINTS};
+
+use super::{
+    fade::{fade_amount, FADE_STEPS},
+    mesh_utils::{MeshPass, AO_BRIGHTNESS},
+    packed::ATTRIBUTE_PACKED_VERTEX,
+};
+
+// the standard material with the fragment shader swapped for one that samples the block texture
+// array with tiled uvs, and the vertex shader for one that unpacks packed chunk vertices
+pub type ChunkMaterial = ExtendedMaterial<StandardMaterial, ChunkTextures>;
+
+// The materials every chunk is drawn with, created once so all chunks share them and bevy can
+// batch their draws. they all sample the block texture array, switching textures only means
+// replacing the image behind textures.
+#[derive(Resource)]
+pub struct BlockMaterials {
+    pub textures: Handle<Image>,
+    pub opaque: Handle<ChunkMaterial>,
+    // for blocks with fully see through texels, alpha below one half is discarded
+    pub cutout: Handle<ChunkMaterial>,
+    pub translucent: Handle<ChunkMaterial>,
+    // copies of opaque, cutout and translucent for every step of the fade in of new chunks, only
+    // chunks that are fading in break the batch
+    pub opaque_fade: Vec<Handle<ChunkMaterial>>,
+    pub cutout_fade: Vec<Handle<ChunkMaterial>>,
+    pub translucent_fade: Vec<Handle<ChunkMaterial>>,
+}
+
+impl BlockMaterials {
+    pub fn new(textures: Handle<Image>, materials: &mut Assets<ChunkMaterial>) -> Self {
+        let mut material = |alpha_mode, fade| {
+            materials.add(ChunkMaterial {
+                base: StandardMaterial {
+                    alpha_mode,
+                    unlit: false,
+                    ..default()
+                },
+                extension: ChunkTextures {
+                    fade,
+                    ..ChunkTextures::new(textures.clone())
+                },
+            })
+        };
+        Self {
+            opaque: material(AlphaMode::Opaque, 1.0),
+            cutout: material(AlphaMode::Mask(0.5), 1.0),
+            translucent: material(AlphaMode::Blend, 1.0),
+            opaque_fade: (0..FADE_STEPS)
+                .map(|step| material(AlphaMode::Opaque, fade_amount(step)))
+                .collect(),
+            cutout_fade: (0..FADE_STEPS)
+                .map(|step| material(AlphaMode::Mask(0.5), fade_amount(step)))
+                .collect(),
+            translucent_fade: (0..FADE_STEPS)
+                .map(|step| material(AlphaMode::Blend, fade_amount(step)))
+                .collect(),
+            textures,
+        }
+    }
+
+    // the material of a mesh pass, or its copy for a step of the fade in
+    pub fn get(&self, pass: MeshPass, fade_step: Option<usize>) -> &Handle<ChunkMaterial> {
+        match (pass, fade_step) {
+            (MeshPass::Opaque, None) => &self.opaque,
+            (MeshPass::Opaque, Some(step)) => &self.opaque_fade[step],
+            (MeshPass::Cutout, None) => &self.cutout,
+            (MeshPass::Cutout, Some(step)) => &self.cutout_fade[step],
+            (MeshPass::Transparent, None) => &self.translucent,
+            (MeshPass::Transparent, Some(step)) => &self.translucent_fade[step],
+        }
+    }
+}
+
+// What standard meshes are drawn with: the plain standard material with the block atlas as its
+// texture, the vertex colors carry tint and ambient occlusion. the atlas is packed once the block
+// textures are loaded, like the texture array.
+#[derive(Resource)]
+pub struct AtlasMaterials {
+    pub atlas: Handle<Image>,
+    pub opaque: Handle<StandardMaterial>,
+    pub cutout: Handle<StandardMaterial>,
+    pub translucent: Handle<StandardMaterial>,
+}
+
+impl AtlasMaterials {
+    pub fn new(atlas: Handle<Image>, materials: &mut Assets<StandardMaterial>) -> Self {
+        let mut material = |alpha_mode| {
+            materials.add(StandardMaterial {
+                base_color_texture: Some(atlas.clone()),
+                alpha_mode,
+                ..default()
+            })
+        };
+        Self {
+            opaque: material(AlphaMode::Opaque),
+            cutout: material(AlphaMode::Mask(0.5)),
+            translucent: material(AlphaMode::Blend),
+            atlas,
+        }
+    }
+
+    pub fn get(&self, pass: MeshPass) -> &Handle<StandardMaterial> {
+        match pass {
+            MeshPass::Opaque => &self.opaque,
+            MeshPass::Cutout => &self.cutout,
+            MeshPass::Transparent => &self.translucent,
+        }
+    }
+}
+
+// the standard material can only sample plain 2d textures, the chunk shader samples the block
+// texture array itself and multiplies it into the base color
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone)]
+pub struct ChunkTextures {
+    // bindings 0-99 belong to the base material
+    #[uniform(100)]
+    pub tile_stride: f32,
+    // the palettes packed vertices index into
+    #[uniform(100)]
+    pub tints: [Vec4; 4],
+    #[uniform(100)]
+    pub ao_brightness: Vec4,
+    // the part of the surface that is drawn, below one the rest is dithered away
+    #[uniform(100)]
+    pub fade: f32,
+    #[texture(101, dimension = "2d_array")]
+    #[sampler(102)]
+    pub block_textures: Handle<Image>,
+}
+
+impl ChunkTextures {
+    pub fn new(block_textures: Handle<Image>) -> Self {
+        Self {
+            tile_stride: TILE_STRIDE,
+            tints: TINTS.map(Vec4::from),
+            ao_brightness: Vec4::from(AO_BRIGHTNESS),
+            fade: 1.0,
+            block_textures,
+        }
+    }
+}
+
+impl MaterialExtension for ChunkTextures {
+    fn vertex_shader() -> ShaderRef {
+        "shaders/chunk_vertex.wgsl".into()
+    }
+
+    fn prepass_vertex_shader() -> ShaderRef {
+        "shaders/chunk_vertex.wgsl".into()
+    }
+
+    fn deferred_vertex_shader() -> ShaderRef {
+        "shaders/chunk_vertex.wgsl".into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        "shaders/chunk.wgsl".into()
+    }
+
+    fn deferred_fragment_shader() -> ShaderRef {
+        "shaders/chunk.wgsl".into()
+    }
+
+    // meshes with the standard attributes keep the standard vertex shaders, packed meshes read
+    // their single attribute and produce uvs and colors for the fragment shader
+    fn specialize(
+        _pipeline: &MaterialExtensionPipeline,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayout,
+        _key: MaterialExtensionKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        if !layout.contains(ATTRIBUTE_PACKED_VERTEX) {
+            let prepass = descriptor
+                .vertex
+                .shader_defs
+                .contains(&"PREPASS_PIPELINE".into());
+            descriptor.vertex.shader = if prepass {
+                PREPASS_SHADER_HANDLE
+            } else {
+                MESH_SHADER_HANDLE
+            };
+            return Ok(());
+        }
+        descriptor.vertex.buffers =
+            vec![layout.get_layout(&[ATTRIBUTE_PACKED_VERTEX.at_shader_location(0)])?];
+        let shader_defs: [ShaderDefVal; 2] = ["VERTEX_UVS".into(), "VERTEX_COLORS".into()];
+        descriptor.vertex.shader_defs.extend(shader_defs.clone());
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            fragment.shader_defs.extend(shader_defs);
+        }
+        Ok(())
+    }
+}
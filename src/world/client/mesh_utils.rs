@@ -1,152 +1,513 @@
-use bevy::math::{Vec2, Vec3};
-use bevy::render::mesh::Indices;
-use bevy::render::mesh::{Mesh, VertexAttributeValues};
+use bevy::math::{IVec3, Vec2, Vec3};
+use bevy::render::mesh::{Indices, Mesh};
 use bevy::render::render_resource::PrimitiveTopology;
-use std::hash::Hash;
-use std::hash::Hasher;
 
-use crate::world::block::{create_quad, BlockFace};
-use crate::world::chunk::{Chunk, CHUNK_DEPTH, CHUNK_HEIGHT, CHUNK_WIDTH};
+use crate::world::block::{
+    get_texture, get_tiled_texture, quad_vertices, Block, BlockFace, BlockTexture,
+};
+use crate::world::chunk::{ChunkView, CHUNK_DEPTH, CHUNK_HEIGHT, CHUNK_WIDTH};
 use crate::world::element::Element;
+use crate::world::model::ModelPart;
 
-#[derive(Clone)]
-struct VertexData {
-    position: Vec3,
-    normal: Vec3,
-    uv: Vec2,
+use super::packed::{ChunkVertexFormat, PackedVertex, ATTRIBUTE_PACKED_VERTEX};
+
+// Collects the faces of one or more chunks straight into shared vertex buffers.
+// build hands the buffers out as a Mesh and clears them, keeping their allocations,
+// so a builder that is kept around meshes every following chunk without growing its buffers again.
+// the vertices are either written with the standard attributes or packed, see packed.rs.
+pub struct ChunkMeshBuilder {
+    format: ChunkVertexFormat,
+    // packed positions are relative to the origin of the chunk that is added
+    pub(super) origin: Vec3,
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    colors: Vec<[f32; 4]>,
+    packed: Vec<[u32; 2]>,
+    indices: Vec<u32>,
+}
+
+// What a quad shows: its texture and, for every corner, where it lies on the texture counted in
+// texture repeats. atlas uvs can only show a single repeat, the chunks drawn by the client use
+// tiled uvs into the block texture array instead (see get_tiled_texture).
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct QuadTexture {
+    pub texture: BlockTexture,
+    pub corners: [Vec2; 4],
+    pub tiled: bool,
 }
 
-impl PartialEq for VertexData {
-    fn eq(&self, other: &Self) -> bool {
-        self.position == other.position && self.normal == other.normal && self.uv == other.uv
+impl QuadTexture {
+    // the whole texture, once
+    pub fn single(texture: BlockTexture) -> Self {
+        Self {
+            texture,
+            corners: [Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y],
+            tiled: false,
+        }
+    }
+
+    pub fn uvs(&self) -> [Vec2; 4] {
+        let cell = if self.tiled {
+            get_tiled_texture(self.texture.layer(), 1.0, 1.0)
+        } else {
+            get_texture(self.texture.atlas_rect())
+        };
+        self.corners
+            .map(|corner| texture_point(cell, corner.x, corner.y))
     }
 }
 
-impl Eq for VertexData {}
+// Opaque, cutout and transparent blocks end up in separate meshes. cutout blocks are either fully
+// drawn or have holes where their texture is see through, so they are alpha tested and need no
+// sorting. the transparent mesh is drawn blended after everything else so the faces behind it stay
+// visible.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MeshPass {
+    Opaque,
+    Cutout,
+    Transparent,
+}
+
+impl MeshPass {
+    pub const ALL: [MeshPass; 3] = [MeshPass::Opaque, MeshPass::Cutout, MeshPass::Transparent];
 
-impl Hash for VertexData {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        hash_float_array(&self.position.to_array(), state);
-        hash_float_array(&self.normal.to_array(), state);
-        hash_float_array(&self.uv.to_array(), state);
+    pub fn of(element: Element) -> Option<MeshPass> {
+        match element {
+            Element::Air => None,
+            Element::Leaves | Element::Flower | Element::Torch => Some(MeshPass::Cutout),
+            _ if element.is_transparent() => Some(MeshPass::Transparent),
+            _ => Some(MeshPass::Opaque),
+        }
     }
 }
 
-fn hash_float_array<H: Hasher>(arr: &[f32], state: &mut H) {
-    for &num in arr {
-        num.to_bits().hash(state);
+// how bright a vertex is for each ambient occlusion level, 0 is a corner enclosed on all sides
+pub const AO_BRIGHTNESS: [f32; 4] = [0.4, 0.6, 0.8, 1.0];
+
+// The classic voxel ambient occlusion: a vertex of a face is darkened by the blocks in front of the face
+// that touch it, the two along its edges and the one on its corner. two edge blocks hide the corner
+// block completely. position is the block the face belongs to, corner the vertex relative to that block
+// with every component 0 or 1, blocks in neighbouring chunks are read through the view.
+pub fn vertex_ao(view: &ChunkView, position: IVec3, normal: IVec3, corner: IVec3) -> u8 {
+    let front = position + normal;
+    // the direction from the middle of the face to the corner
+    let diagonal = (corner * 2 - IVec3::ONE) * (IVec3::ONE - normal.abs());
+    let mut edges = [IVec3::X, IVec3::Y, IVec3::Z]
+        .map(|axis| diagonal * axis)
+        .into_iter()
+        .filter(|edge| *edge != IVec3::ZERO);
+    let first = view.is_opaque(front + edges.next().unwrap_or_default());
+    let second = view.is_opaque(front + edges.next().unwrap_or_default());
+    if first && second {
+        return 0;
     }
+    3 - first as u8 - second as u8 - view.is_opaque(front + diagonal) as u8
 }
 
-pub fn merge_meshes(meshes: Vec<Mesh>) -> Mesh {
-    let mut combined_mesh = Mesh::new(PrimitiveTopology::TriangleList);
-    let mut vertices: Vec<[f32; 3]> = Vec::new();
-    let mut normals: Vec<[f32; 3]> = Vec::new();
-    let mut uvs: Vec<[f32; 2]> = Vec::new();
-    let mut indices: Vec<u32> = Vec::new();
-
-    let mut offset = 0;
-    for mesh in meshes {
-        if let Some(VertexAttributeValues::Float32x3(positions)) =
-            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
-        {
-            vertices.extend_from_slice(positions);
-        }
-        if let Some(VertexAttributeValues::Float32x3(norms)) =
-            mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
-        {
-            normals.extend_from_slice(norms);
-        }
-        if let Some(VertexAttributeValues::Float32x2(texture_coords)) =
-            mesh.attribute(Mesh::ATTRIBUTE_UV_0)
-        {
-            uvs.extend_from_slice(texture_coords);
+pub fn face_ao(view: &ChunkView, position: IVec3, normal: IVec3, corners: [IVec3; 4]) -> [u8; 4] {
+    corners.map(|corner| vertex_ao(view, position, normal, corner))
+}
+
+impl ChunkMeshBuilder {
+    pub fn new(format: ChunkVertexFormat) -> Self {
+        Self {
+            format,
+            origin: Vec3::ZERO,
+            positions: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            colors: Vec::new(),
+            packed: Vec::new(),
+            indices: Vec::new(),
         }
+    }
 
-        indices.push(offset);
-        indices.push(1 + offset);
-        indices.push(2 + offset);
-        indices.push(2 + offset);
-        indices.push(3 + offset);
-        indices.push(offset);
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len() + self.packed.len()
+    }
 
-        offset += 4;
+    pub fn is_empty(&self) -> bool {
+        self.vertex_count() == 0
     }
 
-    combined_mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices);
-    combined_mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
-    combined_mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
-    combined_mesh.set_indices(Some(Indices::U32(indices)));
+    // corners go counter clockwise when looking at the front of the quad, ao is the ambient
+    // occlusion level of every corner and element the block the quad belongs to, for its tint
+    pub fn push_quad(
+        &mut self,
+        corners: [Vec3; 4],
+        normal: Vec3,
+        texture: QuadTexture,
+        ao: [u8; 4],
+        element: Element,
+    ) {
+        let offset = self.vertex_count() as u32;
+        match self.format {
+            ChunkVertexFormat::Standard => {
+                let tint = element.tint();
+                self.positions
+                    .extend(corners.map(|corner| corner.to_array()));
+                self.normals.extend([normal.to_array(); 4]);
+                self.uvs.extend(texture.uvs().map(|uv| uv.to_array()));
+                self.colors.extend(ao.map(|level| {
+                    let brightness = AO_BRIGHTNESS[level as usize];
+                    [
+                        tint[0] * brightness,
+                        tint[1] * brightness,
+                        tint[2] * brightness,
+                        tint[3],
+                    ]
+                }));
+            }
+            ChunkVertexFormat::Packed => {
+                for corner in 0..4 {
+                    let vertex = PackedVertex {
+                        layer: texture.texture.layer(),
+                        ao: ao[corner] as u32,
+                        tint: element.tint_index(),
+                        ..PackedVertex::new(
+                            corners[corner] - self.origin,
+                            normal,
+                            texture.corners[corner],
+                        )
+                    };
+                    self.packed.push(vertex.pack());
+                }
+            }
+        }
+        // the quad is split along the diagonal between the brighter corners, otherwise
+        // the occlusion of a single corner gets smeared across the whole face
+        let [first, second, third, fourth] = if ao[1] + ao[3] > ao[0] + ao[2] {
+            [1, 2, 3, 0]
+        } else {
+            [0, 1, 2, 3]
+        };
+        self.indices.extend([
+            offset + first,
+            offset + second,
+            offset + third,
+            offset + third,
+            offset + fourth,
+            offset + first,
+        ]);
+    }
 
-    combined_mesh
-}
+    pub fn push_face(
+        &mut self,
+        scale: f32,
+        side: BlockFace,
+        offset: Vec3,
+        block: Block,
+        ao: [u8; 4],
+    ) {
+        let (corners, normal) = quad_vertices(scale, side, offset);
+        let texture = QuadTexture::single(block.texture(side));
+        self.push_quad(corners, normal, texture, ao, block.element);
+    }
 
-pub fn gen_meshes(scale: f32, chunk: &Chunk) -> Vec<Mesh> {
-    let mut gen_meshes: Vec<Mesh> = Vec::new();
-
-    for x in 0..CHUNK_WIDTH {
-        for y in 0..CHUNK_HEIGHT {
-            for z in 0..CHUNK_DEPTH {
-                let block = chunk.blocks[x][y][z];
-                let mesh_location = Vec3::new(
-                    chunk.chunk_x * 15.0 + x as f32,
-                    chunk.chunk_y * 15.0 + y as f32,
-                    chunk.chunk_z * 15.0 + z as f32,
-                );
-                // exempt air from needing a mesh
-                if block.element == Element::Air {
-                    continue;
-                };
-                if x == CHUNK_WIDTH - 1 || chunk.blocks[x + 1][y][z].element == Element::Air {
-                    gen_meshes.push(create_quad(
-                        scale,
-                        BlockFace::East,
-                        mesh_location,
-                        block.uv_mapping,
-                    ));
-                }
-                if z == CHUNK_DEPTH - 1 || chunk.blocks[x][y][z + 1].element == Element::Air {
-                    gen_meshes.push(create_quad(
-                        scale,
-                        BlockFace::North,
-                        mesh_location,
-                        block.uv_mapping,
-                    ));
-                }
-                if y == CHUNK_HEIGHT - 1 || chunk.blocks[x][y + 1][z].element == Element::Air {
-                    gen_meshes.push(create_quad(
-                        scale,
-                        BlockFace::Top,
-                        mesh_location,
-                        block.uv_mapping,
-                    ));
+    // one quad for every visible face of the blocks of the chunk that belong to pass, with atlas
+    // uvs so the export tool can write them with the block atlas as their texture
+    pub fn add_chunk(&mut self, scale: f32, view: &ChunkView, pass: MeshPass) {
+        let chunk = view.chunk;
+        let chunk_origin = chunk.world_origin();
+        self.origin = chunk_origin.as_vec3();
+
+        for x in 0..CHUNK_WIDTH {
+            for y in 0..CHUNK_HEIGHT {
+                for z in 0..CHUNK_DEPTH {
+                    let block = chunk.blocks[x][y][z];
+                    // exempt air from needing a mesh, models are added below
+                    if MeshPass::of(block.element) != Some(pass) || block.element.model().is_some()
+                    {
+                        continue;
+                    };
+                    let position = IVec3::new(x as i32, y as i32, z as i32);
+                    let mesh_location = (chunk_origin + position).as_vec3();
+                    // faces on the chunk border look into the neighbouring chunk
+                    for (side, direction) in FACE_DIRECTIONS {
+                        if !block
+                            .element
+                            .shows_face_against(view.element(position + direction), side)
+                        {
+                            continue;
+                        }
+                        let (corners, _) = quad_vertices(1.0, side, Vec3::ZERO);
+                        let ao = face_ao(
+                            view,
+                            position,
+                            direction,
+                            corners.map(|corner| corner.as_ivec3()),
+                        );
+                        self.push_face(scale, side, mesh_location, block, ao);
+                    }
                 }
-                if y == 0 || chunk.blocks[x][y - 1][z].element == Element::Air {
-                    gen_meshes.push(create_quad(
-                        scale,
-                        BlockFace::Bottom,
-                        mesh_location,
-                        block.uv_mapping,
-                    ));
+            }
+        }
+        self.add_models(scale, view, pass, false);
+    }
+
+    // the blocks of the chunk that have a model and belong to pass, every face shows the part of
+    // the texture it covers. tiled picks the kind of uvs, see QuadTexture
+    pub fn add_models(&mut self, scale: f32, view: &ChunkView, pass: MeshPass, tiled: bool) {
+        let chunk = view.chunk;
+        let chunk_origin = chunk.world_origin();
+        for x in 0..CHUNK_WIDTH {
+            for y in 0..CHUNK_HEIGHT {
+                for z in 0..CHUNK_DEPTH {
+                    let block = chunk.blocks[x][y][z];
+                    if block.element.model().is_none() || MeshPass::of(block.element) != Some(pass)
+                    {
+                        continue;
+                    }
+                    let position = IVec3::new(x as i32, y as i32, z as i32);
+                    let offset = (chunk_origin + position).as_vec3();
+                    self.push_model(scale, view, position, offset, block, tiled);
                 }
-                if z == 0 || chunk.blocks[x][y][z - 1].element == Element::Air {
-                    gen_meshes.push(create_quad(
-                        scale,
-                        BlockFace::South,
-                        mesh_location,
-                        block.uv_mapping,
-                    ));
+            }
+        }
+    }
+
+    // the boxes and planes of the model of block. a box face is left out where another box of the
+    // block or a box of the neighbour on that side lies against it, and completely when that
+    // neighbour covers the side. faces of full cubes next to a model only check the sides the model
+    // covers. models get no ambient occlusion
+    pub fn push_model(
+        &mut self,
+        scale: f32,
+        view: &ChunkView,
+        position: IVec3,
+        offset: Vec3,
+        block: Block,
+        tiled: bool,
+    ) {
+        let Some(model) = block.element.model() else {
+            return;
+        };
+        let element = block.element;
+        let neighbour = |side: BlockFace| view.element(position + side.direction());
+        let parts: Vec<&ModelPart> = model
+            .parts(|side| element.connects_to(neighbour(side), side))
+            .collect();
+        let boxes: Vec<(Vec3, Vec3)> = parts
+            .iter()
+            .filter(|part| matches!(part, ModelPart::Box { .. }))
+            .map(|part| part.bounds())
+            .collect();
+        let beside = BlockFace::ALL.map(|side| neighbour_boxes(element, neighbour(side), side));
+        for part in parts {
+            let (from, to) = part.bounds();
+            let texture = |side: BlockFace, corners: [Vec2; 4]| QuadTexture {
+                texture: part.texture().unwrap_or(block.texture(side)),
+                corners,
+                tiled,
+            };
+            match part {
+                ModelPart::Box { .. } => {
+                    for (index, side) in BlockFace::ALL.into_iter().enumerate() {
+                        let (unit, normal) = quad_vertices(1.0, side, Vec3::ZERO);
+                        let corners = unit.map(|corner| from + corner * (to - from));
+                        let direction = side.direction();
+                        let on_border =
+                            (corners[0] - Vec3::splat(0.5)).dot(direction.as_vec3()) >= 0.5;
+                        if on_border && neighbour(side).occludes(side.opposite()) {
+                            continue;
+                        }
+                        let (u, v) = (corners[1] - corners[0], corners[3] - corners[0]);
+                        let covering = boxes.iter().chain(beside[index].iter());
+                        for (start, end) in uncovered_rects(corners[0], u, v, normal, covering) {
+                            let piece = [
+                                corners[0] + u * start.x + v * start.y,
+                                corners[0] + u * end.x + v * start.y,
+                                corners[0] + u * end.x + v * end.y,
+                                corners[0] + u * start.x + v * end.y,
+                            ];
+                            // where the corners lie on the face of the full cube
+                            let (unit_u, unit_v) = (unit[1] - unit[0], unit[3] - unit[0]);
+                            let texture_corners = piece.map(|corner| {
+                                Vec2::new(
+                                    (corner - unit[0]).dot(unit_u),
+                                    (corner - unit[0]).dot(unit_v),
+                                )
+                            });
+                            self.push_quad(
+                                piece.map(|corner| corner * scale + offset),
+                                normal,
+                                texture(side, texture_corners),
+                                [3; 4],
+                                element,
+                            );
+                        }
+                    }
                 }
-                if x == 0 || chunk.blocks[x - 1][y][z].element == Element::Air {
-                    gen_meshes.push(create_quad(
-                        scale,
-                        BlockFace::West,
-                        mesh_location,
-                        block.uv_mapping,
-                    ));
+                ModelPart::Plane { .. } => {
+                    let corners = [
+                        from,
+                        Vec3::new(to.x, from.y, to.z),
+                        to,
+                        Vec3::new(from.x, to.y, from.z),
+                    ]
+                    .map(|corner| corner * scale + offset);
+                    let normal = (corners[1] - corners[0]).cross(Vec3::Y).normalize_or_zero();
+                    let [first, second, third, fourth] = [
+                        Vec2::new(0.0, from.y),
+                        Vec2::new(1.0, from.y),
+                        Vec2::new(1.0, to.y),
+                        Vec2::new(0.0, to.y),
+                    ];
+                    self.push_quad(
+                        corners,
+                        normal,
+                        texture(BlockFace::North, [first, second, third, fourth]),
+                        [3; 4],
+                        element,
+                    );
+                    // the back is the same quad mirrored
+                    let [a, b, c, d] = corners;
+                    self.push_quad(
+                        [b, a, d, c],
+                        -normal,
+                        texture(BlockFace::North, [second, first, fourth, third]),
+                        [3; 4],
+                        element,
+                    );
                 }
             }
         }
     }
-    gen_meshes
+
+    pub fn build(&mut self) -> Mesh {
+        let mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        let mesh = match self.format {
+            ChunkVertexFormat::Standard => mesh
+                .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, self.positions.clone())
+                .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals.clone())
+                .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs.clone())
+                .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, self.colors.clone()),
+            ChunkVertexFormat::Packed => {
+                mesh.with_inserted_attribute(ATTRIBUTE_PACKED_VERTEX, self.packed.clone())
+            }
+        }
+        .with_indices(Some(Indices::U32(self.indices.clone())));
+        self.clear();
+        mesh
+    }
+
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.normals.clear();
+        self.uvs.clear();
+        self.colors.clear();
+        self.packed.clear();
+        self.indices.clear();
+    }
+}
+
+// the boxes of the model of neighbour, which lies on side of a block of element, moved next to
+// that block. of the connections of the neighbour only the one facing the block is known here
+fn neighbour_boxes(element: Element, neighbour: Element, side: BlockFace) -> Vec<(Vec3, Vec3)> {
+    let Some(model) = neighbour.model() else {
+        return Vec::new();
+    };
+    if MeshPass::of(neighbour) != Some(MeshPass::Opaque) {
+        return Vec::new();
+    }
+    let facing = side.opposite();
+    let shift = side.direction().as_vec3();
+    model
+        .parts(|connection| connection == facing && neighbour.connects_to(element, facing))
+        .filter(|part| matches!(part, ModelPart::Box { .. }))
+        .map(|part| {
+            let (from, to) = part.bounds();
+            (from + shift, to + shift)
+        })
+        .collect()
+}
+
+// the parts of the face from corner, spanned by u and v, that none of the boxes lies against, as
+// start and end fractions along u and v. a box lies against the face when it reaches from the
+// plane of the face outwards or contains it
+fn uncovered_rects<'a>(
+    corner: Vec3,
+    u: Vec3,
+    v: Vec3,
+    normal: Vec3,
+    boxes: impl Iterator<Item = &'a (Vec3, Vec3)>,
+) -> Vec<(Vec2, Vec2)> {
+    let mut rects = vec![(Vec2::ZERO, Vec2::ONE)];
+    if u.length_squared() == 0.0 || v.length_squared() == 0.0 {
+        return rects;
+    }
+    let plane = corner.dot(normal);
+    let along = |from: Vec3, to: Vec3, axis: Vec3| {
+        let start = (from - corner).dot(axis) / axis.length_squared();
+        let end = (to - corner).dot(axis) / axis.length_squared();
+        (start.min(end), start.max(end))
+    };
+    for (from, to) in boxes {
+        let (near, far) = (from.dot(normal), to.dot(normal));
+        if plane < near.min(far) || plane >= near.max(far) {
+            continue;
+        }
+        let (u_start, u_end) = along(*from, *to, u);
+        let (v_start, v_end) = along(*from, *to, v);
+        let cover = (Vec2::new(u_start, v_start), Vec2::new(u_end, v_end));
+        rects = rects
+            .into_iter()
+            .flat_map(|rect| subtract_rect(rect, cover))
+            .collect();
+    }
+    rects
+}
+
+// what is left of rect without cover, the strips beside the cover span the whole height of rect,
+// the ones below and above only the width of the cover
+fn subtract_rect((min, max): (Vec2, Vec2), cover: (Vec2, Vec2)) -> Vec<(Vec2, Vec2)> {
+    let (cover_min, cover_max) = (cover.0.max(min), cover.1.min(max));
+    if cover_min.cmpge(cover_max).any() {
+        return vec![(min, max)];
+    }
+    let mut rest = Vec::new();
+    if min.x < cover_min.x {
+        rest.push((min, Vec2::new(cover_min.x, max.y)));
+    }
+    if cover_max.x < max.x {
+        rest.push((Vec2::new(cover_max.x, min.y), max));
+    }
+    if min.y < cover_min.y {
+        rest.push((
+            Vec2::new(cover_min.x, min.y),
+            Vec2::new(cover_max.x, cover_min.y),
+        ));
+    }
+    if cover_max.y < max.y {
+        rest.push((
+            Vec2::new(cover_min.x, cover_max.y),
+            Vec2::new(cover_max.x, max.y),
+        ));
+    }
+    rest
+}
+
+// the point at s, t of a texture given by the uvs of its corners in quad_vertices order
+fn texture_point(uvs: [Vec2; 4], s: f32, t: f32) -> Vec2 {
+    // weighted so the corners come out exactly
+    let bottom = uvs[0] * (1.0 - s) + uvs[1] * s;
+    let top = uvs[3] * (1.0 - s) + uvs[2] * s;
+    bottom * (1.0 - t) + top * t
+}
+
+const FACE_DIRECTIONS: [(BlockFace, IVec3); 6] = [
+    (BlockFace::East, IVec3::X),
+    (BlockFace::North, IVec3::Z),
+    (BlockFace::Top, IVec3::Y),
+    (BlockFace::Bottom, IVec3::NEG_Y),
+    (BlockFace::South, IVec3::NEG_Z),
+    (BlockFace::West, IVec3::NEG_X),
+];
+
+// a mesh with one quad for every visible block face, see gen_greedy_mesh for the merged version
+pub fn gen_mesh(scale: f32, view: &ChunkView, pass: MeshPass) -> Mesh {
+    let mut builder = ChunkMeshBuilder::new(ChunkVertexFormat::Standard);
+    builder.add_chunk(scale, view, pass);
+    builder.build()
 }
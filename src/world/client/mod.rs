@@ -1,5 +1,14 @@
 mod atmosphere;
+mod block_textures;
 mod client_in;
 mod events;
-mod mesh_utils;
+pub mod fade;
+pub mod fog;
+pub mod greedy_mesh;
+pub mod lod;
+pub mod material;
+pub mod mesh_utils;
+pub mod packed;
 pub mod plugin;
+pub mod remesh;
+pub mod visibility;
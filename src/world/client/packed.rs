@@ -0,0 +1,131 @@
+use std::f32::consts::FRAC_1_SQRT_2 as DIAGONAL;
+
+use bevy::{
+    prelude::*,
+    render::{mesh::MeshVertexAttribute, render_resource::VertexFormat},
+};
+
+// Packed chunk vertices.
+// instead of full f32 positions, normals, uvs and colors (48 bytes) every vertex is stored in two
+// u32s that assets/shaders/chunk_vertex.wgsl decodes again:
+//     low:  x (9 bits) | y (9) | z (9) | normal (4)
+//     high: u (9 bits) | v (9) | layer (6) | ao (2) | light (4) | tint (2)
+// positions are relative to the chunk origin and uvs count texture repeats, both in 1/16 steps so
+// model geometry fits. the normal is an index into PACKED_NORMALS, the layer the layer of the
+// texture in the block texture array and the tint an index into TINTS.
+
+pub const ATTRIBUTE_PACKED_VERTEX: MeshVertexAttribute =
+    MeshVertexAttribute::new("Vertex_Packed", 988_540_917, VertexFormat::Uint32x2);
+
+// how many steps a block (or a texture repeat) is split into
+pub const PACKED_STEPS: f32 = 16.0;
+
+// light is not computed yet, every vertex is fully lit
+pub const FULL_LIGHT: u32 = 15;
+
+// the directions faces can point in, the axes for cube faces and the diagonals for crossed planes
+pub const PACKED_NORMALS: [Vec3; 10] = [
+    Vec3::X,
+    Vec3::NEG_X,
+    Vec3::Y,
+    Vec3::NEG_Y,
+    Vec3::Z,
+    Vec3::NEG_Z,
+    Vec3::new(DIAGONAL, 0.0, DIAGONAL),
+    Vec3::new(-DIAGONAL, 0.0, -DIAGONAL),
+    Vec3::new(DIAGONAL, 0.0, -DIAGONAL),
+    Vec3::new(-DIAGONAL, 0.0, DIAGONAL),
+];
+
+// which vertex attributes chunk meshes are built with. packed meshes need the chunk shader and
+// the block texture array. standard meshes are the fallback for where that does not work: one
+// quad per block face with atlas uvs, drawn with the plain standard material
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ChunkVertexFormat {
+    #[default]
+    Packed,
+    Standard,
+}
+
+impl ChunkVertexFormat {
+    pub fn from_string(s: &str) -> Option<ChunkVertexFormat> {
+        match s {
+            "packed" => Some(ChunkVertexFormat::Packed),
+            "standard" => Some(ChunkVertexFormat::Standard),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct PackedVertex {
+    pub position: UVec3,
+    pub normal: u32,
+    pub uv: UVec2,
+    pub layer: u32,
+    pub ao: u32,
+    pub light: u32,
+    pub tint: u32,
+}
+
+const fn mask(bits: u32) -> u32 {
+    (1 << bits) - 1
+}
+
+impl PackedVertex {
+    // position is relative to the chunk origin and uv in texture repeats
+    pub fn new(position: Vec3, normal: Vec3, uv: Vec2) -> Self {
+        Self {
+            position: (position * PACKED_STEPS).round().as_uvec3(),
+            normal: normal_index(normal),
+            uv: (uv * PACKED_STEPS).round().as_uvec2(),
+            light: FULL_LIGHT,
+            ..default()
+        }
+    }
+
+    pub fn pack(&self) -> [u32; 2] {
+        [
+            (self.position.x & mask(9))
+                | (self.position.y & mask(9)) << 9
+                | (self.position.z & mask(9)) << 18
+                | (self.normal & mask(4)) << 27,
+            (self.uv.x & mask(9))
+                | (self.uv.y & mask(9)) << 9
+                | (self.layer & mask(6)) << 18
+                | (self.ao & mask(2)) << 24
+                | (self.light & mask(4)) << 26
+                | (self.tint & mask(2)) << 30,
+        ]
+    }
+
+    pub fn unpack([low, high]: [u32; 2]) -> Self {
+        Self {
+            position: UVec3::new(low & mask(9), (low >> 9) & mask(9), (low >> 18) & mask(9)),
+            normal: (low >> 27) & mask(4),
+            uv: UVec2::new(high & mask(9), (high >> 9) & mask(9)),
+            layer: (high >> 18) & mask(6),
+            ao: (high >> 24) & mask(2),
+            light: (high >> 26) & mask(4),
+            tint: (high >> 30) & mask(2),
+        }
+    }
+
+    pub fn local_position(&self) -> Vec3 {
+        self.position.as_vec3() / PACKED_STEPS
+    }
+
+    pub fn texture_position(&self) -> Vec2 {
+        self.uv.as_vec2() / PACKED_STEPS
+    }
+}
+
+// the entry of PACKED_NORMALS closest to normal
+pub fn normal_index(normal: Vec3) -> u32 {
+    PACKED_NORMALS
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.dot(normal).total_cmp(&b.dot(normal)))
+        .map(|(index, _)| index as u32)
+        .unwrap_or_default()
+}
@@ -2,32 +2,77 @@ use bevy::prelude::*;
 use bevy::utils::Duration;
 use bevy_atmosphere::prelude::*;
 
-use crate::world::chunk::{ChunkQueue, ChunkRadius, ChunkRegistry};
+use crate::world::{
+    chunk::{ChunkQueue, ChunkRadius, ChunkRegistry, DEFAULT_VIEW_DISTANCE},
+    wire::ChunkTransferStats,
+};
 
 use super::atmosphere::{daylight_cycle, setup_environment, CycleTimer};
+use super::block_textures::{build_block_texture_array, load_block_textures, BlockTextureHandles};
 use super::client_in::get_chunk_from_server;
-use super::events::{render, RenderChunk};
+use super::events::{RenderChunk, UnloadChunk};
+use super::fade::fade_in_chunks;
+use super::fog::update_fog;
+use super::lod::{follow_view_distance, update_chunk_lods, LodSettings};
+use super::material::ChunkMaterial;
+use super::packed::ChunkVertexFormat;
+use super::remesh::{
+    finish_mesh_tasks, receive_chunks, start_mesh_tasks, unload_chunks, ChunkEntities, DirtyChunks,
+    MeshTasks, RemeshBudget,
+};
+use super::visibility::{cull_chunks, ChunkVisibilityStats};
 
 pub struct ClientWorldPlugin;
 
 impl Plugin for ClientWorldPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(ChunkRegistry {
-            chunks: Vec::<Vec3>::new(),
-        })
-        .insert_resource(ChunkRadius { radius: 3 })
-        .insert_resource(ChunkQueue { chunks: Vec::new() })
-        .insert_resource(Msaa::Sample4)
-        .insert_resource(AtmosphereModel::default())
-        .insert_resource(CycleTimer(Timer::new(
-            Duration::from_millis(50),
-            TimerMode::Repeating,
-        )))
-        .add_plugins(AtmospherePlugin)
-        .add_event::<RenderChunk>()
-        .add_systems(Update, render)
-        .add_systems(Startup, setup_environment)
-        .add_systems(Update, daylight_cycle)
-        .add_systems(Update, get_chunk_from_server);
+        // the default format unless the client picked one before adding the plugin, see bin/client.rs
+        app.init_resource::<ChunkVertexFormat>()
+            .init_resource::<ChunkRegistry>()
+            .insert_resource(ChunkRadius {
+                radius: DEFAULT_VIEW_DISTANCE,
+            })
+            .insert_resource(ChunkQueue { chunks: Vec::new() })
+            .insert_resource(Msaa::Sample4)
+            .insert_resource(AtmosphereModel::default())
+            .insert_resource(CycleTimer(Timer::new(
+                Duration::from_millis(50),
+                TimerMode::Repeating,
+            )))
+            .add_plugins(AtmospherePlugin)
+            .add_plugins(MaterialPlugin::<ChunkMaterial>::default())
+            .init_resource::<LodSettings>()
+            .init_resource::<ChunkEntities>()
+            .init_resource::<DirtyChunks>()
+            .init_resource::<RemeshBudget>()
+            .init_resource::<MeshTasks>()
+            .init_resource::<ChunkVisibilityStats>()
+            .init_resource::<ChunkTransferStats>()
+            .add_event::<RenderChunk>()
+            .add_event::<UnloadChunk>()
+            .add_systems(
+                Update,
+                (
+                    receive_chunks,
+                    unload_chunks,
+                    apply_deferred,
+                    follow_view_distance,
+                    update_chunk_lods,
+                    start_mesh_tasks,
+                    finish_mesh_tasks,
+                    cull_chunks,
+                    fade_in_chunks,
+                )
+                    .chain(),
+            )
+            .add_systems(Startup, setup_environment)
+            .add_systems(Startup, load_block_textures)
+            .add_systems(
+                Update,
+                build_block_texture_array.run_if(resource_exists::<BlockTextureHandles>()),
+            )
+            .add_systems(Update, daylight_cycle)
+            .add_systems(Update, update_fog.after(daylight_cycle))
+            .add_systems(Update, get_chunk_from_server.before(receive_chunks));
     }
 }
@@ -0,0 +1,403 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{
+    ecs::system::EntityCommands,
+    prelude::*,
+    render::primitives::Aabb,
+    tasks::{block_on, AsyncComputeTaskPool, Task},
+};
+
+use crate::{
+    player::lib::Player,
+    world::chunk::{changed_border_neighbours, chunk_neighbourhood, Chunk, ChunkView, CHUNK_SIZE},
+};
+
+use super::{
+    events::{RenderChunk, UnloadChunk},
+    fade::ChunkFadeIn,
+    lod::{camera_column, downsample_chunk, ChunkLod, LodSettings},
+    material::{AtlasMaterials, BlockMaterials},
+    mesh_utils::{ChunkMeshBuilder, MeshPass},
+    packed::ChunkVertexFormat,
+    visibility::{chunk_connections, ChunkConnections},
+};
+
+// Chunks are meshed incrementally. every chunk gets one entity that is kept for as long as the chunk
+// is loaded, received chunks only mark themselves (and the neighbours that can see a changed
+// border block) as dirty, and a limited number of dirty chunks is handed to background tasks every
+// frame, closest to the player first. finished meshes replace the old ones in place.
+
+#[derive(Resource, Default)]
+pub struct ChunkEntities(pub HashMap<IVec3, Entity>);
+
+#[derive(Resource, Default)]
+pub struct DirtyChunks(pub HashSet<IVec3>);
+
+// how many mesh tasks are started per frame
+#[derive(Resource)]
+pub struct RemeshBudget {
+    pub chunks_per_frame: usize,
+}
+
+impl Default for RemeshBudget {
+    fn default() -> Self {
+        Self {
+            chunks_per_frame: 8,
+        }
+    }
+}
+
+// the alpha tested mesh of leaves, flowers and torches, a child of the chunk entity that is only
+// spawned once the chunk has such blocks
+#[derive(Component)]
+pub struct CutoutMesh(pub Entity);
+
+// the blended mesh of glass and water, spawned like the cutout mesh
+#[derive(Component)]
+pub struct TransparentMesh(pub Entity);
+
+// standard meshes are built in world space, packed ones relative to the chunk origin
+fn chunk_transform(chunk: &Chunk, format: ChunkVertexFormat) -> Transform {
+    match format {
+        ChunkVertexFormat::Standard => Transform::IDENTITY,
+        ChunkVertexFormat::Packed => Transform::from_translation(chunk.world_origin().as_vec3()),
+    }
+}
+
+// bevy can not compute the bounds of packed meshes, they get the bounds of the whole chunk
+fn chunk_bounds(format: ChunkVertexFormat) -> Option<Aabb> {
+    match format {
+        ChunkVertexFormat::Standard => None,
+        ChunkVertexFormat::Packed => Some(Aabb::from_min_max(Vec3::ZERO, CHUNK_SIZE.as_vec3())),
+    }
+}
+
+// packed meshes are drawn with the chunk material, standard ones with the plain standard material.
+// only packed chunks fade in, at the given step
+fn insert_material(
+    entity: &mut EntityCommands,
+    format: ChunkVertexFormat,
+    block_materials: &BlockMaterials,
+    atlas_materials: &AtlasMaterials,
+    pass: MeshPass,
+    fade_step: Option<usize>,
+) {
+    match format {
+        ChunkVertexFormat::Standard => entity.insert(atlas_materials.get(pass).clone()),
+        ChunkVertexFormat::Packed => entity.insert(block_materials.get(pass, fade_step).clone()),
+    };
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn receive_chunks(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut render_chunk_event_reader: EventReader<RenderChunk>,
+    block_materials: Res<BlockMaterials>,
+    atlas_materials: Res<AtlasMaterials>,
+    vertex_format: Res<ChunkVertexFormat>,
+    mut chunk_entities: ResMut<ChunkEntities>,
+    mut dirty_chunks: ResMut<DirtyChunks>,
+    mut chunk_query: Query<&mut Chunk>,
+) {
+    for event in render_chunk_event_reader.read() {
+        let position = event.chunk.position();
+        dirty_chunks.0.insert(position);
+        let Some(entity) = chunk_entities.0.get(&position) else {
+            // the neighbours drew their border faces against air until now
+            dirty_chunks
+                .0
+                .extend(chunk_neighbourhood().map(|offset| position + offset));
+            let mut entity = commands.spawn((
+                event.chunk,
+                meshes.add(ChunkMeshBuilder::new(*vertex_format).build()),
+                SpatialBundle::from_transform(chunk_transform(&event.chunk, *vertex_format)),
+            ));
+            insert_material(
+                &mut entity,
+                *vertex_format,
+                &block_materials,
+                &atlas_materials,
+                MeshPass::Opaque,
+                None,
+            );
+            if let Some(bounds) = chunk_bounds(*vertex_format) {
+                entity.insert(bounds);
+            }
+            let entity = entity.id();
+            chunk_entities.0.insert(position, entity);
+            continue;
+        };
+        match chunk_query.get_mut(*entity) {
+            Ok(mut chunk) => {
+                dirty_chunks.0.extend(
+                    changed_border_neighbours(&chunk, &event.chunk)
+                        .into_iter()
+                        .map(|offset| position + offset),
+                );
+                *chunk = event.chunk;
+            }
+            // spawned earlier this frame, the newer copy replaces it
+            Err(_) => {
+                commands.entity(*entity).insert(event.chunk);
+            }
+        }
+    }
+}
+
+// chunks the server stopped streaming are despawned, their loaded neighbours are remeshed since
+// they now border air
+pub fn unload_chunks(
+    mut commands: Commands,
+    mut unload_chunk_event_reader: EventReader<UnloadChunk>,
+    mut chunk_entities: ResMut<ChunkEntities>,
+    mut dirty_chunks: ResMut<DirtyChunks>,
+    mut mesh_tasks: ResMut<MeshTasks>,
+) {
+    for event in unload_chunk_event_reader.read() {
+        let Some(entity) = chunk_entities.0.remove(&event.position) else {
+            continue;
+        };
+        commands.entity(entity).despawn_recursive();
+        dirty_chunks.0.remove(&event.position);
+        // dropping the task cancels it
+        mesh_tasks.0.remove(&event.position);
+        dirty_chunks.0.extend(
+            chunk_neighbourhood()
+                .map(|offset| event.position + offset)
+                .filter(|position| chunk_entities.0.contains_key(position)),
+        );
+    }
+}
+
+// Everything a background task needs to mesh one chunk: copies of the chunk and its loaded
+// neighbours together with the level of detail each of them is drawn at.
+pub struct ChunkMeshJob {
+    pub position: IVec3,
+    pub chunks: HashMap<IVec3, (Chunk, u32)>,
+    pub format: ChunkVertexFormat,
+}
+
+pub struct ChunkMeshes {
+    pub level: u32,
+    pub opaque: Mesh,
+    pub cutout: Mesh,
+    pub transparent: Mesh,
+    pub connections: ChunkConnections,
+}
+
+impl ChunkMeshJob {
+    pub fn run(self) -> Option<ChunkMeshes> {
+        let (chunk, level) = self.chunks.get(&self.position)?;
+        let level = *level;
+        // from the full chunk, a downsampled one may close or open small gaps
+        let connections = chunk_connections(chunk);
+        // neighbours at another level read as air, see lod.rs
+        let downsampled: HashMap<IVec3, Chunk> = self
+            .chunks
+            .iter()
+            .filter(|(_, (_, chunk_level))| *chunk_level == level)
+            .map(|(position, (chunk, _))| (*position, downsample_chunk(chunk, level)))
+            .collect();
+        let neighbours: HashMap<IVec3, &Chunk> = downsampled
+            .iter()
+            .map(|(position, chunk)| (*position, chunk))
+            .collect();
+        let view = ChunkView::new(neighbours.get(&self.position)?, &neighbours);
+
+        // merged faces repeat their texture, which only the chunk shader can do. standard meshes
+        // get one quad per face with atlas uvs instead
+        let mut mesh_builder = ChunkMeshBuilder::new(self.format);
+        let mut mesh = |pass| {
+            match self.format {
+                ChunkVertexFormat::Standard => mesh_builder.add_chunk(1.0, &view, pass),
+                ChunkVertexFormat::Packed => mesh_builder.add_greedy_chunk(1.0, &view, pass),
+            }
+            mesh_builder.build()
+        };
+        let opaque = mesh(MeshPass::Opaque);
+        let cutout = mesh(MeshPass::Cutout);
+        let transparent = mesh(MeshPass::Transparent);
+        Some(ChunkMeshes {
+            level,
+            opaque,
+            cutout,
+            transparent,
+            connections,
+        })
+    }
+}
+
+// the chunks that are being meshed in the background, a chunk that is scheduled again while its
+// task is still running replaces the task, which drops and cancels the outdated one
+#[derive(Resource, Default)]
+pub struct MeshTasks(pub HashMap<IVec3, Task<Option<ChunkMeshes>>>);
+
+#[allow(clippy::too_many_arguments)]
+pub fn start_mesh_tasks(
+    lod_settings: Res<LodSettings>,
+    remesh_budget: Res<RemeshBudget>,
+    vertex_format: Res<ChunkVertexFormat>,
+    player_query: Query<&Transform, With<Player>>,
+    chunk_entities: Res<ChunkEntities>,
+    mut dirty_chunks: ResMut<DirtyChunks>,
+    mut mesh_tasks: ResMut<MeshTasks>,
+    chunk_query: Query<&Chunk>,
+) {
+    // neighbours of received chunks that are not loaded themselves have nothing to mesh
+    dirty_chunks
+        .0
+        .retain(|position| chunk_entities.0.contains_key(position));
+    if dirty_chunks.0.is_empty() {
+        return;
+    }
+    let column = player_query
+        .get_single()
+        .map(|transform| camera_column(transform.translation))
+        .unwrap_or_default();
+    let mut batch: Vec<IVec3> = dirty_chunks.0.iter().copied().collect();
+    batch.sort_by_key(|position| {
+        (
+            (position.xz() - column).length_squared(),
+            position.y,
+            position.x,
+            position.z,
+        )
+    });
+    batch.truncate(remesh_budget.chunks_per_frame);
+
+    let task_pool = AsyncComputeTaskPool::get();
+    for position in batch {
+        dirty_chunks.0.remove(&position);
+        let job = ChunkMeshJob {
+            position,
+            chunks: chunk_neighbourhood()
+                .chain([IVec3::ZERO])
+                .map(|offset| position + offset)
+                .filter_map(|position| {
+                    let chunk = chunk_query.get(*chunk_entities.0.get(&position)?).ok()?;
+                    Some((position, (*chunk, lod_settings.level(column, position))))
+                })
+                .collect(),
+            format: *vertex_format,
+        };
+        mesh_tasks
+            .0
+            .insert(position, task_pool.spawn(async move { job.run() }));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn finish_mesh_tasks(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    block_materials: Res<BlockMaterials>,
+    atlas_materials: Res<AtlasMaterials>,
+    vertex_format: Res<ChunkVertexFormat>,
+    chunk_entities: Res<ChunkEntities>,
+    dirty_chunks: Res<DirtyChunks>,
+    mut mesh_tasks: ResMut<MeshTasks>,
+    chunk_query: Query<
+        (
+            &Handle<Mesh>,
+            Option<&CutoutMesh>,
+            Option<&TransparentMesh>,
+            Option<&ChunkFadeIn>,
+            Has<ChunkConnections>,
+        ),
+        With<Chunk>,
+    >,
+    child_query: Query<&Handle<Mesh>, Without<Chunk>>,
+) {
+    let finished: Vec<IVec3> = mesh_tasks
+        .0
+        .iter()
+        .filter(|(_, task)| task.is_finished())
+        .map(|(position, _)| *position)
+        .collect();
+    for position in finished {
+        let Some(task) = mesh_tasks.0.remove(&position) else {
+            continue;
+        };
+        let Some(chunk_meshes) = block_on(task) else {
+            continue;
+        };
+        // the chunk was unloaded, or changed and will be meshed again
+        let Some(entity) = chunk_entities.0.get(&position) else {
+            continue;
+        };
+        if dirty_chunks.0.contains(&position) {
+            continue;
+        }
+        let Ok((mesh, cutout, transparent, fade, meshed)) = chunk_query.get(*entity) else {
+            continue;
+        };
+
+        meshes.insert(mesh, chunk_meshes.opaque);
+        commands
+            .entity(*entity)
+            .insert((ChunkLod(chunk_meshes.level), chunk_meshes.connections));
+        // the first mesh of a packed chunk fades in, see fade.rs
+        let fade_step = match fade {
+            Some(fade) => Some(fade.step),
+            None if !meshed && *vertex_format == ChunkVertexFormat::Packed => {
+                let mut chunk = commands.entity(*entity);
+                chunk.insert(ChunkFadeIn::default());
+                insert_material(
+                    &mut chunk,
+                    *vertex_format,
+                    &block_materials,
+                    &atlas_materials,
+                    MeshPass::Opaque,
+                    Some(0),
+                );
+                Some(0)
+            }
+            None => None,
+        };
+
+        // leaves, flowers and torches get their own alpha tested mesh as a child of the chunk,
+        // glass and water a blended one that bevy draws after the others sorted back to front
+        for (pass, pass_mesh, child) in [
+            (
+                MeshPass::Cutout,
+                chunk_meshes.cutout,
+                cutout.map(|child| child.0),
+            ),
+            (
+                MeshPass::Transparent,
+                chunk_meshes.transparent,
+                transparent.map(|child| child.0),
+            ),
+        ] {
+            match child.and_then(|child| child_query.get(child).ok()) {
+                Some(handle) => {
+                    meshes.insert(handle, pass_mesh);
+                }
+                None if pass_mesh.count_vertices() > 0 => {
+                    let mut child =
+                        commands.spawn((meshes.add(pass_mesh), SpatialBundle::default()));
+                    insert_material(
+                        &mut child,
+                        *vertex_format,
+                        &block_materials,
+                        &atlas_materials,
+                        pass,
+                        fade_step,
+                    );
+                    if let Some(bounds) = chunk_bounds(*vertex_format) {
+                        child.insert(bounds);
+                    }
+                    let child = child.id();
+                    let mut chunk = commands.entity(*entity);
+                    chunk.add_child(child);
+                    match pass {
+                        MeshPass::Cutout => chunk.insert(CutoutMesh(child)),
+                        _ => chunk.insert(TransparentMesh(child)),
+                    };
+                }
+                None => {}
+            }
+        }
+    }
+}
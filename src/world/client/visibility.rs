@@ -0,0 +1,230 @@
+use std::collections::{HashSet, VecDeque};
+
+use bevy::{
+    math::Affine3A,
+    prelude::*,
+    render::primitives::{Aabb, Frustum},
+};
+
+use crate::{
+    player::lib::Player,
+    world::{
+        block::BlockFace,
+        chunk::{chunk_origin, world_to_chunk_position, Chunk, CHUNK_SIZE},
+    },
+};
+
+use super::remesh::ChunkEntities;
+
+// Cave culling: while a chunk is meshed it records which of its six faces are connected through
+// open (not opaque) blocks. every frame the client walks from the chunk the camera is in to its
+// neighbours, leaving a chunk only through a face that is connected to the face it was entered
+// through and never turning back towards the camera. chunks the walk does not reach, like caves
+// enclosed by solid rock, can not be seen and are hidden. the walk also stops at the edge of the
+// view frustum.
+
+// which pairs of faces are connected, bit a * 6 + b for the faces with index a and b
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ChunkConnections(u64);
+
+fn face_index(face: BlockFace) -> usize {
+    BlockFace::ALL
+        .iter()
+        .position(|other| *other == face)
+        .unwrap_or_default()
+}
+
+impl ChunkConnections {
+    pub const NONE: ChunkConnections = ChunkConnections(0);
+    pub const ALL: ChunkConnections = ChunkConnections((1 << 36) - 1);
+
+    pub fn connect(&mut self, a: BlockFace, b: BlockFace) {
+        let (a, b) = (face_index(a), face_index(b));
+        self.0 |= 1 << (a * 6 + b) | 1 << (b * 6 + a);
+    }
+
+    pub fn connects(&self, a: BlockFace, b: BlockFace) -> bool {
+        self.0 & 1 << (face_index(a) * 6 + face_index(b)) != 0
+    }
+}
+
+// the faces of the chunk the block at local position lies on
+fn border_faces(local: IVec3) -> impl Iterator<Item = BlockFace> {
+    BlockFace::ALL.into_iter().filter(move |face| {
+        let direction = face.direction();
+        let edge = IVec3::select(
+            direction.cmpgt(IVec3::ZERO),
+            CHUNK_SIZE - IVec3::ONE,
+            IVec3::ZERO,
+        );
+        let axis = direction.abs();
+        (local * axis) == (edge * axis)
+    })
+}
+
+// flood fills every region of open blocks and connects all chunk faces the region touches
+pub fn chunk_connections(chunk: &Chunk) -> ChunkConnections {
+    let index = |position: IVec3| {
+        (position.x * CHUNK_SIZE.y * CHUNK_SIZE.z + position.y * CHUNK_SIZE.z + position.z) as usize
+    };
+    let is_open = |position: IVec3| {
+        !chunk.blocks[position.x as usize][position.y as usize][position.z as usize]
+            .element
+            .is_opaque()
+    };
+    let mut visited = vec![false; (CHUNK_SIZE.x * CHUNK_SIZE.y * CHUNK_SIZE.z) as usize];
+    let mut connections = ChunkConnections::NONE;
+    let mut stack = Vec::new();
+    for x in 0..CHUNK_SIZE.x {
+        for y in 0..CHUNK_SIZE.y {
+            for z in 0..CHUNK_SIZE.z {
+                let start = IVec3::new(x, y, z);
+                if visited[index(start)] || !is_open(start) {
+                    continue;
+                }
+                visited[index(start)] = true;
+                stack.push(start);
+                let mut touched: Vec<BlockFace> = Vec::new();
+                while let Some(position) = stack.pop() {
+                    for face in border_faces(position) {
+                        if !touched.contains(&face) {
+                            touched.push(face);
+                        }
+                    }
+                    for face in BlockFace::ALL {
+                        let next = position + face.direction();
+                        if next.cmplt(IVec3::ZERO).any()
+                            || next.cmpge(CHUNK_SIZE).any()
+                            || visited[index(next)]
+                            || !is_open(next)
+                        {
+                            continue;
+                        }
+                        visited[index(next)] = true;
+                        stack.push(next);
+                    }
+                }
+                for a in touched.iter() {
+                    for b in touched.iter() {
+                        connections.connect(*a, *b);
+                    }
+                }
+            }
+        }
+    }
+    connections
+}
+
+// The chunks that can be seen from start. connections returns None for chunks that are not loaded,
+// the walk does not continue through them. in_frustum decides if a chunk is inside the view.
+pub fn visible_chunks(
+    start: IVec3,
+    connections: impl Fn(IVec3) -> Option<ChunkConnections>,
+    in_frustum: impl Fn(IVec3) -> bool,
+) -> HashSet<IVec3> {
+    let mut visible = HashSet::from([start]);
+    // the chunk, the face it was entered through and every direction walked to get there
+    let mut queue = VecDeque::from([(start, None::<BlockFace>, Vec::<BlockFace>::new())]);
+    while let Some((position, entered, walked)) = queue.pop_front() {
+        let Some(current) = connections(position) else {
+            continue;
+        };
+        for face in BlockFace::ALL {
+            if walked.contains(&face.opposite()) {
+                continue;
+            }
+            if entered.is_some_and(|entered| !current.connects(entered, face)) {
+                continue;
+            }
+            let next = position + face.direction();
+            if visible.contains(&next) || connections(next).is_none() || !in_frustum(next) {
+                continue;
+            }
+            visible.insert(next);
+            let mut next_walked = walked.clone();
+            if !next_walked.contains(&face) {
+                next_walked.push(face);
+            }
+            queue.push_back((next, Some(face.opposite()), next_walked));
+        }
+    }
+    visible
+}
+
+// what cull_chunks did in the last frame, shown by the debug menu
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ChunkVisibilityStats {
+    pub loaded: usize,
+    pub outside_frustum: usize,
+    pub occluded: usize,
+    pub visible: usize,
+}
+
+pub fn chunk_bounds(position: IVec3) -> Aabb {
+    let origin = chunk_origin(position).as_vec3();
+    Aabb::from_min_max(origin, origin + CHUNK_SIZE.as_vec3())
+}
+
+pub fn cull_chunks(
+    camera_query: Query<(&Transform, &Frustum), With<Player>>,
+    chunk_entities: Res<ChunkEntities>,
+    connections_query: Query<Option<&ChunkConnections>, With<Chunk>>,
+    mut visibility_query: Query<&mut Visibility, With<Chunk>>,
+    mut stats: ResMut<ChunkVisibilityStats>,
+) {
+    let Ok((transform, frustum)) = camera_query.get_single() else {
+        return;
+    };
+    let in_frustum = |position: IVec3| {
+        frustum.intersects_obb(&chunk_bounds(position), &Affine3A::IDENTITY, true, true)
+    };
+    // chunks that were not meshed yet could connect anything
+    let connections = |position: IVec3| {
+        let entity = chunk_entities.0.get(&position)?;
+        Some(
+            connections_query
+                .get(*entity)
+                .ok()
+                .flatten()
+                .copied()
+                .unwrap_or(ChunkConnections::ALL),
+        )
+    };
+    let (camera_chunk, _) = world_to_chunk_position(transform.translation.floor().as_ivec3());
+    // outside of the loaded chunks there is no walk to start, only the frustum decides
+    let visible =
+        connections(camera_chunk).map(|_| visible_chunks(camera_chunk, connections, in_frustum));
+
+    let mut new_stats = ChunkVisibilityStats {
+        loaded: chunk_entities.0.len(),
+        ..default()
+    };
+    for (position, entity) in chunk_entities.0.iter() {
+        let shown = if !in_frustum(*position) {
+            new_stats.outside_frustum += 1;
+            false
+        } else if visible
+            .as_ref()
+            .is_some_and(|visible| !visible.contains(position))
+        {
+            new_stats.occluded += 1;
+            false
+        } else {
+            new_stats.visible += 1;
+            true
+        };
+        let Ok(mut visibility) = visibility_query.get_mut(*entity) else {
+            continue;
+        };
+        let target = if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != target {
+            *visibility = target;
+        }
+    }
+    // only a real change wakes up the debug menu
+    stats.set_if_neq(new_stats);
+}
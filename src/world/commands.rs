@@ -1,13 +1,40 @@
+use std::path::Path;
+
 use bevy::prelude::*;
 
-use crate::command_system::events::CommandDispatchEvent;
+use crate::{
+    command_system::{
+        arguments::{parse_file_name, parse_position},
+        builder_commands::{player_block_position, selection_size, Selections},
+        events::CommandDispatchEvent,
+    },
+    net::ClientPlayer,
+};
 
-use super::chunk::{Chunk, ChunkRadius, ChunkRegistry};
+use super::{
+    chunk::{get_block, Chunk, ChunkRadius, ChunkRegistry},
+    element::Element,
+    server::{
+        chunk::LoadedChunks,
+        edit::EditedChunks,
+        events::{BlockEditEvent, EditSource},
+        history::EditHistory,
+        interest::ChunkInterest,
+    },
+    shapes::{
+        cylinder_positions, parse_height, parse_hollow, parse_radius, region_positions,
+        replaced_blocks, sphere_positions, MAX_SHAPE_HEIGHT, MAX_SHAPE_RADIUS,
+    },
+    vox::{blocks_to_vox, load_vox, save_vox, vox_to_blocks, VoxMapping, VOX_DIRECTORY},
+};
 
 pub fn chunk_despawn_command(
     mut commands: Commands,
     mut chunk_registry: ResMut<ChunkRegistry>,
+    mut edited_chunks: ResMut<EditedChunks>,
+    mut loaded_chunks: ResMut<LoadedChunks>,
     chunk_query: Query<Entity, With<Chunk>>,
+    mut interest_query: Query<&mut ChunkInterest>,
     mut command_dispatch_event_reader: EventReader<CommandDispatchEvent>,
 ) {
     for event in command_dispatch_event_reader.read() {
@@ -17,6 +44,12 @@ pub fn chunk_despawn_command(
                 commands.entity(entity).despawn();
                 chunk_registry.chunks.clear();
             }
+            loaded_chunks.0.clear();
+            edited_chunks.clear();
+            // the chunks are generated again and resent to everyone
+            for mut interest in interest_query.iter_mut() {
+                interest.reset();
+            }
         }
     }
 }
@@ -34,3 +67,272 @@ pub fn chunk_radius_command(
         }
     }
 }
+
+// /vox paste <name> <x> <y> <z>
+// pastes every model of vox/<name>.vox with its minimum corner at the given position
+pub fn vox_paste_command(
+    vox_mapping: Res<VoxMapping>,
+    mut command_dispatch_event_reader: EventReader<CommandDispatchEvent>,
+    mut block_edit_event_writer: EventWriter<BlockEditEvent>,
+) {
+    for event in command_dispatch_event_reader.read() {
+        let parts: Vec<&str> = event.command.split_whitespace().collect();
+        if parts.len() != 6 || parts[0] != "/vox" || parts[1] != "paste" {
+            continue;
+        }
+        let (Some(name), Some(origin)) = (parse_file_name(parts[2]), parse_position(&parts[3..6]))
+        else {
+            warn!("usage: /vox paste <name> <x> <y> <z>");
+            continue;
+        };
+        let path = Path::new(VOX_DIRECTORY).join(format!("{}.vox", name));
+        match load_vox(&path) {
+            Ok(vox) => {
+                let changes = vox_to_blocks(&vox, &vox_mapping)
+                    .into_iter()
+                    .map(|(offset, element)| (origin + offset, element))
+                    .collect();
+                block_edit_event_writer.send(BlockEditEvent {
+                    changes,
+                    source: EditSource::from_client(event.client_id),
+                });
+            }
+            Err(error) => warn!("could not load {}: {}", path.display(), error),
+        }
+    }
+}
+
+// /vox export <name> <x1> <y1> <z1> <x2> <y2> <z2>
+// writes the region between the two corners (inclusive) to vox/<name>.vox
+pub fn vox_export_command(
+    vox_mapping: Res<VoxMapping>,
+    loaded_chunks: Res<LoadedChunks>,
+    chunk_query: Query<&Chunk>,
+    mut command_dispatch_event_reader: EventReader<CommandDispatchEvent>,
+) {
+    for event in command_dispatch_event_reader.read() {
+        let parts: Vec<&str> = event.command.split_whitespace().collect();
+        if parts.len() != 9 || parts[0] != "/vox" || parts[1] != "export" {
+            continue;
+        }
+        let (Some(name), Some(first), Some(second)) = (
+            parse_file_name(parts[2]),
+            parse_position(&parts[3..6]),
+            parse_position(&parts[6..9]),
+        ) else {
+            warn!("usage: /vox export <name> <x1> <y1> <z1> <x2> <y2> <z2>");
+            continue;
+        };
+        let (min, max) = (first.min(second), first.max(second));
+        let Some(size) = selection_size(min, max) else {
+            warn!("region from {} to {} is too large to export", min, max);
+            continue;
+        };
+        let chunks = loaded_chunks.in_region(min, max, &chunk_query);
+        let path = Path::new(VOX_DIRECTORY).join(format!("{}.vox", name));
+        let result = blocks_to_vox(size, &vox_mapping, |offset| {
+            get_block(&chunks, min + offset)
+        })
+        .and_then(|vox| save_vox(&path, &vox));
+        match result {
+            Ok(()) => info!("exported {} to {}", size, path.display()),
+            Err(error) => warn!("could not export {}: {}", path.display(), error),
+        }
+    }
+}
+
+// /undo [n] and /redo [n], reverts or reapplies the sender's last n edits
+pub fn history_command(
+    mut edit_history: ResMut<EditHistory>,
+    mut command_dispatch_event_reader: EventReader<CommandDispatchEvent>,
+    mut block_edit_event_writer: EventWriter<BlockEditEvent>,
+) {
+    for event in command_dispatch_event_reader.read() {
+        let parts: Vec<&str> = event.command.split_whitespace().collect();
+        if parts.is_empty() || (parts[0] != "/undo" && parts[0] != "/redo") {
+            continue;
+        }
+        let count = match parts.get(1) {
+            Some(count) => count.parse::<usize>().ok(),
+            None => Some(1),
+        };
+        let (Some(client_id), Some(count), 1..=2) = (event.client_id, count, parts.len()) else {
+            warn!("usage: {} [n]", parts[0]);
+            continue;
+        };
+        for _ in 0..count {
+            let (changes, source) = if parts[0] == "/undo" {
+                (
+                    edit_history.take_undo(client_id),
+                    EditSource::Undo(client_id),
+                )
+            } else {
+                (
+                    edit_history.take_redo(client_id),
+                    EditSource::Redo(client_id),
+                )
+            };
+            let Some(changes) = changes else {
+                info!("nothing left to {}", &parts[0][1..]);
+                break;
+            };
+            block_edit_event_writer.send(BlockEditEvent { changes, source });
+        }
+    }
+}
+
+// /fill <x1> <y1> <z1> <x2> <y2> <z2> <element>
+pub fn fill_command(
+    mut command_dispatch_event_reader: EventReader<CommandDispatchEvent>,
+    mut block_edit_event_writer: EventWriter<BlockEditEvent>,
+) {
+    for event in command_dispatch_event_reader.read() {
+        let parts: Vec<&str> = event.command.split_whitespace().collect();
+        if parts.is_empty() || parts[0] != "/fill" {
+            continue;
+        }
+        let (Some(first), Some(second), Some(element)) = (
+            parts.get(1..4).and_then(parse_position),
+            parts.get(4..7).and_then(parse_position),
+            parts.get(7).and_then(|name| Element::from_string(name)),
+        ) else {
+            warn!("usage: /fill <x1> <y1> <z1> <x2> <y2> <z2> <element>");
+            continue;
+        };
+        let min = first.min(second);
+        let max = first.max(second);
+        let Some(positions) = region_positions(min, max) else {
+            warn!("region from {} to {} is too large to fill", min, max);
+            continue;
+        };
+        block_edit_event_writer.send(BlockEditEvent {
+            changes: positions
+                .into_iter()
+                .map(|position| (position, element))
+                .collect(),
+            source: EditSource::from_client(event.client_id),
+        });
+    }
+}
+
+// /replace <from> <to>, replaces blocks inside the sender's /pos1 /pos2 selection
+pub fn replace_command(
+    selections: Res<Selections>,
+    loaded_chunks: Res<LoadedChunks>,
+    chunk_query: Query<&Chunk>,
+    mut command_dispatch_event_reader: EventReader<CommandDispatchEvent>,
+    mut block_edit_event_writer: EventWriter<BlockEditEvent>,
+) {
+    for event in command_dispatch_event_reader.read() {
+        let parts: Vec<&str> = event.command.split_whitespace().collect();
+        if parts.is_empty() || parts[0] != "/replace" {
+            continue;
+        }
+        let (Some(from), Some(to)) = (
+            parts.get(1).and_then(|name| Element::from_string(name)),
+            parts.get(2).and_then(|name| Element::from_string(name)),
+        ) else {
+            warn!("usage: /replace <from> <to>");
+            continue;
+        };
+        let Some((min, max)) = event
+            .client_id
+            .and_then(|client_id| selections.0.get(&client_id))
+            .and_then(|selection| selection.bounds())
+        else {
+            warn!("set both /pos1 and /pos2 before replacing");
+            continue;
+        };
+        if selection_size(min, max).is_none() {
+            warn!("selection from {} to {} is too large to replace", min, max);
+            continue;
+        }
+        let chunks = loaded_chunks.in_region(min, max, &chunk_query);
+        let Some(changes) = replaced_blocks(min, max, &chunks, from, to) else {
+            continue;
+        };
+        block_edit_event_writer.send(BlockEditEvent {
+            changes,
+            source: EditSource::from_client(event.client_id),
+        });
+    }
+}
+
+// /sphere <element> <radius> [hollow], centered on the player
+pub fn sphere_command(
+    player_query: Query<(&Transform, &ClientPlayer)>,
+    mut command_dispatch_event_reader: EventReader<CommandDispatchEvent>,
+    mut block_edit_event_writer: EventWriter<BlockEditEvent>,
+) {
+    for event in command_dispatch_event_reader.read() {
+        let parts: Vec<&str> = event.command.split_whitespace().collect();
+        if parts.is_empty() || parts[0] != "/sphere" {
+            continue;
+        }
+        let (Some(element), Some(radius), Some(hollow)) = (
+            parts.get(1).and_then(|name| Element::from_string(name)),
+            parts.get(2).and_then(|radius| parse_radius(radius)),
+            parse_hollow(parts.get(3..).unwrap_or_default()),
+        ) else {
+            warn!(
+                "usage: /sphere <element> <radius> [hollow], radius up to {}",
+                MAX_SHAPE_RADIUS
+            );
+            continue;
+        };
+        // commands from the server console have no player to stand on
+        let Some(center) = event
+            .client_id
+            .and_then(|client_id| player_block_position(&player_query, client_id))
+        else {
+            continue;
+        };
+        block_edit_event_writer.send(BlockEditEvent {
+            changes: sphere_positions(center, radius, hollow)
+                .into_iter()
+                .map(|position| (position, element))
+                .collect(),
+            source: EditSource::from_client(event.client_id),
+        });
+    }
+}
+
+// /cylinder <element> <radius> <height> [hollow], standing on the block the player is in
+pub fn cylinder_command(
+    player_query: Query<(&Transform, &ClientPlayer)>,
+    mut command_dispatch_event_reader: EventReader<CommandDispatchEvent>,
+    mut block_edit_event_writer: EventWriter<BlockEditEvent>,
+) {
+    for event in command_dispatch_event_reader.read() {
+        let parts: Vec<&str> = event.command.split_whitespace().collect();
+        if parts.is_empty() || parts[0] != "/cylinder" {
+            continue;
+        }
+        let (Some(element), Some(radius), Some(height), Some(hollow)) = (
+            parts.get(1).and_then(|name| Element::from_string(name)),
+            parts.get(2).and_then(|radius| parse_radius(radius)),
+            parts.get(3).and_then(|height| parse_height(height)),
+            parse_hollow(parts.get(4..).unwrap_or_default()),
+        ) else {
+            warn!(
+                "usage: /cylinder <element> <radius> <height> [hollow], radius up to {}, height up to {}",
+                MAX_SHAPE_RADIUS, MAX_SHAPE_HEIGHT
+            );
+            continue;
+        };
+        // commands from the server console have no player to stand on
+        let Some(base) = event
+            .client_id
+            .and_then(|client_id| player_block_position(&player_query, client_id))
+        else {
+            continue;
+        };
+        block_edit_event_writer.send(BlockEditEvent {
+            changes: cylinder_positions(base, radius, height, hollow)
+                .into_iter()
+                .map(|position| (position, element))
+                .collect(),
+            source: EditSource::from_client(event.client_id),
+        });
+    }
+}
@@ -1,11 +1,33 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+use super::{
+    block::BlockFace,
+    model::{block_model, BlockModel},
+};
+
+// every tint a block can have, packed chunk vertices only store the index into this list. leaves
+// are alpha tested, their holes come from the texture and not from the tint
+pub const TINTS: [[f32; 4]; 4] = [
+    [1.0, 1.0, 1.0, 1.0],
+    [0.9, 0.95, 1.0, 0.35],
+    [0.45, 0.75, 0.3, 1.0],
+    [0.6, 0.75, 1.0, 0.65],
+];
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Element {
     Air,
     Dirt,
     Grass,
     Stone,
+    Glass,
+    Leaves,
+    Water,
+    StoneSlab,
+    StoneStairs,
+    Fence,
+    Flower,
+    Torch,
 }
 
 impl Element {
@@ -15,7 +37,74 @@ impl Element {
             "dirt" => Some(Element::Dirt),
             "grass" => Some(Element::Grass),
             "stone" => Some(Element::Stone),
+            "glass" => Some(Element::Glass),
+            "leaves" => Some(Element::Leaves),
+            "water" => Some(Element::Water),
+            "stone_slab" => Some(Element::StoneSlab),
+            "stone_stairs" => Some(Element::StoneStairs),
+            "fence" => Some(Element::Fence),
+            "flower" => Some(Element::Flower),
+            "torch" => Some(Element::Torch),
             _ => None, // Handle the case where the string doesn't match any variant
         }
     }
+
+    // blocks that can be seen through, they are meshed separately from the opaque blocks and either
+    // alpha tested or drawn blended, see MeshPass
+    pub fn is_transparent(&self) -> bool {
+        matches!(
+            self,
+            Element::Glass | Element::Leaves | Element::Water | Element::Flower | Element::Torch
+        )
+    }
+
+    // the shape of blocks that are not a full cube, see model.rs
+    pub fn model(&self) -> Option<&'static BlockModel> {
+        block_model(*self)
+    }
+
+    // a solid full cube, the only kind of block that hides everything behind it
+    pub fn is_opaque(&self) -> bool {
+        *self != Element::Air && !self.is_transparent() && self.model().is_none()
+    }
+
+    // whether the side of the block is covered completely, hiding the face of the neighbour behind it
+    pub fn occludes(&self, side: BlockFace) -> bool {
+        match self.model() {
+            Some(model) => model.occludes.contains(&side),
+            None => self.is_opaque(),
+        }
+    }
+
+    // whether the connections of the model on side are added next to neighbour: blocks of the
+    // same kind connect to each other and to blocks that cover the side facing them
+    pub fn connects_to(&self, neighbour: Element, side: BlockFace) -> bool {
+        neighbour == *self || neighbour.occludes(side.opposite())
+    }
+
+    // whether the face of this block on side, towards neighbour, has to be drawn.
+    // faces behind transparent blocks stay visible, faces between two blocks of the same
+    // transparent element are hidden so water and glass look like one volume
+    pub fn shows_face_against(&self, neighbour: Element, side: BlockFace) -> bool {
+        let covered = neighbour.occludes(side.opposite());
+        match self {
+            Element::Air => false,
+            _ if self.is_transparent() => !covered && neighbour != *self,
+            _ => !covered,
+        }
+    }
+
+    // the colour the block texture is multiplied with, the alpha is how much of the block is seen
+    pub fn tint(&self) -> [f32; 4] {
+        TINTS[self.tint_index() as usize]
+    }
+
+    pub fn tint_index(&self) -> u32 {
+        match self {
+            Element::Glass => 1,
+            Element::Leaves => 2,
+            Element::Water => 3,
+            _ => 0,
+        }
+    }
 }
@@ -1,6 +1,11 @@
+pub mod atlas;
 pub mod block;
 pub mod chunk;
 pub mod client;
 pub(crate) mod commands;
 pub mod element;
+pub mod model;
 pub mod server;
+pub mod shapes;
+pub mod vox;
+pub mod wire;
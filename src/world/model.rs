@@ -0,0 +1,126 @@
+use std::{collections::HashMap, fmt, sync::OnceLock};
+
+use bevy::math::Vec3;
+use serde::Deserialize;
+
+use super::{
+    block::{BlockFace, BlockTexture},
+    element::Element,
+};
+
+// Block models give the blocks that are not a full cube their shape: slabs, stairs, fences, plants
+// and torches. a model is a list of boxes and planes measured in 1/16 of a block, together with
+// the sides of the block it covers completely, which hide the faces of the neighbours behind them.
+// connections are parts that are only added on the sides where the neighbour connects to the
+// block, see Element::connects_to, like the bars between two fence posts.
+// models have no facing: a block is only its element, so a model is never rotated and stairs
+// always climb towards north (+z), wherever and however they are placed.
+// the models live in assets/models/blocks.json and are compiled in, so meshing works the same on
+// the client, in the export tool and in tests without the asset server.
+
+const BLOCK_MODELS: &str = include_str!("../../assets/models/blocks.json");
+
+// the size of a block in model units
+pub const MODEL_UNITS: f32 = 16.0;
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelPart {
+    // an axis aligned box between the two corners
+    Box {
+        from: [f32; 3],
+        to: [f32; 3],
+        #[serde(default)]
+        texture: Option<BlockTexture>,
+    },
+    // a vertical quad seen from both sides, from the bottom of the from corner to the top of the
+    // to corner. two crossed planes make a plant
+    Plane {
+        from: [f32; 3],
+        to: [f32; 3],
+        #[serde(default)]
+        texture: Option<BlockTexture>,
+    },
+}
+
+impl ModelPart {
+    // the corners in block units
+    pub fn bounds(&self) -> (Vec3, Vec3) {
+        let (ModelPart::Box { from, to, .. } | ModelPart::Plane { from, to, .. }) = self;
+        (
+            Vec3::from(*from) / MODEL_UNITS,
+            Vec3::from(*to) / MODEL_UNITS,
+        )
+    }
+
+    // parts without a texture of their own use the textures of their block
+    pub fn texture(&self) -> Option<BlockTexture> {
+        let (ModelPart::Box { texture, .. } | ModelPart::Plane { texture, .. }) = self;
+        *texture
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct BlockModel {
+    pub parts: Vec<ModelPart>,
+    #[serde(default)]
+    pub occludes: Vec<BlockFace>,
+    #[serde(default)]
+    pub connections: HashMap<BlockFace, Vec<ModelPart>>,
+}
+
+impl BlockModel {
+    // the parts of a block that is connected on the sides for which connected is true, in the
+    // same order every time so meshes do not change between runs
+    pub fn parts(&self, connected: impl Fn(BlockFace) -> bool) -> impl Iterator<Item = &ModelPart> {
+        let connections = BlockFace::ALL
+            .into_iter()
+            .filter(move |side| connected(*side))
+            .filter_map(|side| self.connections.get(&side))
+            .flatten();
+        self.parts.iter().chain(connections)
+    }
+}
+
+#[derive(Debug)]
+pub enum ModelError {
+    Json(serde_json::Error),
+    UnknownElement(String),
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::Json(error) => write!(f, "invalid block models: {}", error),
+            ModelError::UnknownElement(name) => write!(f, "model for unknown element {}", name),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+// the models indexed by element, so looking one up while meshing is a single index
+pub fn parse_block_models(source: &str) -> Result<Vec<Option<BlockModel>>, ModelError> {
+    let definitions: HashMap<String, BlockModel> =
+        serde_json::from_str(source).map_err(ModelError::Json)?;
+    let mut models = Vec::new();
+    for (name, model) in definitions {
+        let element = Element::from_string(&name).ok_or(ModelError::UnknownElement(name))?;
+        let index = element as usize;
+        if models.len() <= index {
+            models.resize(index + 1, None);
+        }
+        models[index] = Some(model);
+    }
+    Ok(models)
+}
+
+pub fn block_model(element: Element) -> Option<&'static BlockModel> {
+    static MODELS: OnceLock<Vec<Option<BlockModel>>> = OnceLock::new();
+    MODELS
+        .get_or_init(|| {
+            parse_block_models(BLOCK_MODELS).unwrap_or_else(|error| panic!("{}", error))
+        })
+        .get(element as usize)?
+        .as_ref()
+}
@@ -1,64 +1,21 @@
+use std::collections::HashMap;
+
 use bevy::prelude::*;
 use noise::{NoiseFn, Perlin};
 
-use crate::{
-    player::events::PlayerSpawnEvent,
-    world::{
-        block::Block,
-        chunk::{
-            Chunk, ChunkQueue, ChunkRadius, ChunkRegistry, CHUNK_DEPTH, CHUNK_HEIGHT, CHUNK_VERT,
-            CHUNK_WIDTH,
-        },
-        element::Element,
+use crate::world::{
+    block::Block,
+    chunk::{
+        world_to_chunk_position, Chunk, ChunkQueue, ChunkRegistry, CHUNK_DEPTH, CHUNK_HEIGHT,
+        CHUNK_VERT, CHUNK_WIDTH,
     },
+    element::Element,
 };
 
-use super::events::{ChunkCreatedEvent, ChunkEnterEvent, PrepareChunkLoadEvent};
-
-pub fn chunk_enter_listener(
-    mut chunk_registry: ResMut<ChunkRegistry>,
-    chunk_radius: Res<ChunkRadius>,
-    mut chunk_queue: ResMut<ChunkQueue>,
-    mut chunk_enter_event_reader: EventReader<ChunkEnterEvent>,
-) {
-    for event in chunk_enter_event_reader.read() {
-        println!("Server: entered new chunk!");
-        let chunks = get_surrounding_chunks(
-            event.chunk_coords[0] as i32,
-            event.chunk_coords[1] as i32,
-            event.chunk_coords[2] as i32,
-            chunk_radius.radius,
-        );
-        for chunk in chunks.iter() {
-            if !chunk_registry.chunks.contains(chunk) {
-                chunk_registry.chunks.push(*chunk);
-                chunk_queue.chunks.push(*chunk);
-            }
-        }
-    }
-}
-
-pub fn setup_initial_chunks(
-    mut chunk_registry: ResMut<ChunkRegistry>,
-    chunk_radius: Res<ChunkRadius>,
-    mut chunk_queue: ResMut<ChunkQueue>,
-    mut player_spawned_event: EventReader<PlayerSpawnEvent>,
-) {
-    for event in player_spawned_event.read() {
-        let chunks = get_surrounding_chunks(
-            convert_to_chunk_location(event.position.x),
-            convert_to_chunk_location(event.position.y),
-            convert_to_chunk_location(event.position.z),
-            chunk_radius.radius,
-        );
-        for chunk in chunks.iter() {
-            if !chunk_registry.chunks.contains(chunk) {
-                chunk_registry.chunks.push(*chunk);
-                chunk_queue.chunks.push(*chunk);
-            }
-        }
-    }
-}
+use super::{
+    edit::EditedChunks,
+    events::{ChunkCreatedEvent, PrepareChunkLoadEvent},
+};
 
 // Takes in the chunk_x and chunk_z values to find the chunks
 pub fn get_surrounding_chunks(x: i32, _y: i32, z: i32, radius: i32) -> Vec<Vec3> {
@@ -84,10 +41,6 @@ pub fn get_surrounding_chunks(x: i32, _y: i32, z: i32, radius: i32) -> Vec<Vec3>
     chunks
 }
 
-fn convert_to_chunk_location(location: f32) -> i32 {
-    (location / 16.0).floor() as i32
-}
-
 pub fn generate_noise(x: f32, y: f32, z: f32) -> f64 {
     let perlin = Perlin::new(1);
     perlin.get([x as f64 * 0.1, y as f64 * 0.1, z as f64 * 0.1])
@@ -105,6 +58,14 @@ fn get_random_element(y: usize, noise_value: f64) -> Element {
     }
 }
 
+// the element generate_chunk puts at a world position
+pub fn generated_element(position: IVec3) -> Element {
+    get_random_element(
+        position.y as usize,
+        generate_noise(position.x as f32, position.y as f32, position.z as f32),
+    )
+}
+
 pub fn generate_chunk(x: f32, y: f32, z: f32) -> Chunk {
     let mut chunk = Chunk {
         chunk_x: x,
@@ -112,17 +73,12 @@ pub fn generate_chunk(x: f32, y: f32, z: f32) -> Chunk {
         chunk_z: z,
         blocks: [[[Block::default(); CHUNK_WIDTH]; CHUNK_HEIGHT]; CHUNK_DEPTH],
     };
+    let origin = chunk.world_origin();
     for dx in 0..CHUNK_WIDTH {
         for dy in 0..CHUNK_HEIGHT {
             for dz in 0..CHUNK_DEPTH {
-                chunk.blocks[dx][dy][dz] = Block::new(get_random_element(
-                    y as usize * 15 + dy,
-                    generate_noise(
-                        x * 15.0 + dx as f32,
-                        y * 15.0 + dy as f32,
-                        z * 15.0 + dz as f32,
-                    ),
-                ));
+                let position = origin + IVec3::new(dx as i32, dy as i32, dz as i32);
+                chunk.blocks[dx][dy][dz] = Block::new(generated_element(position));
             }
         }
     }
@@ -143,29 +99,65 @@ pub fn load_chunk_from_queue(
     }
 }
 
+// the entity of every loaded chunk by its position, kept up to date by load_chunk so systems do not
+// have to look through all chunks
+#[derive(Resource, Default)]
+pub struct LoadedChunks(pub HashMap<IVec3, Entity>);
+
+impl LoadedChunks {
+    // the loaded chunks overlapping the region between two inclusive corners, for get_block
+    pub fn in_region<'a>(
+        &self,
+        min: IVec3,
+        max: IVec3,
+        chunk_query: &'a Query<&Chunk>,
+    ) -> HashMap<IVec3, &'a Chunk> {
+        let (min, _) = world_to_chunk_position(min);
+        let (max, _) = world_to_chunk_position(max);
+        let mut chunks = HashMap::new();
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    let position = IVec3::new(x, y, z);
+                    let chunk = self
+                        .0
+                        .get(&position)
+                        .and_then(|entity| chunk_query.get(*entity).ok());
+                    if let Some(chunk) = chunk {
+                        chunks.insert(position, chunk);
+                    }
+                }
+            }
+        }
+        chunks
+    }
+}
+
 // this needs a better function name
 pub fn load_chunk(
     mut commands: Commands,
     mut prepare_chunk_load_event_reader: EventReader<PrepareChunkLoadEvent>,
     mut chunk_created_event_write: EventWriter<ChunkCreatedEvent>,
+    mut edited_chunks: ResMut<EditedChunks>,
+    mut loaded_chunks: ResMut<LoadedChunks>,
     chunk_registry: ResMut<ChunkRegistry>,
 ) {
     for event in prepare_chunk_load_event_reader.read() {
+        // edits made while the chunk was not loaded, see apply_block_edits
+        let mut chunk = event.chunk;
+        edited_chunks.apply(&mut chunk);
         let chunk_transform = commands.spawn((
-            event.chunk,
+            chunk,
             TransformBundle {
-                local: {
-                    Transform::from_xyz(
-                        event.chunk.chunk_x,
-                        event.chunk.chunk_y,
-                        event.chunk.chunk_z,
-                    )
-                },
+                local: Transform::from_xyz(chunk.chunk_x, chunk.chunk_y, chunk.chunk_z),
                 ..Default::default()
             },
         ));
+        loaded_chunks
+            .0
+            .insert(chunk.position(), chunk_transform.id());
         chunk_created_event_write.send(ChunkCreatedEvent {
-            chunk: event.chunk,
+            chunk,
             chunk_id: chunk_transform.id(),
             registry_size: chunk_registry.chunks.len(),
         });
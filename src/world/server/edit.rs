@@ -0,0 +1,222 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+
+use crate::world::{
+    block::Block,
+    chunk::{world_to_chunk_position, Chunk, CHUNK_VERT},
+    element::Element,
+};
+
+use super::{
+    chunk::{generated_element, LoadedChunks},
+    events::{BlockEditEvent, ChunkUpdatedEvent},
+    history::{BlockChange, EditHistory},
+};
+
+// default memory budget of the edits waiting for their chunks to load
+pub const MAX_EDITED_BYTES: usize = 64 * 1024 * 1024;
+
+// what a single waiting edit costs, roughly
+const EDITED_BLOCK_BYTES: usize = std::mem::size_of::<([usize; 3], Element)>();
+
+// Edits to chunks that are not loaded. only the changed blocks are kept, by chunk and local position,
+// and load_chunk applies them on top of the generated chunk. what an edit replaced is read from the
+// generator block by block, so nothing is generated up front. edits past max_bytes are dropped.
+// apply_block_edits runs before load_chunk, so a chunk spawned the same frame is covered.
+// loaded chunks that differ from the generator are remembered as modified, their changed blocks
+// are stored here again when they are unloaded, see unload_unwanted_chunks.
+#[derive(Resource)]
+pub struct EditedChunks {
+    chunks: HashMap<IVec3, HashMap<[usize; 3], Element>>,
+    modified: HashSet<IVec3>,
+    bytes: usize,
+    pub max_bytes: usize,
+}
+
+impl Default for EditedChunks {
+    fn default() -> Self {
+        Self {
+            chunks: HashMap::new(),
+            modified: HashSet::new(),
+            bytes: 0,
+            max_bytes: MAX_EDITED_BYTES,
+        }
+    }
+}
+
+impl EditedChunks {
+    pub fn element(&self, world_position: IVec3) -> Element {
+        let (chunk_position, local) = world_to_chunk_position(world_position);
+        self.chunks
+            .get(&chunk_position)
+            .and_then(|blocks| blocks.get(&local))
+            .copied()
+            .unwrap_or_else(|| generated_element(world_position))
+    }
+
+    // false if the edit does not fit into the budget
+    pub fn set(&mut self, world_position: IVec3, element: Element) -> bool {
+        let (chunk_position, local) = world_to_chunk_position(world_position);
+        // a block set back to what the generator makes needs no edit
+        if element == generated_element(world_position) {
+            if let Some(blocks) = self.chunks.get_mut(&chunk_position) {
+                if blocks.remove(&local).is_some() {
+                    self.bytes -= EDITED_BLOCK_BYTES;
+                }
+                if blocks.is_empty() {
+                    self.chunks.remove(&chunk_position);
+                }
+            }
+            return true;
+        }
+        let blocks = self.chunks.entry(chunk_position).or_default();
+        if !blocks.contains_key(&local) {
+            if self.bytes + EDITED_BLOCK_BYTES > self.max_bytes {
+                if blocks.is_empty() {
+                    self.chunks.remove(&chunk_position);
+                }
+                return false;
+            }
+            self.bytes += EDITED_BLOCK_BYTES;
+        }
+        blocks.insert(local, element);
+        true
+    }
+
+    // writes the edits of a chunk that is being loaded into it and forgets them
+    pub fn apply(&mut self, chunk: &mut Chunk) {
+        let Some(blocks) = self.chunks.remove(&chunk.position()) else {
+            return;
+        };
+        self.bytes -= blocks.len() * EDITED_BLOCK_BYTES;
+        for ([x, y, z], element) in blocks {
+            chunk.blocks[x][y][z] = Block::new(element);
+        }
+        self.modified.insert(chunk.position());
+    }
+
+    // keeps the changed blocks of a loaded chunk that is about to be unloaded. false if they do not
+    // fit into the budget, the chunk has to stay loaded then
+    pub fn store(&mut self, chunk: &Chunk) -> bool {
+        let position = chunk.position();
+        if !self.modified.contains(&position) {
+            return true;
+        }
+        let origin = chunk.world_origin();
+        let mut changed = HashMap::new();
+        for (x, plane) in chunk.blocks.iter().enumerate() {
+            for (y, column) in plane.iter().enumerate() {
+                for (z, block) in column.iter().enumerate() {
+                    let local = IVec3::new(x as i32, y as i32, z as i32);
+                    if block.element != generated_element(origin + local) {
+                        changed.insert([x, y, z], block.element);
+                    }
+                }
+            }
+        }
+        let bytes = changed.len() * EDITED_BLOCK_BYTES;
+        if self.bytes + bytes > self.max_bytes {
+            return false;
+        }
+        self.modified.remove(&position);
+        if !changed.is_empty() {
+            self.bytes += bytes;
+            self.chunks.insert(position, changed);
+        }
+        true
+    }
+
+    pub fn mark_modified(&mut self, position: IVec3) {
+        self.modified.insert(position);
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+        self.modified.clear();
+        self.bytes = 0;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+pub fn apply_block_edits(
+    mut edit_history: ResMut<EditHistory>,
+    mut edited_chunks: ResMut<EditedChunks>,
+    loaded_chunks: Res<LoadedChunks>,
+    mut block_edit_event_reader: EventReader<BlockEditEvent>,
+    mut chunk_updated_event_writer: EventWriter<ChunkUpdatedEvent>,
+    mut chunk_query: Query<(Entity, &mut Chunk)>,
+) {
+    if block_edit_event_reader.is_empty() {
+        return;
+    }
+    // every chunk touched this frame is only resent once, chunks where nothing
+    // actually changed do not need to be remeshed or resent at all
+    let mut updated_chunks: HashSet<Entity> = HashSet::new();
+
+    for event in block_edit_event_reader.read() {
+        let mut operation = Vec::new();
+        let (mut outside, mut dropped) = (0, 0);
+        for (world_position, element) in event.changes.iter() {
+            let (chunk_position, [x, y, z]) = world_to_chunk_position(*world_position);
+            // no chunk is ever loaded above or below the world
+            if !(0..CHUNK_VERT).contains(&chunk_position.y) {
+                outside += 1;
+                continue;
+            }
+            let loaded = loaded_chunks
+                .0
+                .get(&chunk_position)
+                .and_then(|entity| chunk_query.get_mut(*entity).ok());
+            let old = match loaded.as_ref() {
+                Some((_, chunk)) => chunk.blocks[x][y][z].element,
+                None => edited_chunks.element(*world_position),
+            };
+            if old == *element {
+                continue;
+            }
+            match loaded {
+                Some((entity, mut chunk)) => {
+                    chunk.blocks[x][y][z] = Block::new(*element);
+                    updated_chunks.insert(entity);
+                    edited_chunks.mark_modified(chunk_position);
+                }
+                // unloaded chunks are sent with the edit once they are loaded
+                None => {
+                    if !edited_chunks.set(*world_position, *element) {
+                        dropped += 1;
+                        continue;
+                    }
+                }
+            }
+            operation.push(BlockChange {
+                position: *world_position,
+                old,
+                new: *element,
+            });
+        }
+        if outside > 0 {
+            warn!("skipped {} blocks outside of the world", outside);
+        }
+        if dropped > 0 {
+            warn!(
+                "dropped {} blocks, too many edits are waiting for their chunks to load",
+                dropped
+            );
+        }
+        edit_history.record(event.source, operation);
+    }
+
+    for entity in updated_chunks {
+        if let Ok((_, chunk)) = chunk_query.get(entity) {
+            chunk_updated_event_writer.send(ChunkUpdatedEvent { chunk: *chunk });
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use bevy::prelude::*;
+use bevy_renet::renet::ClientId;
 
-use crate::world::chunk::Chunk;
+use crate::world::{chunk::Chunk, element::Element};
 
 #[derive(Event)]
 pub struct ChunkCreatedEvent {
@@ -10,11 +11,38 @@ pub struct ChunkCreatedEvent {
 }
 
 #[derive(Event)]
-pub struct ChunkEnterEvent {
-    pub chunk_coords: Vec3,
+pub struct PrepareChunkLoadEvent {
+    pub chunk: Chunk,
 }
 
+// a batch of block changes in world coordinates, every chunk touched by the batch is resent once.
+// each event is one operation in the edit history of the player that caused it.
 #[derive(Event)]
-pub struct PrepareChunkLoadEvent {
+pub struct BlockEditEvent {
+    pub changes: Vec<(IVec3, Element)>,
+    pub source: EditSource,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditSource {
+    // edits without a player are not recorded
+    Server,
+    Player(ClientId),
+    // the history already moved these when they were taken, see EditHistory
+    Undo(ClientId),
+    Redo(ClientId),
+}
+
+impl EditSource {
+    pub fn from_client(client_id: Option<ClientId>) -> Self {
+        match client_id {
+            Some(client_id) => EditSource::Player(client_id),
+            None => EditSource::Server,
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct ChunkUpdatedEvent {
     pub chunk: Chunk,
 }
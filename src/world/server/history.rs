@@ -0,0 +1,122 @@
+use std::collections::{HashMap, VecDeque};
+
+use bevy::prelude::*;
+use bevy_renet::renet::ClientId;
+
+use crate::world::element::Element;
+
+use super::events::EditSource;
+
+// default memory budget of a single player's undo and redo history
+pub const MAX_HISTORY_BYTES: usize = 16 * 1024 * 1024;
+
+// positions are stored instead of chunk entities so the history still applies after
+// the chunk was unloaded and generated again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockChange {
+    pub position: IVec3,
+    pub old: Element,
+    pub new: Element,
+}
+
+// every block changed by a single command
+pub type EditOperation = Vec<BlockChange>;
+
+#[derive(Default)]
+struct PlayerHistory {
+    undo: VecDeque<EditOperation>,
+    redo: Vec<EditOperation>,
+    bytes: usize,
+}
+
+#[derive(Resource)]
+pub struct EditHistory {
+    players: HashMap<ClientId, PlayerHistory>,
+    pub max_bytes: usize,
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        Self {
+            players: HashMap::new(),
+            max_bytes: MAX_HISTORY_BYTES,
+        }
+    }
+}
+
+fn operation_bytes(operation: &EditOperation) -> usize {
+    operation.len() * std::mem::size_of::<BlockChange>()
+}
+
+impl EditHistory {
+    // undo and redo move operations between the two stacks when they are taken, so a /redo right
+    // after an /undo finds the operation even before the undo was applied
+    pub fn record(&mut self, source: EditSource, operation: EditOperation) {
+        let EditSource::Player(client_id) = source else {
+            return;
+        };
+        if operation.is_empty() {
+            return;
+        }
+        // a new edit makes the redo history meaningless
+        let history = self.players.entry(client_id).or_default();
+        let redo_bytes: usize = history.redo.iter().map(operation_bytes).sum();
+        history.bytes -= redo_bytes;
+        history.redo.clear();
+
+        let bytes = operation_bytes(&operation);
+        if bytes > self.max_bytes {
+            warn!(
+                "edit of {} blocks is too large to be undone",
+                operation.len()
+            );
+            return;
+        }
+        history.bytes += bytes;
+        history.undo.push_back(operation);
+        // forget the oldest operations first
+        while history.bytes > self.max_bytes {
+            let Some(dropped) = history.undo.pop_front() else {
+                break;
+            };
+            history.bytes -= operation_bytes(&dropped);
+        }
+    }
+
+    // the changes that revert the latest operation, which can be redone afterwards
+    pub fn take_undo(&mut self, client_id: ClientId) -> Option<Vec<(IVec3, Element)>> {
+        let history = self.players.get_mut(&client_id)?;
+        let operation = history.undo.pop_back()?;
+        let changes = operation
+            .iter()
+            .rev()
+            .map(|change| (change.position, change.old))
+            .collect();
+        history.redo.push(operation);
+        Some(changes)
+    }
+
+    // the changes that reapply the latest undone operation, which can be undone again
+    pub fn take_redo(&mut self, client_id: ClientId) -> Option<Vec<(IVec3, Element)>> {
+        let history = self.players.get_mut(&client_id)?;
+        let operation = history.redo.pop()?;
+        let changes = operation
+            .iter()
+            .map(|change| (change.position, change.new))
+            .collect();
+        history.undo.push_back(operation);
+        Some(changes)
+    }
+
+    pub fn undo_len(&self, client_id: ClientId) -> usize {
+        self.players
+            .get(&client_id)
+            .map_or(0, |history| history.undo.len())
+    }
+
+    pub fn redo_len(&self, client_id: ClientId) -> usize {
+        self.players
+            .get(&client_id)
+            .map_or(0, |history| history.redo.len())
+    }
+}
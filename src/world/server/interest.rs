@@ -0,0 +1,212 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+
+use crate::{
+    net::{send_to_client, ClientPlayer, ServerMessage},
+    player::server::handshake::ConnectedPlayers,
+    world::{
+        chunk::{Chunk, ChunkQueue, ChunkRegistry, CHUNK_SIZE},
+        wire::ChunkTransferStats,
+    },
+};
+
+use super::{
+    chunk::{get_surrounding_chunks, LoadedChunks},
+    edit::EditedChunks,
+    server_out::EncodedChunk,
+};
+
+// Every client is only sent the chunks within its view distance. the server remembers which chunks
+// went to which client, sends the missing ones closest first once they are generated and tells the
+// client to forget the ones its player walked away from. the interest lives on the player entity,
+// so a client that reconnects gets a new player and is sent everything again.
+// whenever a player moves, the server itself unloads the chunks no player sees any more.
+
+// how many chunks one client is sent per frame
+pub const CHUNKS_PER_FRAME: usize = 16;
+
+// the chunk a world position is in
+pub fn chunk_at(position: Vec3) -> IVec3 {
+    (position / CHUNK_SIZE.as_vec3()).floor().as_ivec3()
+}
+
+// every chunk a player in the given chunk sees
+pub fn chunks_in_view(center: IVec3, view_distance: i32) -> HashSet<IVec3> {
+    get_surrounding_chunks(center.x, center.y, center.z, view_distance)
+        .into_iter()
+        .map(|chunk| chunk.as_ivec3())
+        .collect()
+}
+
+#[derive(Component, Default, Debug)]
+pub struct ChunkInterest {
+    center: Option<IVec3>,
+    view_distance: i32,
+    wanted: HashSet<IVec3>,
+    sent: HashSet<IVec3>,
+}
+
+impl ChunkInterest {
+    // moves the area the client sees. none if it did not change, otherwise the chunks the
+    // client was sent that are out of view now
+    pub fn update(&mut self, center: IVec3, view_distance: i32) -> Option<Vec<IVec3>> {
+        if self.center == Some(center) && self.view_distance == view_distance {
+            return None;
+        }
+        self.center = Some(center);
+        self.view_distance = view_distance;
+        self.wanted = chunks_in_view(center, view_distance);
+        let mut unloaded: Vec<IVec3> = self.sent.difference(&self.wanted).copied().collect();
+        for position in unloaded.iter() {
+            self.sent.remove(position);
+        }
+        unloaded.sort_by_key(|position| position.to_array());
+        Some(unloaded)
+    }
+
+    pub fn wanted(&self) -> impl Iterator<Item = &IVec3> {
+        self.wanted.iter()
+    }
+
+    // the chunks in view the client does not have, closest to its player first
+    pub fn missing(&self) -> Vec<IVec3> {
+        let center = self.center.unwrap_or_default();
+        let mut missing: Vec<IVec3> = self.wanted.difference(&self.sent).copied().collect();
+        missing.sort_by_key(|position| {
+            let offset = *position - center;
+            (
+                offset.x.pow(2) + offset.z.pow(2),
+                offset.y.abs(),
+                position.to_array(),
+            )
+        });
+        missing
+    }
+
+    pub fn mark_sent(&mut self, position: IVec3) {
+        self.sent.insert(position);
+    }
+
+    pub fn has_sent(&self, position: IVec3) -> bool {
+        self.sent.contains(&position)
+    }
+
+    // forgets everything, the chunks in view are requested and sent again
+    pub fn reset(&mut self) {
+        self.center = None;
+        self.sent.clear();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_chunk_interest(
+    mut commands: Commands,
+    mut server: ResMut<RenetServer>,
+    connected_players: Res<ConnectedPlayers>,
+    mut stats: ResMut<ChunkTransferStats>,
+    mut chunk_registry: ResMut<ChunkRegistry>,
+    mut chunk_queue: ResMut<ChunkQueue>,
+    mut loaded_chunks: ResMut<LoadedChunks>,
+    mut edited_chunks: ResMut<EditedChunks>,
+    mut player_query: Query<(&ClientPlayer, &Transform, &mut ChunkInterest)>,
+    chunk_query: Query<&Chunk>,
+) {
+    let mut encoded: HashMap<IVec3, Option<EncodedChunk>> = HashMap::new();
+    let mut moved = false;
+
+    for (player, transform, mut interest) in player_query.iter_mut() {
+        let Some(profile) = connected_players.0.get(&player.id) else {
+            continue;
+        };
+        let center = chunk_at(transform.translation);
+        if let Some(unloaded) = interest.update(center, profile.view_distance) {
+            moved = true;
+            if !unloaded.is_empty() {
+                send_to_client(
+                    &mut server,
+                    player.id,
+                    &ServerMessage::UnloadChunks(unloaded),
+                );
+            }
+            // chunks nobody asked for yet are generated, see load_chunk_from_queue
+            for position in interest.wanted() {
+                if chunk_registry.chunks.insert(*position) {
+                    chunk_queue.chunks.push(position.as_vec3());
+                }
+            }
+        }
+
+        let mut sent = 0;
+        for position in interest.missing() {
+            if sent == CHUNKS_PER_FRAME {
+                break;
+            }
+            // still being generated
+            let Some(chunk) = loaded_chunks
+                .0
+                .get(&position)
+                .and_then(|entity| chunk_query.get(*entity).ok())
+            else {
+                continue;
+            };
+            let entry = encoded
+                .entry(position)
+                .or_insert_with(|| EncodedChunk::new(chunk));
+            if let Some(chunk) = entry {
+                chunk.send(&mut server, &mut stats, player);
+            }
+            interest.mark_sent(position);
+            sent += 1;
+        }
+    }
+    if moved {
+        let wanted: HashSet<IVec3> = player_query
+            .iter()
+            .flat_map(|(_, _, interest)| interest.wanted().copied())
+            .collect();
+        unload_unwanted_chunks(
+            &mut commands,
+            &wanted,
+            &mut chunk_registry,
+            &mut chunk_queue,
+            &mut loaded_chunks,
+            &mut edited_chunks,
+            &chunk_query,
+        );
+    }
+}
+
+// Despawns the chunks no player sees and forgets the queued ones, they are generated again once
+// someone comes back. chunks with edits keep their changed blocks in EditedChunks, if those do not
+// fit the chunk stays loaded.
+fn unload_unwanted_chunks(
+    commands: &mut Commands,
+    wanted: &HashSet<IVec3>,
+    chunk_registry: &mut ChunkRegistry,
+    chunk_queue: &mut ChunkQueue,
+    loaded_chunks: &mut LoadedChunks,
+    edited_chunks: &mut EditedChunks,
+    chunk_query: &Query<&Chunk>,
+) {
+    chunk_queue
+        .chunks
+        .retain(|position| wanted.contains(&position.as_ivec3()));
+    loaded_chunks.0.retain(|position, entity| {
+        if wanted.contains(position) {
+            return true;
+        }
+        if let Ok(chunk) = chunk_query.get(*entity) {
+            if !edited_chunks.store(chunk) {
+                return true;
+            }
+        }
+        commands.entity(*entity).despawn();
+        false
+    });
+    // what is left in the queue is wanted
+    chunk_registry
+        .chunks
+        .retain(|position| wanted.contains(position) || loaded_chunks.0.contains_key(position));
+}
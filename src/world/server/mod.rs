@@ -1,4 +1,7 @@
-mod chunk;
+pub mod chunk;
+pub mod edit;
 pub mod events;
+pub mod history;
+pub mod interest;
 pub mod plugin;
 mod server_out;
@@ -3,38 +3,64 @@ use bevy::prelude::*;
 use crate::{
     player::{client::events::PlayerMoveEvent, events::PlayerSpawnEvent},
     world::{
-        chunk::{player_move_event_listener, ChunkQueue, ChunkRadius, ChunkRegistry},
-        commands::{chunk_despawn_command, chunk_radius_command},
+        chunk::{ChunkQueue, ChunkRadius, ChunkRegistry, DEFAULT_VIEW_DISTANCE},
+        commands::{
+            chunk_despawn_command, chunk_radius_command, cylinder_command, fill_command,
+            history_command, replace_command, sphere_command, vox_export_command,
+            vox_paste_command,
+        },
+        vox::{VoxMapping, VOX_MAPPING_FILE},
+        wire::ChunkTransferStats,
     },
 };
 
 use super::{
-    chunk::{chunk_enter_listener, load_chunk, load_chunk_from_queue, setup_initial_chunks},
-    events::{ChunkCreatedEvent, ChunkEnterEvent, PrepareChunkLoadEvent},
-    server_out::send_chunk_to_client,
+    chunk::{load_chunk, load_chunk_from_queue, LoadedChunks},
+    edit::{apply_block_edits, EditedChunks},
+    events::{BlockEditEvent, ChunkCreatedEvent, ChunkUpdatedEvent, PrepareChunkLoadEvent},
+    history::EditHistory,
+    interest::update_chunk_interest,
+    server_out::{log_chunk_transfer_stats, send_updated_chunk_to_client, ChunkTransferLogTimer},
 };
 
 pub struct ServerWorldPlugin;
 
 impl Plugin for ServerWorldPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(ChunkRegistry {
-            chunks: Vec::<Vec3>::new(),
-        })
-        .insert_resource(ChunkRadius { radius: 3 })
-        .insert_resource(ChunkQueue { chunks: Vec::new() })
-        .add_event::<ChunkCreatedEvent>()
-        .add_event::<ChunkEnterEvent>()
-        .add_event::<PrepareChunkLoadEvent>()
-        .add_event::<PlayerMoveEvent>()
-        .add_event::<PlayerSpawnEvent>()
-        .add_systems(Update, load_chunk)
-        .add_systems(Update, load_chunk_from_queue)
-        .add_systems(Update, chunk_enter_listener)
-        .add_systems(Update, player_move_event_listener)
-        .add_systems(Update, setup_initial_chunks)
-        .add_systems(Update, chunk_despawn_command)
-        .add_systems(Update, chunk_radius_command)
-        .add_systems(Update, send_chunk_to_client);
+        app.init_resource::<ChunkRegistry>()
+            .insert_resource(ChunkRadius {
+                radius: DEFAULT_VIEW_DISTANCE,
+            })
+            .insert_resource(ChunkQueue { chunks: Vec::new() })
+            .insert_resource(VoxMapping::load_or_default(VOX_MAPPING_FILE))
+            .init_resource::<EditHistory>()
+            .init_resource::<EditedChunks>()
+            .init_resource::<LoadedChunks>()
+            .init_resource::<ChunkTransferStats>()
+            .insert_resource(ChunkTransferLogTimer(Timer::from_seconds(
+                10.0,
+                TimerMode::Repeating,
+            )))
+            .add_event::<BlockEditEvent>()
+            .add_event::<ChunkCreatedEvent>()
+            .add_event::<ChunkUpdatedEvent>()
+            .add_event::<PrepareChunkLoadEvent>()
+            .add_event::<PlayerMoveEvent>()
+            .add_event::<PlayerSpawnEvent>()
+            .add_systems(Update, load_chunk.after(apply_block_edits))
+            .add_systems(Update, load_chunk_from_queue)
+            .add_systems(Update, chunk_despawn_command)
+            .add_systems(Update, chunk_radius_command)
+            .add_systems(Update, vox_paste_command)
+            .add_systems(Update, vox_export_command)
+            .add_systems(Update, fill_command)
+            .add_systems(Update, replace_command)
+            .add_systems(Update, sphere_command)
+            .add_systems(Update, cylinder_command)
+            .add_systems(Update, history_command)
+            .add_systems(Update, apply_block_edits)
+            .add_systems(Update, update_chunk_interest)
+            .add_systems(Update, send_updated_chunk_to_client)
+            .add_systems(Update, log_chunk_transfer_stats);
     }
 }
@@ -1,19 +1,94 @@
 use bevy::prelude::*;
-use bevy_renet::renet::RenetServer;
+use bevy_renet::renet::{Bytes, RenetServer};
 
-use crate::{net::ServerChannel, world::chunk::Chunk};
+use crate::{
+    net::{ClientPlayer, ServerChannel, ServerMessage},
+    world::{
+        chunk::Chunk,
+        wire::{raw_chunk_size, ChunkPayload, ChunkTransferStats},
+    },
+};
 
-use super::events::ChunkCreatedEvent;
+use super::{events::ChunkUpdatedEvent, interest::ChunkInterest};
 
-pub fn send_chunk_to_client(
-    mut chunk_created_event_reader: EventReader<ChunkCreatedEvent>,
+// how often the server logs what the chunk wire format saved
+#[derive(Resource)]
+pub struct ChunkTransferLogTimer(pub Timer);
+
+// a chunk encoded once for every client it goes to
+#[derive(Clone)]
+pub(super) struct EncodedChunk {
+    uniform: bool,
+    raw_bytes: u64,
+    bytes: Bytes,
+}
+
+impl EncodedChunk {
+    pub(super) fn new(chunk: &Chunk) -> Option<Self> {
+        let payload = match ChunkPayload::encode(chunk) {
+            Ok(payload) => payload,
+            Err(error) => {
+                warn!("could not encode chunk {}: {}", chunk.position(), error);
+                return None;
+            }
+        };
+        let uniform = payload.is_uniform();
+        match ServerMessage::Chunk(payload).encode() {
+            Ok(bytes) => Some(Self {
+                uniform,
+                raw_bytes: raw_chunk_size(chunk),
+                bytes: Bytes::from(bytes),
+            }),
+            Err(error) => {
+                warn!("could not encode chunk {}: {}", chunk.position(), error);
+                None
+            }
+        }
+    }
+
+    pub(super) fn send(
+        &self,
+        server: &mut RenetServer,
+        stats: &mut ChunkTransferStats,
+        player: &ClientPlayer,
+    ) {
+        stats.record(self.uniform, self.raw_bytes, self.bytes.len() as u64);
+        server.send_message(player.id, ServerChannel::Chunks, self.bytes.clone());
+    }
+}
+
+// edited chunks only go to the clients that were sent the chunk before, the others get the
+// edited chunk once it comes into view
+pub fn send_updated_chunk_to_client(
+    mut chunk_updated_event_reader: EventReader<ChunkUpdatedEvent>,
     mut server: ResMut<RenetServer>,
+    mut stats: ResMut<ChunkTransferStats>,
+    player_query: Query<(&ClientPlayer, &ChunkInterest)>,
 ) {
-    for event in chunk_created_event_reader.read() {
-        for client_id in server.clients_id() {
-            if let Ok(message) = bincode::serialize::<Chunk>(&event.chunk) {
-                server.send_message(client_id, ServerChannel::Chunks, message)
-            };
+    for event in chunk_updated_event_reader.read() {
+        let position = event.chunk.position();
+        let mut players = player_query
+            .iter()
+            .filter(|(_, interest)| interest.has_sent(position))
+            .peekable();
+        if players.peek().is_none() {
+            continue;
         }
+        let Some(encoded) = EncodedChunk::new(&event.chunk) else {
+            continue;
+        };
+        for (player, _) in players {
+            encoded.send(&mut server, &mut stats, player);
+        }
+    }
+}
+
+pub fn log_chunk_transfer_stats(
+    time: Res<Time>,
+    stats: Res<ChunkTransferStats>,
+    mut timer: ResMut<ChunkTransferLogTimer>,
+) {
+    if timer.0.tick(time.delta()).just_finished() && stats.chunks > 0 {
+        info!("chunk transfer: {}", *stats);
     }
 }
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::command_system::builder_commands::selection_size;
+
+use super::{
+    chunk::{get_block, Chunk, CHUNK_HEIGHT, CHUNK_VERT},
+    element::Element,
+};
+
+// the blocks changed by /fill, /replace, /sphere and /cylinder
+
+// larger shapes would go past MAX_SELECTION_VOLUME anyway
+pub const MAX_SHAPE_RADIUS: i32 = 64;
+pub const MAX_SHAPE_HEIGHT: i32 = CHUNK_VERT * CHUNK_HEIGHT as i32;
+
+pub fn parse_radius(radius: &str) -> Option<i32> {
+    radius
+        .parse::<i32>()
+        .ok()
+        .filter(|radius| (1..=MAX_SHAPE_RADIUS).contains(radius))
+}
+
+// cylinders can reach from the bottom to the top of the world
+pub fn parse_height(height: &str) -> Option<i32> {
+    height
+        .parse::<i32>()
+        .ok()
+        .filter(|height| (1..=MAX_SHAPE_HEIGHT).contains(height))
+}
+
+pub fn parse_hollow(parts: &[&str]) -> Option<bool> {
+    match parts {
+        [] => Some(false),
+        ["hollow"] => Some(true),
+        _ => None,
+    }
+}
+
+// every position between two inclusive corners, unless the region is too large to edit at once
+pub fn region_positions(min: IVec3, max: IVec3) -> Option<Vec<IVec3>> {
+    let size = selection_size(min, max)?;
+    let mut positions = Vec::with_capacity((size.x * size.y * size.z) as usize);
+    for x in min.x..=max.x {
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                positions.push(IVec3::new(x, y, z));
+            }
+        }
+    }
+    Some(positions)
+}
+
+// every block of one element inside the region, changed to another
+pub fn replaced_blocks(
+    min: IVec3,
+    max: IVec3,
+    chunks: &HashMap<IVec3, &Chunk>,
+    from: Element,
+    to: Element,
+) -> Option<Vec<(IVec3, Element)>> {
+    Some(
+        region_positions(min, max)?
+            .into_iter()
+            .filter(|position| get_block(chunks, *position) == from)
+            .map(|position| (position, to))
+            .collect(),
+    )
+}
+
+// a block is part of the sphere when its center is within radius + 0.5,
+// the shell of a hollow sphere is every such block with a neighbour outside of it.
+pub fn sphere_positions(center: IVec3, radius: i32, hollow: bool) -> Vec<IVec3> {
+    let limit = (radius as f32 + 0.5).powi(2);
+    let inside = |offset: IVec3| offset.as_vec3().length_squared() <= limit;
+    let mut positions = Vec::new();
+    for x in -radius..=radius {
+        for y in -radius..=radius {
+            for z in -radius..=radius {
+                let offset = IVec3::new(x, y, z);
+                if !inside(offset) {
+                    continue;
+                }
+                if hollow
+                    && NEIGHBOURS
+                        .iter()
+                        .all(|neighbour| inside(offset + *neighbour))
+                {
+                    continue;
+                }
+                positions.push(center + offset);
+            }
+        }
+    }
+    positions
+}
+
+pub fn cylinder_positions(base: IVec3, radius: i32, height: i32, hollow: bool) -> Vec<IVec3> {
+    let limit = (radius as f32 + 0.5).powi(2);
+    let inside = |x: i32, z: i32| ((x * x + z * z) as f32) <= limit;
+    let mut positions = Vec::new();
+    for x in -radius..=radius {
+        for z in -radius..=radius {
+            if !inside(x, z) {
+                continue;
+            }
+            // hollow cylinders keep their wall but are open at the top and bottom
+            if hollow
+                && inside(x + 1, z)
+                && inside(x - 1, z)
+                && inside(x, z + 1)
+                && inside(x, z - 1)
+            {
+                continue;
+            }
+            for y in 0..height {
+                positions.push(base + IVec3::new(x, y, z));
+            }
+        }
+    }
+    positions
+}
+
+const NEIGHBOURS: [IVec3; 6] = [
+    IVec3::X,
+    IVec3::NEG_X,
+    IVec3::Y,
+    IVec3::NEG_Y,
+    IVec3::Z,
+    IVec3::NEG_Z,
+];
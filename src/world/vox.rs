@@ -0,0 +1,462 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use bevy::prelude::*;
+
+use super::element::Element;
+
+// MagicaVoxel .vox reader and writer
+// format reference: https://github.com/ephtracy/voxel-model/blob/master/MagicaVoxel-file-format-vox.txt
+// only the SIZE, XYZI and RGBA chunks are understood, everything else (scene graph, materials, layers) is skipped.
+// magicavoxel is z-up while the world is y-up, the axes are swapped when converting to and from blocks.
+
+pub const VOX_DIRECTORY: &str = "vox";
+pub const VOX_MAPPING_FILE: &str = "vox/mapping.txt";
+
+const VOX_VERSION: i32 = 150;
+// a single model can not be larger than this on any axis
+pub const VOX_MAX_SIZE: i32 = 256;
+
+pub type VoxPalette = [[u8; 4]; 256];
+
+#[derive(Debug)]
+pub enum VoxError {
+    Io(io::Error),
+    InvalidHeader,
+    UnsupportedVersion(i32),
+    MissingChunk(&'static str),
+    ModelTooLarge(IVec3),
+    InvalidSize(i32),
+    InvalidMapping(String),
+}
+
+impl fmt::Display for VoxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VoxError::Io(error) => write!(f, "io error: {}", error),
+            VoxError::InvalidHeader => write!(f, "not a .vox file"),
+            VoxError::UnsupportedVersion(version) => {
+                write!(f, "unsupported .vox version {}", version)
+            }
+            VoxError::MissingChunk(id) => write!(f, "missing {} chunk", id),
+            VoxError::ModelTooLarge(size) => write!(
+                f,
+                "model size {} exceeds the .vox limit of {}",
+                size, VOX_MAX_SIZE
+            ),
+            VoxError::InvalidSize(size) => {
+                write!(f, "size {} does not fit in the rest of the file", size)
+            }
+            VoxError::InvalidMapping(line) => write!(f, "invalid mapping line: {}", line),
+        }
+    }
+}
+
+impl std::error::Error for VoxError {}
+
+impl From<io::Error> for VoxError {
+    fn from(error: io::Error) -> Self {
+        VoxError::Io(error)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Voxel {
+    pub x: u8,
+    pub y: u8,
+    pub z: u8,
+    // 1..=255, index 0 is reserved for empty space
+    pub color_index: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VoxModel {
+    // in magicavoxel axes, z is up
+    pub size: UVec3,
+    pub voxels: Vec<Voxel>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VoxFile {
+    pub models: Vec<VoxModel>,
+    // palette[i - 1] is the colour of color_index i
+    pub palette: VoxPalette,
+}
+
+pub fn load_vox(path: impl AsRef<Path>) -> Result<VoxFile, VoxError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    read_vox(&mut reader)
+}
+
+pub fn save_vox(path: impl AsRef<Path>, vox: &VoxFile) -> Result<(), VoxError> {
+    if let Some(parent) = path.as_ref().parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_vox(&mut writer, vox)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn read_vox(reader: &mut impl Read) -> Result<VoxFile, VoxError> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != b"VOX " {
+        return Err(VoxError::InvalidHeader);
+    }
+    let version = read_i32(reader)?;
+    if version > VOX_VERSION {
+        return Err(VoxError::UnsupportedVersion(version));
+    }
+
+    // the rest is read at once so every size in the file can be checked against what is left
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest)?;
+    let (id, content, children) = read_chunk(&mut rest.as_slice())?;
+    if &id != b"MAIN" || !content.is_empty() {
+        return Err(VoxError::MissingChunk("MAIN"));
+    }
+
+    let mut models = Vec::new();
+    let mut palette = None;
+    let mut size = None;
+    let mut children = children.as_slice();
+    while !children.is_empty() {
+        let (id, content, _) = read_chunk(&mut children)?;
+        let mut content = content.as_slice();
+        match &id {
+            b"SIZE" => {
+                size = Some(UVec3::new(
+                    read_size(&mut content, u32::MAX as usize)? as u32,
+                    read_size(&mut content, u32::MAX as usize)? as u32,
+                    read_size(&mut content, u32::MAX as usize)? as u32,
+                ));
+            }
+            b"XYZI" => {
+                let size = size.take().ok_or(VoxError::MissingChunk("SIZE"))?;
+                // every voxel takes 4 bytes after the count
+                let max_count = content.len().saturating_sub(4) / 4;
+                let count = read_size(&mut content, max_count)?;
+                let mut voxels = Vec::with_capacity(count);
+                for _ in 0..count {
+                    let mut voxel = [0u8; 4];
+                    content.read_exact(&mut voxel)?;
+                    voxels.push(Voxel {
+                        x: voxel[0],
+                        y: voxel[1],
+                        z: voxel[2],
+                        color_index: voxel[3],
+                    });
+                }
+                models.push(VoxModel { size, voxels });
+            }
+            b"RGBA" => {
+                let mut colors = [[0u8; 4]; 256];
+                for color in colors.iter_mut() {
+                    content.read_exact(color)?;
+                }
+                palette = Some(colors);
+            }
+            _ => {}
+        }
+    }
+
+    if models.is_empty() {
+        return Err(VoxError::MissingChunk("XYZI"));
+    }
+
+    Ok(VoxFile {
+        models,
+        // files without a palette use the magicavoxel default palette, which we do not ship.
+        // those files can still be imported through index based mappings.
+        palette: palette.unwrap_or([[0, 0, 0, 255]; 256]),
+    })
+}
+
+pub fn write_vox(writer: &mut impl Write, vox: &VoxFile) -> Result<(), VoxError> {
+    let mut children = Vec::new();
+    for model in vox.models.iter() {
+        if model.size.max_element() > VOX_MAX_SIZE as u32 {
+            return Err(VoxError::ModelTooLarge(model.size.as_ivec3()));
+        }
+        let mut size = Vec::with_capacity(12);
+        size.extend_from_slice(&(model.size.x as i32).to_le_bytes());
+        size.extend_from_slice(&(model.size.y as i32).to_le_bytes());
+        size.extend_from_slice(&(model.size.z as i32).to_le_bytes());
+        write_chunk(&mut children, b"SIZE", &size, &[]);
+
+        let mut xyzi = Vec::with_capacity(4 + model.voxels.len() * 4);
+        xyzi.extend_from_slice(&(model.voxels.len() as i32).to_le_bytes());
+        for voxel in model.voxels.iter() {
+            xyzi.extend_from_slice(&[voxel.x, voxel.y, voxel.z, voxel.color_index]);
+        }
+        write_chunk(&mut children, b"XYZI", &xyzi, &[]);
+    }
+    let palette: Vec<u8> = vox.palette.iter().flatten().copied().collect();
+    write_chunk(&mut children, b"RGBA", &palette, &[]);
+
+    let mut main = Vec::with_capacity(children.len() + 12);
+    write_chunk(&mut main, b"MAIN", &[], &children);
+
+    writer.write_all(b"VOX ")?;
+    writer.write_all(&VOX_VERSION.to_le_bytes())?;
+    writer.write_all(&main)?;
+    Ok(())
+}
+
+fn read_i32(reader: &mut impl Read) -> Result<i32, VoxError> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(i32::from_le_bytes(bytes))
+}
+
+// a size or count that is neither negative nor larger than max
+fn read_size(reader: &mut impl Read, max: usize) -> Result<usize, VoxError> {
+    let size = read_i32(reader)?;
+    match usize::try_from(size) {
+        Ok(value) if value <= max => Ok(value),
+        _ => Err(VoxError::InvalidSize(size)),
+    }
+}
+
+// every chunk is: id, content size, children size, content, children
+fn read_chunk(reader: &mut &[u8]) -> Result<([u8; 4], Vec<u8>, Vec<u8>), VoxError> {
+    let mut id = [0u8; 4];
+    reader.read_exact(&mut id)?;
+    // both sizes are read before the content starts
+    let left = reader.len().saturating_sub(8);
+    let content_size = read_size(reader, left)?;
+    let children_size = read_size(reader, left - content_size)?;
+    let (content, rest) = reader.split_at(content_size);
+    let (children, rest) = rest.split_at(children_size);
+    *reader = rest;
+    Ok((id, content.to_vec(), children.to_vec()))
+}
+
+fn write_chunk(buffer: &mut Vec<u8>, id: &[u8; 4], content: &[u8], children: &[u8]) {
+    buffer.extend_from_slice(id);
+    buffer.extend_from_slice(&(content.len() as i32).to_le_bytes());
+    buffer.extend_from_slice(&(children.len() as i32).to_le_bytes());
+    buffer.extend_from_slice(content);
+    buffer.extend_from_slice(children);
+}
+
+// representative colour of an element, used to pick the closest element for unmapped palette entries
+// and to build the palette when exporting.
+pub fn element_color(element: Element) -> [u8; 3] {
+    match element {
+        Element::Air => [0, 0, 0],
+        Element::Dirt => [134, 96, 67],
+        Element::Grass => [89, 145, 57],
+        Element::Stone => [125, 125, 125],
+        Element::Glass => [200, 220, 235],
+        Element::Leaves => [60, 110, 40],
+        Element::Water => [42, 93, 251],
+        Element::StoneSlab => [150, 150, 150],
+        Element::StoneStairs => [105, 105, 105],
+        Element::Fence => [160, 130, 80],
+        Element::Flower => [200, 30, 40],
+        Element::Torch => [250, 200, 60],
+    }
+}
+
+// Maps palette entries to elements.
+// explicit index entries always win, every other entry is matched to the element with the closest colour.
+// the mapping file has one "<palette index> <element>" pair per line, lines starting with # are ignored.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct VoxMapping {
+    pub indices: HashMap<u8, Element>,
+}
+
+impl VoxMapping {
+    pub fn parse(source: &str) -> Result<Self, VoxError> {
+        let mut indices = HashMap::new();
+        for line in source.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let index = parts.first().and_then(|index| index.parse::<u8>().ok());
+            let element = parts
+                .get(1)
+                .and_then(|element| Element::from_string(element));
+            match (index, element, parts.len()) {
+                (Some(index), Some(element), 2) if index != 0 => {
+                    indices.insert(index, element);
+                }
+                _ => return Err(VoxError::InvalidMapping(line.to_string())),
+            }
+        }
+        Ok(Self { indices })
+    }
+
+    pub fn load_or_default(path: impl AsRef<Path>) -> Self {
+        match std::fs::read_to_string(path.as_ref()) {
+            Ok(source) => Self::parse(&source).unwrap_or_else(|error| {
+                warn!("{}: {}", path.as_ref().display(), error);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn element(&self, palette: &VoxPalette, color_index: u8) -> Element {
+        if color_index == 0 {
+            return Element::Air;
+        }
+        if let Some(element) = self.indices.get(&color_index) {
+            return *element;
+        }
+        let [r, g, b, _] = palette[color_index as usize - 1];
+        closest_element([r, g, b])
+    }
+
+    // The palette index every element is written with. explicit mappings are reused so a file that
+    // was imported and exported again keeps its indices. other elements get their default index, or
+    // the first free one if an explicit mapping already uses it, so no index stands for two elements.
+    // an element only misses out if the mapping uses every index.
+    pub fn color_indices(&self) -> HashMap<Element, u8> {
+        let mut color_indices = HashMap::new();
+        let mut explicit: Vec<(u8, Element)> = self
+            .indices
+            .iter()
+            .map(|(index, element)| (*index, *element))
+            .filter(|(_, element)| *element != Element::Air)
+            .collect();
+        explicit.sort_by_key(|(index, _)| *index);
+        for (index, element) in explicit {
+            color_indices.entry(element).or_insert(index);
+        }
+        let mut taken: HashSet<u8> = self.indices.keys().copied().collect();
+        for element in EXPORTED_ELEMENTS {
+            if color_indices.contains_key(&element) {
+                continue;
+            }
+            let free = Some(default_color_index(element))
+                .filter(|index| !taken.contains(index))
+                .or_else(|| (1..=255).find(|index| !taken.contains(index)));
+            if let Some(index) = free {
+                taken.insert(index);
+                color_indices.insert(element, index);
+            }
+        }
+        color_indices
+    }
+
+    // the palette for color_indices, explicitly mapped indices get the colour of their element too
+    pub fn palette(&self, color_indices: &HashMap<Element, u8>) -> VoxPalette {
+        let mut palette = [[0, 0, 0, 255]; 256];
+        let entries = color_indices
+            .iter()
+            .map(|(element, index)| (index, element))
+            .chain(self.indices.iter());
+        for (index, element) in entries {
+            let [r, g, b] = element_color(*element);
+            palette[*index as usize - 1] = [r, g, b, 255];
+        }
+        palette
+    }
+}
+
+const EXPORTED_ELEMENTS: [Element; 11] = [
+    Element::Dirt,
+    Element::Grass,
+    Element::Stone,
+    Element::Glass,
+    Element::Leaves,
+    Element::Water,
+    Element::StoneSlab,
+    Element::StoneStairs,
+    Element::Fence,
+    Element::Flower,
+    Element::Torch,
+];
+
+fn default_color_index(element: Element) -> u8 {
+    match element {
+        Element::Air => 0,
+        Element::Dirt => 1,
+        Element::Grass => 2,
+        Element::Stone => 3,
+        Element::Glass => 4,
+        Element::Leaves => 5,
+        Element::Water => 6,
+        Element::StoneSlab => 7,
+        Element::StoneStairs => 8,
+        Element::Fence => 9,
+        Element::Flower => 10,
+        Element::Torch => 11,
+    }
+}
+
+fn closest_element(color: [u8; 3]) -> Element {
+    let distance = |element: &Element| {
+        let other = element_color(*element);
+        (0..3)
+            .map(|i| (color[i] as i32 - other[i] as i32).pow(2))
+            .sum::<i32>()
+    };
+    EXPORTED_ELEMENTS
+        .into_iter()
+        .min_by_key(distance)
+        .unwrap_or(Element::Stone)
+}
+
+// converts every model in the file to block offsets relative to the paste origin, in world axes.
+pub fn vox_to_blocks(vox: &VoxFile, mapping: &VoxMapping) -> Vec<(IVec3, Element)> {
+    let mut blocks = Vec::new();
+    for model in vox.models.iter() {
+        for voxel in model.voxels.iter() {
+            let element = mapping.element(&vox.palette, voxel.color_index);
+            if element == Element::Air {
+                continue;
+            }
+            blocks.push((
+                IVec3::new(voxel.x as i32, voxel.z as i32, voxel.y as i32),
+                element,
+            ));
+        }
+    }
+    blocks
+}
+
+// builds a single model from a region of the world, size is in world axes.
+// block_at is called with offsets relative to the region's minimum corner.
+pub fn blocks_to_vox(
+    size: IVec3,
+    mapping: &VoxMapping,
+    block_at: impl Fn(IVec3) -> Element,
+) -> Result<VoxFile, VoxError> {
+    if size.max_element() > VOX_MAX_SIZE || size.min_element() <= 0 {
+        return Err(VoxError::ModelTooLarge(size));
+    }
+    let color_indices = mapping.color_indices();
+    let mut voxels = Vec::new();
+    for x in 0..size.x {
+        for y in 0..size.y {
+            for z in 0..size.z {
+                if let Some(&color_index) = color_indices.get(&block_at(IVec3::new(x, y, z))) {
+                    voxels.push(Voxel {
+                        x: x as u8,
+                        y: z as u8,
+                        z: y as u8,
+                        color_index,
+                    });
+                }
+            }
+        }
+    }
+    Ok(VoxFile {
+        models: vec![VoxModel {
+            size: UVec3::new(size.x as u32, size.z as u32, size.y as u32),
+            voxels,
+        }],
+        palette: mapping.palette(&color_indices),
+    })
+}
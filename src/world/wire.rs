@@ -0,0 +1,249 @@
+use std::{
+    fmt,
+    io::{self, Read},
+};
+
+use bevy::prelude::*;
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    block::Block,
+    chunk::{Chunk, CHUNK_DEPTH, CHUNK_HEIGHT, CHUNK_WIDTH},
+    element::Element,
+};
+
+// How chunks travel over the network. the blocks are listed in the order of Chunk::blocks and
+// stored as runs of the same element, each run pointing into a palette of the elements in the
+// chunk. the runs are then deflated. chunks made of a single element, like the air above the
+// ground, skip all of that and only send the element.
+
+const BLOCKS_PER_CHUNK: usize = CHUNK_WIDTH * CHUNK_HEIGHT * CHUNK_DEPTH;
+
+// the bincode of the largest valid ChunkRuns: a full palette and one run per block. a payload
+// inflating to more than that is broken or a deflate bomb, it is not read any further
+pub const MAX_RUNS_BYTES: usize = 8 + 256 * 4 + 8 + BLOCKS_PER_CHUNK * 3;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ChunkPayload {
+    Uniform {
+        position: IVec3,
+        element: Element,
+    },
+    Compressed {
+        position: IVec3,
+        // deflated bincode of ChunkRuns
+        data: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ChunkRuns {
+    palette: Vec<Element>,
+    // (length, index into the palette)
+    runs: Vec<(u16, u8)>,
+}
+
+#[derive(Debug)]
+pub enum ChunkWireError {
+    Compression(io::Error),
+    Encoding(bincode::Error),
+    UnknownPaletteIndex(u8),
+    WrongBlockCount(usize),
+    TooLarge,
+}
+
+impl fmt::Display for ChunkWireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkWireError::Compression(error) => write!(f, "compression error: {}", error),
+            ChunkWireError::Encoding(error) => write!(f, "encoding error: {}", error),
+            ChunkWireError::UnknownPaletteIndex(index) => {
+                write!(
+                    f,
+                    "run points to palette entry {} that does not exist",
+                    index
+                )
+            }
+            ChunkWireError::WrongBlockCount(count) => write!(
+                f,
+                "runs cover {} blocks instead of {}",
+                count, BLOCKS_PER_CHUNK
+            ),
+            ChunkWireError::TooLarge => {
+                write!(f, "runs inflate to more than {} bytes", MAX_RUNS_BYTES)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChunkWireError {}
+
+impl From<io::Error> for ChunkWireError {
+    fn from(error: io::Error) -> Self {
+        ChunkWireError::Compression(error)
+    }
+}
+
+impl From<bincode::Error> for ChunkWireError {
+    fn from(error: bincode::Error) -> Self {
+        ChunkWireError::Encoding(error)
+    }
+}
+
+fn elements(chunk: &Chunk) -> impl Iterator<Item = Element> + '_ {
+    chunk
+        .blocks
+        .iter()
+        .flatten()
+        .flatten()
+        .map(|block| block.element)
+}
+
+fn chunk_runs(chunk: &Chunk) -> ChunkRuns {
+    let mut chunk_runs = ChunkRuns::default();
+    for element in elements(chunk) {
+        let index = match chunk_runs
+            .palette
+            .iter()
+            .position(|other| *other == element)
+        {
+            Some(index) => index,
+            None => {
+                chunk_runs.palette.push(element);
+                chunk_runs.palette.len() - 1
+            }
+        } as u8;
+        match chunk_runs.runs.last_mut() {
+            Some((length, last)) if *last == index && *length < u16::MAX => *length += 1,
+            _ => chunk_runs.runs.push((1, index)),
+        }
+    }
+    chunk_runs
+}
+
+impl ChunkPayload {
+    pub fn encode(chunk: &Chunk) -> Result<Self, ChunkWireError> {
+        let position = chunk.position();
+        let first = chunk.blocks[0][0][0].element;
+        if elements(chunk).all(|element| element == first) {
+            return Ok(ChunkPayload::Uniform {
+                position,
+                element: first,
+            });
+        }
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+        bincode::serialize_into(&mut encoder, &chunk_runs(chunk))?;
+        Ok(ChunkPayload::Compressed {
+            position,
+            data: encoder.finish()?,
+        })
+    }
+
+    pub fn decode(&self) -> Result<Chunk, ChunkWireError> {
+        let (position, elements) = match self {
+            ChunkPayload::Uniform { position, element } => {
+                (*position, vec![*element; BLOCKS_PER_CHUNK])
+            }
+            ChunkPayload::Compressed { position, data } => {
+                let mut bytes = Vec::new();
+                DeflateDecoder::new(data.as_slice())
+                    .take(MAX_RUNS_BYTES as u64 + 1)
+                    .read_to_end(&mut bytes)?;
+                if bytes.len() > MAX_RUNS_BYTES {
+                    return Err(ChunkWireError::TooLarge);
+                }
+                let chunk_runs: ChunkRuns = bincode::deserialize(&bytes)?;
+                let mut elements = Vec::with_capacity(BLOCKS_PER_CHUNK);
+                for (length, index) in chunk_runs.runs {
+                    let element = chunk_runs
+                        .palette
+                        .get(index as usize)
+                        .ok_or(ChunkWireError::UnknownPaletteIndex(index))?;
+                    elements.extend(std::iter::repeat_n(*element, length as usize));
+                    if elements.len() > BLOCKS_PER_CHUNK {
+                        return Err(ChunkWireError::WrongBlockCount(elements.len()));
+                    }
+                }
+                (*position, elements)
+            }
+        };
+        if elements.len() != BLOCKS_PER_CHUNK {
+            return Err(ChunkWireError::WrongBlockCount(elements.len()));
+        }
+        let mut chunk = Chunk {
+            chunk_x: position.x as f32,
+            chunk_y: position.y as f32,
+            chunk_z: position.z as f32,
+            blocks: [[[Block::default(); CHUNK_WIDTH]; CHUNK_HEIGHT]; CHUNK_DEPTH],
+        };
+        for (block, element) in chunk.blocks.iter_mut().flatten().flatten().zip(elements) {
+            *block = Block::new(element);
+        }
+        Ok(chunk)
+    }
+
+    pub fn is_uniform(&self) -> bool {
+        matches!(self, ChunkPayload::Uniform { .. })
+    }
+
+    pub fn position(&self) -> IVec3 {
+        match self {
+            ChunkPayload::Uniform { position, .. } | ChunkPayload::Compressed { position, .. } => {
+                *position
+            }
+        }
+    }
+}
+
+// the size of a chunk sent as plain bincode, what the wire format is measured against
+pub fn raw_chunk_size(chunk: &Chunk) -> u64 {
+    bincode::serialized_size(chunk).unwrap_or_default()
+}
+
+// how many chunks went over the network and how many bytes the wire format saved
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ChunkTransferStats {
+    pub chunks: u64,
+    pub uniform_chunks: u64,
+    pub raw_bytes: u64,
+    pub sent_bytes: u64,
+}
+
+impl ChunkTransferStats {
+    pub fn record(&mut self, uniform: bool, raw_bytes: u64, sent_bytes: u64) {
+        self.chunks += 1;
+        if uniform {
+            self.uniform_chunks += 1;
+        }
+        self.raw_bytes += raw_bytes;
+        self.sent_bytes += sent_bytes;
+    }
+
+    pub fn saved_bytes(&self) -> u64 {
+        self.raw_bytes.saturating_sub(self.sent_bytes)
+    }
+
+    // sent bytes as a part of the raw bytes
+    pub fn ratio(&self) -> f32 {
+        if self.raw_bytes == 0 {
+            return 1.0;
+        }
+        self.sent_bytes as f32 / self.raw_bytes as f32
+    }
+}
+
+impl fmt::Display for ChunkTransferStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} chunks ({} uniform), {} KB sent for {} KB, {} KB saved ({:.1}%)",
+            self.chunks,
+            self.uniform_chunks,
+            self.sent_bytes / 1024,
+            self.raw_bytes / 1024,
+            self.saved_bytes() / 1024,
+            (1.0 - self.ratio()) * 100.0
+        )
+    }
+}
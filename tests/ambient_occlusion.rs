@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use bevy::math::{IVec3, Vec3};
+use voxels::world::{
+    block::{Block, BlockFace},
+    chunk::{Chunk, ChunkView, CHUNK_WIDTH},
+    client::{
+        mesh_utils::{gen_mesh, vertex_ao, ChunkMeshBuilder, MeshPass, AO_BRIGHTNESS},
+        packed::ChunkVertexFormat,
+    },
+    element::Element,
+};
+
+mod common;
+
+use common::{build_chunk, chunk_at, colors, indices};
+
+fn set(chunk: &mut Chunk, position: IVec3) {
+    chunk.blocks[position.x as usize][position.y as usize][position.z as usize] =
+        Block::new(Element::Stone);
+}
+
+// a stone floor at y 0
+fn floor_chunk() -> Chunk {
+    build_chunk(|_, y, _| if y == 0 { Element::Stone } else { Element::Air })
+}
+
+#[test]
+fn open_corners_are_not_occluded() {
+    let chunk = floor_chunk();
+    let view = ChunkView::without_neighbours(&chunk);
+    for corner in [IVec3::new(0, 1, 0), IVec3::new(1, 1, 1)] {
+        assert_eq!(vertex_ao(&view, IVec3::new(4, 0, 4), IVec3::Y, corner), 3);
+    }
+}
+
+#[test]
+fn blocks_in_front_of_a_face_darken_its_corners() {
+    let mut chunk = floor_chunk();
+    set(&mut chunk, IVec3::new(5, 1, 5));
+    set(&mut chunk, IVec3::new(4, 1, 6));
+    set(&mut chunk, IVec3::new(6, 1, 5));
+    let view = ChunkView::without_neighbours(&chunk);
+
+    // one edge block
+    assert_eq!(
+        vertex_ao(&view, IVec3::new(4, 0, 5), IVec3::Y, IVec3::new(1, 1, 0)),
+        2
+    );
+    // only the corner block
+    assert_eq!(
+        vertex_ao(&view, IVec3::new(4, 0, 4), IVec3::Y, IVec3::new(1, 1, 1)),
+        2
+    );
+    // both edge blocks hide the corner completely
+    assert_eq!(
+        vertex_ao(&view, IVec3::new(4, 0, 5), IVec3::Y, IVec3::new(1, 1, 1)),
+        0
+    );
+    // edge and corner block
+    assert_eq!(
+        vertex_ao(&view, IVec3::new(5, 0, 4), IVec3::Y, IVec3::new(1, 1, 1)),
+        1
+    );
+}
+
+#[test]
+fn occlusion_reads_neighbouring_chunks() {
+    let chunk = floor_chunk();
+    let neighbour = chunk_at(IVec3::X, &[(IVec3::new(0, 1, 3), Element::Stone)]);
+    let corner = IVec3::new(1, 1, 0);
+    let position = IVec3::new(CHUNK_WIDTH as i32 - 1, 0, 3);
+
+    let alone = ChunkView::without_neighbours(&chunk);
+    assert_eq!(vertex_ao(&alone, position, IVec3::Y, corner), 3);
+
+    let chunks: HashMap<IVec3, &Chunk> = [(IVec3::ZERO, &chunk), (IVec3::X, &neighbour)].into();
+    let view = ChunkView::new(&chunk, &chunks);
+    assert_eq!(vertex_ao(&view, position, IVec3::Y, corner), 2);
+}
+
+#[test]
+fn meshes_carry_the_occlusion_as_vertex_colors() {
+    let mut chunk = floor_chunk();
+    set(&mut chunk, IVec3::new(5, 1, 5));
+    let mesh = gen_mesh(
+        1.0,
+        &ChunkView::without_neighbours(&chunk),
+        MeshPass::Opaque,
+    );
+    let colors = colors(&mesh);
+    assert_eq!(colors.len(), mesh.count_vertices());
+    for level in [1, 2, 3] {
+        let brightness = AO_BRIGHTNESS[level];
+        assert!(colors.iter().any(|color| color.x == brightness));
+    }
+}
+
+#[test]
+fn quads_are_split_between_the_brighter_corners() {
+    let mut builder = ChunkMeshBuilder::new(ChunkVertexFormat::Standard);
+    let block = Block::new(Element::Stone);
+    builder.push_face(1.0, BlockFace::North, Vec3::ZERO, block, [3; 4]);
+    builder.push_face(1.0, BlockFace::North, Vec3::ZERO, block, [0, 3, 3, 3]);
+    builder.push_face(1.0, BlockFace::North, Vec3::ZERO, block, [3, 0, 3, 3]);
+    assert_eq!(
+        indices(&builder.build()),
+        vec![
+            0, 1, 2, 2, 3, 0, //
+            5, 6, 7, 7, 4, 5, //
+            8, 9, 10, 10, 11, 8,
+        ]
+    );
+}
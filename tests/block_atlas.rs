@@ -0,0 +1,226 @@
+use std::path::Path;
+
+use bevy::{
+    asset::{Assets, Handle},
+    math::Vec2,
+    pbr::AlphaMode,
+    render::{
+        render_resource::{Extent3d, TextureDimension, TextureFormat, TextureViewDimension},
+        texture::Image,
+    },
+};
+use voxels::world::{
+    atlas::{
+        block_atlas_rects, load_block_textures, pack_block_atlas, pack_block_texture_array,
+        ATLAS_GRID_SIZE,
+    },
+    block::{get_texture, BlockTexture},
+    client::{
+        fade::FADE_STEPS,
+        material::{BlockMaterials, ChunkMaterial},
+    },
+};
+
+fn pixel(image: &Image, x: u32, y: u32) -> [u8; 4] {
+    let index = ((y * image.width() + x) * 4) as usize;
+    image.data[index..index + 4].try_into().unwrap()
+}
+
+fn solid(size: u32, color: [u8; 4]) -> Image {
+    Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        color.repeat((size * size) as usize),
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+#[test]
+fn every_block_texture_has_an_image() {
+    let textures = load_block_textures(Path::new("assets"));
+    for (texture, image) in BlockTexture::ALL.iter().zip(textures) {
+        assert!(image.is_some(), "{} is missing", texture.file_name());
+    }
+}
+
+#[test]
+fn textures_get_their_own_cells() {
+    let rects: Vec<_> = BlockTexture::ALL
+        .iter()
+        .map(|texture| texture.atlas_rect())
+        .collect();
+    assert_eq!(rects, block_atlas_rects());
+    for (index, rect) in rects.iter().enumerate() {
+        assert!(rect.min.cmpge(Vec2::ZERO).all() && rect.max.cmple(Vec2::ONE).all());
+        assert!(rects[index + 1..]
+            .iter()
+            .all(|other| rect.intersect(*other).is_empty()));
+    }
+}
+
+#[test]
+fn packed_textures_are_surrounded_by_their_border_pixels() {
+    let red = solid(16, [255, 0, 0, 255]);
+    let green = solid(32, [0, 255, 0, 255]);
+    let mut textures = vec![Some(&red), Some(&green)];
+    textures.resize(BlockTexture::ALL.len(), Some(&red));
+    let atlas = pack_block_atlas(&textures);
+
+    // 16 pixel textures with a one pixel gutter, the second one is scaled down to fit
+    let cell_size = 18;
+    assert_eq!(atlas.width(), ATLAS_GRID_SIZE * cell_size);
+    for x in 0..cell_size {
+        for y in 0..cell_size {
+            assert_eq!(pixel(&atlas, x, y), [255, 0, 0, 255]);
+            assert_eq!(pixel(&atlas, cell_size + x, y), [0, 255, 0, 255]);
+        }
+    }
+
+    // the uvs of a face only cover the texture, not the gutter
+    let uvs = get_texture(BlockTexture::ALL[1].atlas_rect());
+    let size = atlas.width() as f32;
+    assert!((uvs[0].x * size - (cell_size + 1) as f32).abs() < 1e-3);
+    assert!((uvs[2].x * size - (2 * cell_size - 1) as f32).abs() < 1e-3);
+}
+
+#[test]
+fn smaller_mip_levels_keep_textures_apart() {
+    let red = solid(80, [255, 0, 0, 255]);
+    let green = solid(80, [0, 255, 0, 255]);
+    let mut textures = vec![Some(&red), Some(&green)];
+    textures.resize(BlockTexture::ALL.len(), Some(&red));
+    let atlas = pack_block_atlas(&textures);
+
+    // a 5 pixel gutter keeps half a texel of room down to the fourth level
+    let (cell_size, levels) = (90, 4);
+    let size = ATLAS_GRID_SIZE * cell_size;
+    assert_eq!(atlas.texture_descriptor.mip_level_count, levels);
+    let level_sizes: Vec<u32> = (0..levels).map(|level| size >> level).collect();
+    assert_eq!(
+        atlas.data.len() as u32,
+        level_sizes.iter().map(|size| size * size * 4).sum::<u32>()
+    );
+
+    // the cells of the first two textures meet at cell_size, no texel mixes the two
+    let mut offset = 0;
+    for size in level_sizes {
+        let pixel = |x: u32, y: u32| {
+            let index = offset + ((y * size + x) * 4) as usize;
+            <[u8; 4]>::try_from(&atlas.data[index..index + 4]).unwrap()
+        };
+        // the texels on both sides of the border, and the one that straddles it if there is one
+        let texel = size as f32 / (ATLAS_GRID_SIZE * cell_size) as f32;
+        let (red_end, green_start) = (
+            (cell_size as f32 * texel).floor() as u32,
+            (cell_size as f32 * texel).ceil() as u32,
+        );
+        for y in 0..red_end {
+            assert_eq!(pixel(red_end - 1, y), [255, 0, 0, 255]);
+            assert_eq!(pixel(green_start, y), [0, 255, 0, 255]);
+            assert!([[255, 0, 0, 255], [0, 255, 0, 255]].contains(&pixel(red_end, y)));
+        }
+        offset += (size * size * 4) as usize;
+    }
+}
+
+#[test]
+fn missing_textures_are_marked() {
+    let atlas = pack_block_atlas(&[]);
+    assert_eq!(pixel(&atlas, 1, 1), [255, 0, 255, 255]);
+    assert_ne!(pixel(&atlas, 1, 1), pixel(&atlas, 15, 1));
+}
+
+#[test]
+fn texture_array_holds_a_layer_per_texture() {
+    let red = solid(16, [255, 0, 0, 255]);
+    let green = solid(32, [0, 255, 0, 255]);
+    let mut textures = vec![Some(&red), Some(&green), None];
+    textures.resize(BlockTexture::ALL.len(), Some(&red));
+    let array = pack_block_texture_array(&textures);
+
+    // no gutter, every texture scaled to the size of the first one
+    let size = array.texture_descriptor.size;
+    assert_eq!((size.width, size.height), (16, 16));
+    assert_eq!(size.depth_or_array_layers, BlockTexture::ALL.len() as u32);
+    assert_eq!(
+        array.texture_view_descriptor.as_ref().unwrap().dimension,
+        Some(TextureViewDimension::D2Array)
+    );
+    // 16, 8, 4, 2 and 1 texels wide, the levels of a layer follow each other
+    assert_eq!(array.texture_descriptor.mip_level_count, 5);
+    let layer_size = (16 * 16 + 8 * 8 + 4 * 4 + 2 * 2 + 1) * 4;
+    assert_eq!(array.data.len(), layer_size * BlockTexture::ALL.len());
+    let layer_pixel = |layer: u32, x: u32, y: u32| {
+        let index = layer as usize * layer_size + (y * 16 + x) as usize * 4;
+        <[u8; 4]>::try_from(&array.data[index..index + 4]).unwrap()
+    };
+    for (x, y) in [(0, 0), (15, 0), (7, 15)] {
+        assert_eq!(
+            layer_pixel(BlockTexture::Stone.layer(), x, y),
+            [255, 0, 0, 255]
+        );
+        assert_eq!(
+            layer_pixel(BlockTexture::Dirt.layer(), x, y),
+            [0, 255, 0, 255]
+        );
+    }
+    // the missing texture is marked like in the atlas
+    let missing = BlockTexture::GrassTop.layer();
+    assert_eq!(layer_pixel(missing, 1, 1), [255, 0, 255, 255]);
+    assert_ne!(layer_pixel(missing, 1, 1), layer_pixel(missing, 15, 1));
+}
+
+#[test]
+fn block_materials_share_the_texture_array() {
+    let mut materials = Assets::<ChunkMaterial>::default();
+    let textures = Handle::<Image>::default();
+    let block_materials = BlockMaterials::new(textures.clone(), &mut materials);
+    // plus an opaque, a cutout and a translucent material for every step of the fade in
+    assert_eq!(materials.len(), 3 + 3 * FADE_STEPS);
+
+    let alpha_modes: Vec<AlphaMode> = [
+        &block_materials.opaque,
+        &block_materials.cutout,
+        &block_materials.translucent,
+    ]
+    .into_iter()
+    .map(|handle| {
+        let material = materials.get(handle).unwrap();
+        assert_eq!(material.extension.block_textures, textures);
+        material.base.alpha_mode
+    })
+    .collect();
+    assert_eq!(
+        alpha_modes,
+        [AlphaMode::Opaque, AlphaMode::Mask(0.5), AlphaMode::Blend]
+    );
+    for fading in [
+        &block_materials.opaque_fade,
+        &block_materials.cutout_fade,
+        &block_materials.translucent_fade,
+    ] {
+        assert_eq!(fading.len(), FADE_STEPS);
+        let fades: Vec<f32> = fading
+            .iter()
+            .map(|handle| {
+                let material = materials.get(handle).unwrap();
+                assert_eq!(material.extension.block_textures, textures);
+                material.extension.fade
+            })
+            .collect();
+        assert!(fades.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(fades[0] > 0.0 && fades[FADE_STEPS - 1] < 1.0);
+    }
+    assert_eq!(
+        materials
+            .get(&block_materials.opaque)
+            .unwrap()
+            .extension
+            .fade,
+        1.0
+    );
+}
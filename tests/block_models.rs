@@ -0,0 +1,225 @@
+use bevy::math::{IVec3, Vec2, Vec3};
+use voxels::world::{
+    block::{decode_tiled_uv, get_texture, BlockFace, BlockTexture},
+    chunk::{Chunk, ChunkView},
+    client::{
+        greedy_mesh::gen_greedy_mesh,
+        mesh_utils::{gen_mesh, MeshPass},
+    },
+    element::Element,
+    model::{parse_block_models, ModelError},
+};
+
+mod common;
+
+use common::{chunk_with, normals, positions, uvs};
+
+fn quads(chunk: &Chunk, pass: MeshPass) -> usize {
+    gen_mesh(1.0, &ChunkView::without_neighbours(chunk), pass).count_vertices() / 4
+}
+
+#[test]
+fn models_are_loaded_for_shaped_blocks() {
+    for element in [
+        Element::StoneSlab,
+        Element::StoneStairs,
+        Element::Fence,
+        Element::Flower,
+        Element::Torch,
+    ] {
+        assert!(element.model().is_some(), "{:?}", element);
+        assert!(!element.is_opaque());
+    }
+    assert!(Element::Stone.model().is_none());
+    assert_eq!(
+        Element::StoneSlab.model().unwrap().occludes,
+        vec![BlockFace::Bottom]
+    );
+    assert!(Element::Stone.occludes(BlockFace::Top));
+    assert!(!Element::Flower.occludes(BlockFace::Bottom));
+    assert!(matches!(
+        parse_block_models(r#"{"lava": {"parts": []}}"#),
+        Err(ModelError::UnknownElement(_))
+    ));
+}
+
+#[test]
+fn slabs_cull_against_the_sides_they_cover() {
+    // the slab covers the top of the stone below it but not the bottom of the stone above it
+    let below = chunk_with(&[
+        (IVec3::new(4, 4, 4), Element::Stone),
+        (IVec3::new(4, 5, 4), Element::StoneSlab),
+    ]);
+    assert_eq!(quads(&below, MeshPass::Opaque), 5 + 5);
+    let above = chunk_with(&[
+        (IVec3::new(4, 4, 4), Element::StoneSlab),
+        (IVec3::new(4, 5, 4), Element::Stone),
+    ]);
+    assert_eq!(quads(&above, MeshPass::Opaque), 6 + 6);
+
+    let mesh = gen_mesh(
+        1.0,
+        &ChunkView::without_neighbours(&above),
+        MeshPass::Opaque,
+    );
+    let top = positions(&mesh)
+        .iter()
+        .filter(|position| position.y > 4.0 && position.y < 5.0)
+        .count();
+    // the four corners of the slab's top and the upper corners of its sides sit half way up
+    assert_eq!(top, 4 + 4 * 2);
+}
+
+#[test]
+fn flowers_are_double_sided_planes_inside_their_block() {
+    let chunk = chunk_with(&[(IVec3::new(2, 3, 4), Element::Flower)]);
+    assert_eq!(quads(&chunk, MeshPass::Opaque), 0);
+    assert_eq!(quads(&chunk, MeshPass::Transparent), 0);
+    assert_eq!(quads(&chunk, MeshPass::Cutout), 4);
+
+    let mesh = gen_mesh(
+        1.0,
+        &ChunkView::without_neighbours(&chunk),
+        MeshPass::Cutout,
+    );
+    let normals = normals(&mesh);
+    for (position, normal) in positions(&mesh).iter().zip(normals.iter()) {
+        let local = *position - Vec3::new(2.0, 3.0, 4.0);
+        assert!(local.cmpge(Vec3::ZERO).all() && local.cmple(Vec3::ONE).all());
+        assert_eq!(normal.y, 0.0);
+    }
+    // every front face has a back face pointing the other way
+    for quad in normals.chunks(8) {
+        assert_eq!(quad[0], -quad[4]);
+    }
+}
+
+#[test]
+fn model_faces_use_part_of_their_texture() {
+    let chunk = chunk_with(&[(IVec3::new(8, 8, 8), Element::Fence)]);
+    let view = ChunkView::without_neighbours(&chunk);
+    let cell = get_texture(BlockTexture::Planks.atlas_rect());
+    let (min, max) = (cell[0].min(cell[2]), cell[0].max(cell[2]));
+
+    let atlas = gen_mesh(1.0, &view, MeshPass::Opaque);
+    let greedy = gen_greedy_mesh(1.0, &view, MeshPass::Opaque);
+    assert_eq!(atlas.count_vertices(), 6 * 4);
+    assert_eq!(greedy.count_vertices(), atlas.count_vertices());
+
+    let width = |uvs: &[Vec2]| {
+        uvs.iter().map(|uv| uv.x).fold(f32::MIN, f32::max)
+            - uvs.iter().map(|uv| uv.x).fold(f32::MAX, f32::min)
+    };
+    // the post is a quarter of a block wide, so its sides only cover a quarter of the texture
+    let atlas_uvs = uvs(&atlas);
+    for uv in atlas_uvs.iter() {
+        assert!(uv.cmpge(min - 1e-4).all() && uv.cmple(max + 1e-4).all());
+    }
+    assert!((width(&atlas_uvs[0..4]) - (max.x - min.x) / 4.0).abs() < 1e-4);
+
+    let tiled_uvs = uvs(&greedy);
+    for uv in tiled_uvs.iter() {
+        assert_eq!(decode_tiled_uv(*uv).0, BlockTexture::Planks.layer());
+    }
+    assert!((width(&tiled_uvs[0..4]) - 0.25).abs() < 1e-4);
+}
+
+#[test]
+fn stairs_hide_the_faces_they_share() {
+    // the slab below the step loses the half of its top the step stands on, the step its bottom
+    let single = chunk_with(&[(IVec3::new(4, 4, 4), Element::StoneStairs)]);
+    assert_eq!(quads(&single, MeshPass::Opaque), 6 + 5);
+
+    // side by side both sides of both boxes lie against each other
+    let beside = chunk_with(&[
+        (IVec3::new(4, 4, 4), Element::StoneStairs),
+        (IVec3::new(5, 4, 4), Element::StoneStairs),
+    ]);
+    assert_eq!(quads(&beside, MeshPass::Opaque), 2 * (11 - 2));
+
+    // one behind the other only the slabs touch, the back of the front step stays visible
+    let behind = chunk_with(&[
+        (IVec3::new(4, 4, 4), Element::StoneStairs),
+        (IVec3::new(4, 4, 5), Element::StoneStairs),
+    ]);
+    assert_eq!(quads(&behind, MeshPass::Opaque), 2 * (11 - 1));
+    let mesh = gen_mesh(
+        1.0,
+        &ChunkView::without_neighbours(&behind),
+        MeshPass::Opaque,
+    );
+    let between = positions(&mesh)
+        .chunks(4)
+        .filter(|quad| quad.iter().all(|position| position.z == 5.0))
+        .count();
+    assert_eq!(between, 1);
+}
+
+#[test]
+fn stairs_always_face_the_same_way() {
+    // blocks carry no facing, every stairs block has its step on the north half
+    for position in [
+        IVec3::new(4, 4, 4),
+        IVec3::new(9, 0, 2),
+        IVec3::new(0, 7, 15),
+    ] {
+        let chunk = chunk_with(&[(position, Element::StoneStairs)]);
+        let mesh = gen_mesh(
+            1.0,
+            &ChunkView::without_neighbours(&chunk),
+            MeshPass::Opaque,
+        );
+        for vertex in positions(&mesh) {
+            if vertex.y > position.y as f32 + 0.5 {
+                assert!(
+                    vertex.z >= position.z as f32 + 0.5,
+                    "{} {}",
+                    position,
+                    vertex
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn fences_connect_to_their_neighbours() {
+    let lone = chunk_with(&[(IVec3::new(4, 4, 4), Element::Fence)]);
+    assert_eq!(quads(&lone, MeshPass::Opaque), 6);
+
+    // each post gets two bars towards the other. the bars hide their ends against the post and
+    // the bars of the other fence, and cut holes into the side of the post they start at
+    let pair = chunk_with(&[
+        (IVec3::new(4, 4, 4), Element::Fence),
+        (IVec3::new(5, 4, 4), Element::Fence),
+    ]);
+    let fence = 5 + 5 + 2 * 4;
+    assert_eq!(quads(&pair, MeshPass::Opaque), 2 * fence);
+    let mesh = gen_mesh(1.0, &ChunkView::without_neighbours(&pair), MeshPass::Opaque);
+    let positions = positions(&mesh);
+    let reaches = |x: f32| positions.iter().any(|position| position.x == x);
+    // the bars of the first fence reach the second
+    assert!(reaches(5.0));
+
+    // solid blocks get bars too, the stone hides their ends
+    let wall = chunk_with(&[
+        (IVec3::new(4, 4, 4), Element::Fence),
+        (IVec3::new(4, 4, 5), Element::Stone),
+    ]);
+    assert_eq!(quads(&wall, MeshPass::Opaque), fence + 6);
+    // but nothing else
+    let flower = chunk_with(&[
+        (IVec3::new(4, 4, 4), Element::Fence),
+        (IVec3::new(4, 4, 5), Element::Flower),
+    ]);
+    assert_eq!(quads(&flower, MeshPass::Opaque), 6);
+
+    // the greedy mesher draws the same model faces
+    for chunk in [&pair, &wall] {
+        let view = ChunkView::without_neighbours(chunk);
+        assert_eq!(
+            gen_greedy_mesh(1.0, &view, MeshPass::Opaque).count_vertices(),
+            gen_mesh(1.0, &view, MeshPass::Opaque).count_vertices()
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use std::io::Write;
+
+use bevy::math::IVec3;
+use flate2::{write::DeflateEncoder, Compression};
+use voxels::{
+    net::ServerMessage,
+    world::{
+        block::Block,
+        element::Element,
+        server::chunk::generate_chunk,
+        wire::{raw_chunk_size, ChunkPayload, ChunkTransferStats, ChunkWireError, MAX_RUNS_BYTES},
+    },
+};
+
+mod common;
+
+use common::{build_chunk_at, chunk_at};
+
+// the chunk the tests send, away from the origin so its position has to survive too
+const POSITION: IVec3 = IVec3::new(2, 9, -4);
+
+#[test]
+fn chunks_survive_the_wire_format() {
+    let mut noisy = chunk_at(POSITION, &[]);
+    let elements = [
+        Element::Air,
+        Element::Dirt,
+        Element::Grass,
+        Element::Stone,
+        Element::Glass,
+    ];
+    let mut rng = fastrand::Rng::with_seed(49);
+    for block in noisy.blocks.iter_mut().flatten().flatten() {
+        *block = Block::new(elements[rng.usize(..elements.len())]);
+    }
+    for chunk in [
+        generate_chunk(1.0, 4.0, -2.0),
+        generate_chunk(0.0, 0.0, 0.0),
+        noisy,
+    ] {
+        let payload = ChunkPayload::encode(&chunk).unwrap();
+        assert!(!payload.is_uniform());
+        assert_eq!(payload.position(), chunk.position());
+        assert_eq!(payload.decode().unwrap(), chunk);
+    }
+
+    // the whole message, as it goes over the Chunks channel
+    let chunk = generate_chunk(1.0, 4.0, -2.0);
+    let message = ServerMessage::Chunk(ChunkPayload::encode(&chunk).unwrap());
+    let bytes = message.encode().unwrap();
+    let ServerMessage::Chunk(payload) = ServerMessage::decode(&bytes).unwrap() else {
+        panic!("not a chunk");
+    };
+    assert_eq!(payload.decode().unwrap(), chunk);
+    // terrain is mostly long runs, it shrinks to a small part of its bincode
+    assert!(
+        (bytes.len() as u64) * 20 < raw_chunk_size(&chunk),
+        "{} bytes",
+        bytes.len()
+    );
+}
+
+#[test]
+fn single_element_chunks_only_send_the_element() {
+    for element in [Element::Air, Element::Stone] {
+        let chunk = build_chunk_at(POSITION, |_, _, _| element);
+        let payload = ChunkPayload::encode(&chunk).unwrap();
+        assert_eq!(
+            payload,
+            ChunkPayload::Uniform {
+                position: chunk.position(),
+                element
+            }
+        );
+        assert_eq!(payload.decode().unwrap(), chunk);
+        assert!(ServerMessage::Chunk(payload).encode().unwrap().len() < 32);
+    }
+
+    // one different block is enough to need the runs
+    let chunk = chunk_at(POSITION, &[(IVec3::splat(15), Element::Dirt)]);
+    let payload = ChunkPayload::encode(&chunk).unwrap();
+    assert!(!payload.is_uniform());
+    assert_eq!(payload.decode().unwrap(), chunk);
+}
+
+#[test]
+fn broken_payloads_are_errors() {
+    let ChunkPayload::Compressed { position, data } =
+        ChunkPayload::encode(&generate_chunk(1.0, 4.0, -2.0)).unwrap()
+    else {
+        panic!("terrain is not uniform");
+    };
+    for data in [vec![], vec![7; 40], data[..data.len() / 2].to_vec()] {
+        assert!(ChunkPayload::Compressed { position, data }
+            .decode()
+            .is_err());
+    }
+}
+
+#[test]
+fn payloads_that_inflate_too_far_are_rejected() {
+    // a few kilobytes that inflate to 8 MB
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    for _ in 0..8 {
+        encoder.write_all(&[0; 1024 * 1024]).unwrap();
+    }
+    let data = encoder.finish().unwrap();
+    assert!(data.len() < MAX_RUNS_BYTES);
+    let payload = ChunkPayload::Compressed {
+        position: IVec3::ZERO,
+        data,
+    };
+    assert!(matches!(payload.decode(), Err(ChunkWireError::TooLarge)));
+}
+
+#[test]
+fn transfer_stats_count_the_saved_bytes() {
+    let mut stats = ChunkTransferStats::default();
+    assert_eq!(stats.ratio(), 1.0);
+    stats.record(true, 16_000, 20);
+    stats.record(false, 16_000, 1_980);
+    assert_eq!(stats.chunks, 2);
+    assert_eq!(stats.uniform_chunks, 1);
+    assert_eq!(stats.saved_bytes(), 30_000);
+    assert_eq!(stats.ratio(), 2_000.0 / 32_000.0);
+    assert_eq!(
+        stats.to_string(),
+        "2 chunks (1 uniform), 1 KB sent for 31 KB, 29 KB saved (93.8%)"
+    );
+}
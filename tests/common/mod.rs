@@ -0,0 +1,166 @@
+// helpers shared by the integration tests, every test crate only uses some of them
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    render::mesh::{Indices, MeshVertexAttribute, VertexAttributeValues},
+};
+use bevy_renet::renet::{ClientId, RenetClient, RenetServer, ServerEvent};
+use voxels::{
+    net::connection_config,
+    player::{
+        events::PlayerSpawnEvent,
+        server::handshake::{ConnectedPlayers, PendingRejections},
+    },
+    world::{
+        block::Block,
+        chunk::{Chunk, ChunkRadius, CHUNK_DEPTH, CHUNK_HEIGHT, CHUNK_WIDTH},
+        client::packed::{PackedVertex, ATTRIBUTE_PACKED_VERTEX},
+        element::Element,
+    },
+};
+
+// a chunk at position with element_at(x, y, z) at every local block
+pub fn build_chunk_at(
+    position: IVec3,
+    element_at: impl Fn(usize, usize, usize) -> Element,
+) -> Chunk {
+    let mut chunk = Chunk {
+        chunk_x: position.x as f32,
+        chunk_y: position.y as f32,
+        chunk_z: position.z as f32,
+        blocks: [[[Block::default(); CHUNK_WIDTH]; CHUNK_HEIGHT]; CHUNK_DEPTH],
+    };
+    for x in 0..CHUNK_WIDTH {
+        for y in 0..CHUNK_HEIGHT {
+            for z in 0..CHUNK_DEPTH {
+                chunk.blocks[x][y][z] = Block::new(element_at(x, y, z));
+            }
+        }
+    }
+    chunk
+}
+
+pub fn build_chunk(element_at: impl Fn(usize, usize, usize) -> Element) -> Chunk {
+    build_chunk_at(IVec3::ZERO, element_at)
+}
+
+// a chunk of air at position with only the given blocks set
+pub fn chunk_at(position: IVec3, blocks: &[(IVec3, Element)]) -> Chunk {
+    let mut chunk = build_chunk_at(position, |_, _, _| Element::Air);
+    for (position, element) in blocks {
+        chunk.blocks[position.x as usize][position.y as usize][position.z as usize] =
+            Block::new(*element);
+    }
+    chunk
+}
+
+pub fn chunk_with(blocks: &[(IVec3, Element)]) -> Chunk {
+    chunk_at(IVec3::ZERO, blocks)
+}
+
+fn vec3s(mesh: &Mesh, attribute: MeshVertexAttribute) -> Vec<Vec3> {
+    let name = attribute.name;
+    match mesh.attribute(attribute) {
+        Some(VertexAttributeValues::Float32x3(values)) => {
+            values.iter().map(|value| Vec3::from(*value)).collect()
+        }
+        _ => panic!("mesh has no {}", name),
+    }
+}
+
+// the vertex attributes of a standard chunk mesh, one entry per vertex
+pub fn positions(mesh: &Mesh) -> Vec<Vec3> {
+    vec3s(mesh, Mesh::ATTRIBUTE_POSITION)
+}
+
+pub fn normals(mesh: &Mesh) -> Vec<Vec3> {
+    vec3s(mesh, Mesh::ATTRIBUTE_NORMAL)
+}
+
+pub fn uvs(mesh: &Mesh) -> Vec<Vec2> {
+    match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+        Some(VertexAttributeValues::Float32x2(values)) => {
+            values.iter().map(|value| Vec2::from(*value)).collect()
+        }
+        _ => panic!("mesh has no uvs"),
+    }
+}
+
+pub fn colors(mesh: &Mesh) -> Vec<Vec4> {
+    match mesh.attribute(Mesh::ATTRIBUTE_COLOR) {
+        Some(VertexAttributeValues::Float32x4(values)) => {
+            values.iter().map(|value| Vec4::from(*value)).collect()
+        }
+        _ => panic!("mesh has no colors"),
+    }
+}
+
+// the vertices of a packed chunk mesh
+pub fn packed_vertices(mesh: &Mesh) -> Vec<PackedVertex> {
+    match mesh.attribute(ATTRIBUTE_PACKED_VERTEX) {
+        Some(VertexAttributeValues::Uint32x2(values)) => values
+            .iter()
+            .map(|value| PackedVertex::unpack(*value))
+            .collect(),
+        _ => panic!("mesh has no packed vertices"),
+    }
+}
+
+pub fn indices(mesh: &Mesh) -> Vec<u32> {
+    match mesh.indices() {
+        Some(Indices::U32(indices)) => indices.clone(),
+        _ => panic!("mesh has no indices"),
+    }
+}
+
+// a server with the handshake resources and no systems, the tests add the ones they run
+pub fn server_app() -> App {
+    let mut app = App::new();
+    app.insert_resource(RenetServer::new(connection_config()))
+        .insert_resource(ChunkRadius { radius: 4 })
+        .init_resource::<ConnectedPlayers>()
+        .init_resource::<PendingRejections>()
+        .add_event::<ServerEvent>()
+        .add_event::<PlayerSpawnEvent>();
+    app
+}
+
+pub fn connect(app: &mut App, id: u64) -> RenetClient {
+    app.world
+        .resource_mut::<RenetServer>()
+        .add_connection(ClientId::from_raw(id));
+    let mut client = RenetClient::new(connection_config());
+    client.set_connected();
+    client
+}
+
+// moves the packets of both sides over and runs the server systems
+pub fn exchange(app: &mut App, clients: &mut [(u64, &mut RenetClient)]) {
+    for (id, client) in clients.iter_mut() {
+        client.update(Duration::ZERO);
+        let mut server = app.world.resource_mut::<RenetServer>();
+        for packet in client.get_packets_to_send() {
+            server
+                .process_packet_from(&packet, ClientId::from_raw(*id))
+                .unwrap();
+        }
+    }
+    app.world
+        .resource_mut::<RenetServer>()
+        .update(Duration::ZERO);
+    app.update();
+    for (id, client) in clients.iter_mut() {
+        let mut server = app.world.resource_mut::<RenetServer>();
+        server.update(Duration::ZERO);
+        let Ok(packets) = server.get_packets_to_send(ClientId::from_raw(*id)) else {
+            continue;
+        };
+        for packet in packets {
+            client.process_packet(&packet);
+        }
+        client.update(Duration::ZERO);
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::HashSet;
+
+use bevy::math::IVec3;
+use voxels::world::{
+    block::Block,
+    chunk::{border_neighbours, changed_border_neighbours, chunk_neighbourhood},
+    element::Element,
+    server::chunk::generate_chunk,
+};
+
+fn neighbours_of(x: i32, y: i32, z: i32) -> HashSet<IVec3> {
+    border_neighbours(IVec3::new(x, y, z)).collect()
+}
+
+#[test]
+fn inner_blocks_are_not_seen_by_neighbours() {
+    assert!(neighbours_of(5, 1, 14).is_empty());
+}
+
+#[test]
+fn border_blocks_are_seen_across_faces_edges_and_corners() {
+    assert_eq!(neighbours_of(0, 7, 7), HashSet::from([IVec3::NEG_X]));
+    assert_eq!(
+        neighbours_of(15, 0, 7),
+        HashSet::from([IVec3::X, IVec3::NEG_Y, IVec3::new(1, -1, 0)])
+    );
+    let corner = neighbours_of(0, 15, 0);
+    assert_eq!(corner.len(), 7);
+    assert!(corner.contains(&IVec3::new(-1, 1, -1)));
+}
+
+#[test]
+fn only_neighbours_next_to_changes_are_remeshed() {
+    let old = generate_chunk(0.0, 4.0, 0.0);
+    assert!(changed_border_neighbours(&old, &old).is_empty());
+
+    let mut new = old;
+    new.blocks[7][8][9] = Block::new(Element::Glass);
+    assert!(changed_border_neighbours(&old, &new).is_empty());
+
+    new.blocks[15][8][9] = Block::new(Element::Glass);
+    new.blocks[3][0][9] = Block::new(Element::Water);
+    assert_eq!(
+        changed_border_neighbours(&old, &new),
+        HashSet::from([IVec3::X, IVec3::NEG_Y])
+    );
+
+    for x in 0..16 {
+        for z in 0..16 {
+            for y in [0, 15] {
+                new.blocks[x][y][z] = Block::new(Element::Leaves);
+            }
+            new.blocks[x][7][0] = Block::new(Element::Leaves);
+            new.blocks[0][7][z] = Block::new(Element::Leaves);
+            new.blocks[15][7][z] = Block::new(Element::Leaves);
+            new.blocks[x][7][15] = Block::new(Element::Leaves);
+        }
+    }
+    assert_eq!(
+        changed_border_neighbours(&old, &new),
+        chunk_neighbourhood().collect()
+    );
+}
@@ -0,0 +1,68 @@
+use bevy::{math::Vec3, render::color::Color};
+use bevy_atmosphere::prelude::Nishita;
+use voxels::world::client::{
+    fade::{fade_step, FADE_DURATION, FADE_STEPS},
+    fog::{fog_distances, horizon_color, sky_color, FOG_START},
+};
+
+fn rgb(color: Color) -> Vec3 {
+    let [r, g, b, _] = color.as_linear_rgba_f32();
+    Vec3::new(r, g, b)
+}
+
+fn sky_at(sun_position: Vec3) -> Nishita {
+    Nishita {
+        sun_position,
+        ..Nishita::default()
+    }
+}
+
+#[test]
+fn fog_ends_at_the_view_radius() {
+    assert_eq!(fog_distances(3), (48.0 * FOG_START, 48.0));
+    let (near_start, near_end) = fog_distances(2);
+    let (far_start, far_end) = fog_distances(8);
+    assert!(near_start < near_end && far_start < far_end);
+    assert!(far_start > near_start && far_end > near_end);
+    // the chunk the camera is in is always clear of it
+    assert_eq!(fog_distances(0), fog_distances(1));
+}
+
+#[test]
+fn fog_matches_the_sky_at_the_time_of_day() {
+    let noon = sky_at(Vec3::Y);
+    let sunset = sky_at(Vec3::new(0.0, 0.02, -1.0));
+    let night = sky_at(Vec3::new(0.0, -1.0, 0.2));
+
+    let day = rgb(horizon_color(&noon, Vec3::NEG_Z));
+    assert!(day.z > day.x, "{}", day);
+    // towards the setting sun the light has passed through more air and lost its blue
+    let evening = rgb(horizon_color(&sunset, Vec3::NEG_Z));
+    assert!(evening.x / evening.z > day.x / day.z, "{} {}", evening, day);
+    let dark = rgb(horizon_color(&night, Vec3::NEG_Z));
+    assert!(dark.length() < day.length() * 0.01, "{} {}", dark, day);
+
+    // only the horizontal part of where the camera looks matters
+    assert_eq!(
+        horizon_color(&sunset, Vec3::new(0.0, -0.9, -0.1)),
+        horizon_color(&sunset, Vec3::NEG_Z)
+    );
+    assert_eq!(
+        horizon_color(&sunset, Vec3::new(0.0, 0.8, -0.6)),
+        sky_color(&sunset, Vec3::new(0.0, 0.05, -1.0))
+    );
+}
+
+#[test]
+fn new_chunks_fade_in_once() {
+    assert_eq!(fade_step(0.0), Some(0));
+    let steps: Vec<Option<usize>> = (0..=20)
+        .map(|frame| fade_step(frame as f32 * FADE_DURATION / 20.0))
+        .collect();
+    assert!(steps
+        .windows(2)
+        .all(|pair| pair[1].is_none() || pair[0] <= pair[1]));
+    assert_eq!(steps[19], Some(FADE_STEPS - 1));
+    assert_eq!(fade_step(FADE_DURATION), None);
+    assert_eq!(fade_step(FADE_DURATION * 3.0), None);
+}
@@ -0,0 +1,302 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    fs,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+};
+
+use bevy::{
+    math::{IVec3, Vec2, Vec3},
+    render::mesh::Mesh,
+};
+use voxels::world::{
+    block::{decode_tiled_uv, BlockTexture},
+    chunk::{Chunk, ChunkView, CHUNK_WIDTH},
+    client::{
+        greedy_mesh::gen_greedy_mesh,
+        mesh_utils::{gen_mesh, MeshPass},
+    },
+    element::Element,
+};
+
+mod common;
+
+use common::{chunk_at, chunk_with, colors, indices, normals, positions, uvs};
+
+// Golden mesh tests: small fixture chunks are meshed on the cpu by both meshers and every mesh is
+// checked for the invariants any chunk mesh has to hold, then compared against the snapshot in
+// tests/golden. after an intended change to the meshes run
+//     UPDATE_GOLDEN=1 cargo test --test golden_mesh
+// and review the diff of the snapshots.
+
+fn cuboid(from: IVec3, to: IVec3, element: Element) -> Vec<(IVec3, Element)> {
+    let mut blocks = Vec::new();
+    for x in from.x..=to.x {
+        for y in from.y..=to.y {
+            for z in from.z..=to.z {
+                blocks.push((IVec3::new(x, y, z), element));
+            }
+        }
+    }
+    blocks
+}
+
+// the fixtures, the first chunk is the one that is meshed and the others are its neighbours
+fn fixtures() -> Vec<(&'static str, Vec<Chunk>)> {
+    let mut steps = Vec::new();
+    for x in 0..3 {
+        steps.extend(cuboid(
+            IVec3::new(x, 0, 0),
+            IVec3::new(x, x, 2),
+            Element::Dirt,
+        ));
+        steps.push((IVec3::new(x, x + 1, 1), Element::Grass));
+    }
+    let mut pool = cuboid(IVec3::new(1, 0, 1), IVec3::new(3, 0, 2), Element::Stone);
+    pool.extend(cuboid(
+        IVec3::new(1, 1, 1),
+        IVec3::new(2, 1, 2),
+        Element::Water,
+    ));
+    pool.push((IVec3::new(3, 1, 1), Element::Glass));
+    pool.push((IVec3::new(3, 1, 2), Element::Leaves));
+    let mut shapes = cuboid(IVec3::new(0, 0, 0), IVec3::new(4, 0, 0), Element::Stone);
+    shapes.extend([
+        (IVec3::new(0, 1, 0), Element::StoneSlab),
+        (IVec3::new(1, 1, 0), Element::StoneStairs),
+        (IVec3::new(2, 1, 0), Element::Fence),
+        (IVec3::new(3, 1, 0), Element::Flower),
+        (IVec3::new(4, 1, 0), Element::Torch),
+    ]);
+    let last = CHUNK_WIDTH as i32 - 1;
+    vec![
+        (
+            "single_block",
+            vec![chunk_with(&[(IVec3::new(1, 1, 1), Element::Grass)])],
+        ),
+        (
+            "solid_cube",
+            vec![chunk_with(&cuboid(
+                IVec3::ONE,
+                IVec3::splat(2),
+                Element::Stone,
+            ))],
+        ),
+        ("grass_steps", vec![chunk_with(&steps)]),
+        ("transparent_pool", vec![chunk_with(&pool)]),
+        ("block_models", vec![chunk_with(&shapes)]),
+        (
+            "chunk_border",
+            vec![
+                chunk_with(&[(IVec3::new(last, 2, 2), Element::Stone)]),
+                chunk_at(IVec3::X, &[(IVec3::new(0, 2, 2), Element::Stone)]),
+            ],
+        ),
+    ]
+}
+
+// the texture whose atlas rect a uv lies in, if it lies inside a texture and not in a gutter
+fn texture_cell(uv: Vec2) -> Option<u32> {
+    BlockTexture::ALL
+        .iter()
+        .find(|texture| texture.atlas_rect().inset(1e-4).contains(uv))
+        .map(|texture| *texture as u32)
+}
+
+// the texture layer stored in a tiled uv, see get_tiled_texture
+fn tiled_layer(uv: Vec2) -> Option<u32> {
+    let (layer, _) = decode_tiled_uv(uv);
+    ((layer as usize) < BlockTexture::ALL.len()).then_some(layer)
+}
+
+fn check_invariants(name: &str, view: &ChunkView, mesh: &Mesh, tiled: bool) {
+    let positions = positions(mesh);
+    let normals = normals(mesh);
+    let uvs = uvs(mesh);
+    let indices = indices(mesh);
+    let origin = view.chunk.world_origin();
+    assert_eq!(positions.len() % 4, 0, "{}: meshes are made of quads", name);
+    assert_eq!(indices.len(), positions.len() / 4 * 6, "{}", name);
+
+    for (quad, triangles) in indices.chunks(6).enumerate() {
+        let first = quad as u32 * 4;
+        assert!(
+            triangles
+                .iter()
+                .all(|index| (first..first + 4).contains(index)),
+            "{}: quad {} uses vertices of another quad",
+            name,
+            quad
+        );
+        let normal = normals[first as usize];
+        assert!((normal.length() - 1.0).abs() < 1e-4, "{}", name);
+
+        // the front of every triangle is on the side its normal points to
+        for triangle in triangles.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i] as usize]);
+            let facing = (b - a).cross(c - a).normalize_or_zero();
+            assert!(
+                facing.dot(normal) > 0.99,
+                "{}: quad {} is wound against its normal {}",
+                name,
+                quad,
+                normal
+            );
+        }
+
+        // the face belongs to the block behind it and is never hidden inside a solid block
+        let corners = &positions[first as usize..first as usize + 4];
+        let center = corners.iter().sum::<Vec3>() / 4.0;
+        let block = |offset: f32| {
+            let world = (center + normal * offset).floor().as_ivec3();
+            view.element(world - origin)
+        };
+        assert_ne!(
+            block(-0.01),
+            Element::Air,
+            "{}: quad {} at {} faces inwards",
+            name,
+            quad,
+            center
+        );
+        assert!(
+            !block(0.01).is_opaque(),
+            "{}: quad {} at {} is hidden by a solid neighbour",
+            name,
+            quad,
+            center
+        );
+
+        // every corner samples the same texture
+        let quad_uvs = &uvs[first as usize..first as usize + 4];
+        let cells: Vec<Option<u32>> = if tiled {
+            quad_uvs.iter().map(|uv| tiled_layer(*uv)).collect()
+        } else {
+            quad_uvs.iter().map(|uv| texture_cell(*uv)).collect()
+        };
+        assert!(
+            cells[0].is_some() && cells.iter().all(|cell| *cell == cells[0]),
+            "{}: quad {} has uvs {:?} outside of a single texture",
+            name,
+            quad,
+            quad_uvs
+        );
+    }
+}
+
+fn number(value: f32) -> String {
+    // no negative zeros, they would make the snapshots flicker
+    let value = if value.abs() < 5e-5 { 0.0 } else { value };
+    format!("{:.4}", value)
+}
+
+fn snapshot(mesh: &Mesh, out: &mut String) {
+    let positions = positions(mesh);
+    let normals = normals(mesh);
+    let uvs = uvs(mesh);
+    let colors = colors(mesh);
+    for (quad, triangles) in indices(mesh).chunks(6).enumerate() {
+        for vertex in quad * 4..quad * 4 + 4 {
+            let line = [
+                positions[vertex].to_array().map(number).join(" "),
+                normals[vertex].to_array().map(number).join(" "),
+                uvs[vertex].to_array().map(number).join(" "),
+                colors[vertex].to_array().map(number).join(" "),
+            ]
+            .join(" | ");
+            writeln!(out, "v {}", line).unwrap();
+        }
+        let triangles: Vec<String> = triangles.iter().map(|index| index.to_string()).collect();
+        writeln!(out, "i {}", triangles.join(" ")).unwrap();
+    }
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.txt", name))
+}
+
+#[test]
+fn meshes_match_their_golden_snapshots() {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let mut failures = Vec::new();
+    for (name, chunks) in fixtures() {
+        let neighbours: HashMap<IVec3, &Chunk> = chunks
+            .iter()
+            .map(|chunk| (chunk.position(), chunk))
+            .collect();
+        let view = ChunkView::new(&chunks[0], &neighbours);
+
+        let mut out = String::new();
+        for pass in MeshPass::ALL {
+            for (mesher, tiled) in [("gen_mesh", false), ("gen_greedy_mesh", true)] {
+                let mesh = match tiled {
+                    false => gen_mesh(1.0, &view, pass),
+                    true => gen_greedy_mesh(1.0, &view, pass),
+                };
+                let label = format!("{} {} {:?}", name, mesher, pass);
+                check_invariants(&label, &view, &mesh, tiled);
+                writeln!(out, "# {} {:?}", mesher, pass).unwrap();
+                snapshot(&mesh, &mut out);
+            }
+        }
+
+        let path = golden_path(name);
+        if update {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, &out).unwrap();
+            continue;
+        }
+        let Ok(golden) = fs::read_to_string(&path) else {
+            failures.push(format!("{}: no snapshot at {}", name, path.display()));
+            continue;
+        };
+        if let Some((line, (expected, actual))) = golden
+            .lines()
+            .zip(out.lines())
+            .enumerate()
+            .find(|(_, (expected, actual))| expected != actual)
+        {
+            failures.push(format!(
+                "{}: line {} differs\n  expected {}\n  actual   {}",
+                name,
+                line + 1,
+                expected,
+                actual
+            ));
+        } else if golden.lines().count() != out.lines().count() {
+            failures.push(format!(
+                "{}: expected {} lines, got {}",
+                name,
+                golden.lines().count(),
+                out.lines().count()
+            ));
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "meshes differ from their snapshots, rerun with UPDATE_GOLDEN=1 if that is intended\n{}",
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn invariants_catch_flipped_normals() {
+    // the invariants have to catch a flipped normal, like the top and bottom faces once had
+    let chunk = chunk_with(&[(IVec3::new(1, 1, 1), Element::Stone)]);
+    let view = ChunkView::without_neighbours(&chunk);
+    let mesh = gen_mesh(1.0, &view, MeshPass::Opaque);
+    let mut flipped = mesh.clone();
+    let normals: Vec<[f32; 3]> = normals(&mesh)
+        .iter()
+        .map(|normal| (-*normal).to_array())
+        .collect();
+    flipped.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    check_invariants("stone", &view, &mesh, false);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        check_invariants("flipped", &view, &flipped, false)
+    }));
+    assert!(result.is_err());
+}
@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+
+use bevy::math::{IVec3, Vec2, Vec3};
+use voxels::world::{
+    block::{decode_tiled_uv, get_texture, get_tiled_texture, Block, BlockTexture},
+    chunk::{Chunk, ChunkView, CHUNK_DEPTH, CHUNK_HEIGHT, CHUNK_NEIGHBOURS, CHUNK_WIDTH},
+    client::{
+        greedy_mesh::gen_greedy_mesh,
+        mesh_utils::{gen_mesh, MeshPass},
+    },
+    element::Element,
+    server::chunk::{generate_chunk, get_surrounding_chunks},
+};
+
+mod common;
+
+use common::{build_chunk_at, colors, indices, positions, uvs};
+
+// the chunk the tests mesh, away from the origin
+const MESHED_CHUNK: IVec3 = IVec3::new(1, 2, -1);
+
+fn random_chunk(seed: u64) -> Chunk {
+    let mut rng = fastrand::Rng::with_seed(seed);
+    let elements = [
+        Element::Air,
+        Element::Dirt,
+        Element::Grass,
+        Element::Stone,
+        Element::Glass,
+        Element::Water,
+    ];
+    let mut chunk = build_chunk_at(MESHED_CHUNK, |_, _, _| Element::Air);
+    for x in 0..CHUNK_WIDTH {
+        for y in 0..CHUNK_HEIGHT {
+            for z in 0..CHUNK_DEPTH {
+                chunk.blocks[x][y][z] = Block::new(elements[rng.usize(0..elements.len())]);
+            }
+        }
+    }
+    chunk
+}
+
+// where a point of the texture in layer lies in the block atlas, like the atlas uvs of gen_mesh
+fn atlas_uv((layer, point): (u32, Vec2)) -> Vec2 {
+    let corners = get_texture(BlockTexture::ALL[layer as usize].atlas_rect());
+    corners[0] + point.x * (corners[1] - corners[0]) + point.y * (corners[3] - corners[0])
+}
+
+// every visible block face as (center of the face, atlas uv at the center of the face,
+// brightness of its corners), sorted. two meshes that produce the same list look the same.
+fn rendered_faces_naive(view: &ChunkView, pass: MeshPass) -> Vec<FaceKey> {
+    let mesh = gen_mesh(1.0, view, pass);
+    let mut faces: Vec<FaceKey> = positions(&mesh)
+        .chunks_exact(4)
+        .zip(uvs(&mesh).chunks_exact(4))
+        .zip(colors(&mesh).chunks_exact(4))
+        .map(|((quad_positions, quad_uvs), quad_colors)| {
+            let center = quad_positions.iter().sum::<Vec3>() / 4.0;
+            let uv = quad_uvs.iter().sum::<Vec2>() / 4.0;
+            let brightness = [0, 1, 2, 3].map(|i| quad_colors[i].x);
+            face_key(center, uv, brightness)
+        })
+        .collect();
+    faces.sort();
+    faces
+}
+
+fn rendered_faces_greedy(view: &ChunkView, pass: MeshPass) -> Vec<FaceKey> {
+    let mesh = gen_greedy_mesh(1.0, view, pass);
+    let positions = positions(&mesh);
+    let uvs = uvs(&mesh);
+    let colors = colors(&mesh);
+    let mut faces = Vec::new();
+    for quad in 0..positions.len() / 4 {
+        let corner = |i: usize| positions[quad * 4 + i];
+        let uv_corner = |i: usize| uvs[quad * 4 + i];
+        let size = uv_corner(2) - uv_corner(0);
+        let u = (corner(1) - corner(0)) / size.x;
+        let v = (corner(3) - corner(0)) / size.y;
+        // the brightness at a point of the quad, in blocks from its first corner
+        let brightness_at = |point: Vec2| {
+            let [a, b, c, d] = [0, 1, 2, 3].map(|i| colors[quad * 4 + i].x);
+            let t = point / size;
+            (a * (1.0 - t.x) + b * t.x) * (1.0 - t.y) + (d * (1.0 - t.x) + c * t.x) * t.y
+        };
+        for du in 0..size.x as i32 {
+            for dv in 0..size.y as i32 {
+                let cell = Vec2::new(du as f32, dv as f32);
+                let local = cell + Vec2::splat(0.5);
+                let center = corner(0) + u * local.x + v * local.y;
+                let brightness = [
+                    Vec2::ZERO,
+                    Vec2::new(1.0, 0.0),
+                    Vec2::ONE,
+                    Vec2::new(0.0, 1.0),
+                ]
+                .map(|offset| brightness_at(cell + offset));
+                faces.push(face_key(
+                    center,
+                    atlas_uv(decode_tiled_uv(uv_corner(0) + local)),
+                    brightness,
+                ));
+            }
+        }
+    }
+    faces.sort();
+    faces
+}
+
+type FaceKey = [i64; 9];
+
+fn face_key(center: Vec3, uv: Vec2, brightness: [f32; 4]) -> FaceKey {
+    let round = |value: f32| (value * 1024.0).round() as i64;
+    [
+        round(center.x),
+        round(center.y),
+        round(center.z),
+        round(uv.x),
+        round(uv.y),
+        round(brightness[0]),
+        round(brightness[1]),
+        round(brightness[2]),
+        round(brightness[3]),
+    ]
+}
+
+#[test]
+fn flat_plane_merges_into_one_quad_per_side() {
+    let chunk = build_chunk_at(MESHED_CHUNK, |_, y, _| {
+        if y == 0 {
+            Element::Grass
+        } else {
+            Element::Air
+        }
+    });
+    let naive = gen_mesh(
+        1.0,
+        &ChunkView::without_neighbours(&chunk),
+        MeshPass::Opaque,
+    );
+    let greedy = gen_greedy_mesh(
+        1.0,
+        &ChunkView::without_neighbours(&chunk),
+        MeshPass::Opaque,
+    );
+    // top, bottom and the four border walls
+    assert_eq!(greedy.count_vertices(), 6 * 4);
+    assert_eq!(naive.count_vertices(), (256 * 2 + 4 * 16) * 4);
+    assert_eq!(indices(&greedy).len(), 6 * 6);
+}
+
+#[test]
+fn empty_chunk_has_no_faces() {
+    let chunk = build_chunk_at(MESHED_CHUNK, |_, _, _| Element::Air);
+    assert_eq!(
+        gen_greedy_mesh(
+            1.0,
+            &ChunkView::without_neighbours(&chunk),
+            MeshPass::Opaque
+        )
+        .count_vertices(),
+        0
+    );
+}
+
+#[test]
+fn different_textures_are_not_merged() {
+    let chunk = build_chunk_at(MESHED_CHUNK, |x, y, _| match (y, x % 2) {
+        (0, 0) => Element::Grass,
+        (0, _) => Element::Stone,
+        _ => Element::Air,
+    });
+    let greedy = gen_greedy_mesh(
+        1.0,
+        &ChunkView::without_neighbours(&chunk),
+        MeshPass::Opaque,
+    );
+    // 16 stripes on top and bottom, the walls along x alternate every block
+    assert_eq!(greedy.count_vertices(), (16 + 16 + 2 + 16 + 16) * 4);
+}
+
+#[test]
+fn greedy_mesh_looks_like_naive_mesh() {
+    let chunks = [
+        build_chunk_at(MESHED_CHUNK, |_, y, _| {
+            if y < 5 {
+                Element::Stone
+            } else {
+                Element::Air
+            }
+        }),
+        random_chunk(1),
+        random_chunk(2),
+        generate_chunk(0.0, 4.0, 0.0),
+        generate_chunk(-3.0, 3.0, 2.0),
+    ];
+    for chunk in chunks.iter() {
+        let view = ChunkView::without_neighbours(chunk);
+        for pass in MeshPass::ALL {
+            assert_eq!(
+                rendered_faces_greedy(&view, pass),
+                rendered_faces_naive(&view, pass)
+            );
+        }
+    }
+}
+
+#[test]
+fn greedy_mesh_has_fewer_vertices() {
+    for chunk in [generate_chunk(0.0, 4.0, 0.0), random_chunk(3)] {
+        let naive = gen_mesh(
+            1.0,
+            &ChunkView::without_neighbours(&chunk),
+            MeshPass::Opaque,
+        )
+        .count_vertices();
+        let greedy = gen_greedy_mesh(
+            1.0,
+            &ChunkView::without_neighbours(&chunk),
+            MeshPass::Opaque,
+        )
+        .count_vertices();
+        assert!(greedy <= naive, "greedy {} naive {}", greedy, naive);
+    }
+}
+
+#[test]
+fn tiled_uvs_repeat_the_texture_layer() {
+    let layer = BlockTexture::GrassSide.layer();
+    let uvs = get_tiled_texture(layer, 3.0, 5.0);
+    for (du, dv) in [(0.5, 0.5), (1.25, 3.75), (2.9, 4.1)] {
+        let local = Vec2::new(du, dv);
+        let (decoded_layer, point) = decode_tiled_uv(uvs[0] + local);
+        assert_eq!(decoded_layer, layer);
+        assert!((point - local.fract()).length() < 1e-5);
+    }
+    // the last layer still decodes to itself at the far edge of a chunk sized face
+    let last = BlockTexture::ALL.len() as u32 - 1;
+    let uvs = get_tiled_texture(last, 16.0, 16.0);
+    assert_eq!(decode_tiled_uv(uvs[2] - Vec2::splat(1e-3)).0, last);
+}
+
+#[test]
+fn faces_against_loaded_neighbours_are_culled() {
+    let solid = |position: IVec3| build_chunk_at(position, |_, _, _| Element::Stone);
+    let center = solid(IVec3::ZERO);
+    let neighbours: Vec<Chunk> = CHUNK_NEIGHBOURS
+        .iter()
+        .map(|offset| solid(*offset))
+        .collect();
+    let mut chunks: HashMap<IVec3, &Chunk> = neighbours
+        .iter()
+        .map(|chunk| (chunk.position(), chunk))
+        .collect();
+    chunks.insert(center.position(), &center);
+
+    let view = ChunkView::new(&center, &chunks);
+    assert_eq!(gen_mesh(1.0, &view, MeshPass::Opaque).count_vertices(), 0);
+    assert_eq!(
+        gen_greedy_mesh(1.0, &view, MeshPass::Opaque).count_vertices(),
+        0
+    );
+
+    // without the chunk above only the top stays visible
+    chunks.remove(&IVec3::Y);
+    let view = ChunkView::new(&center, &chunks);
+    assert_eq!(
+        gen_mesh(1.0, &view, MeshPass::Opaque).count_vertices(),
+        256 * 4
+    );
+    assert_eq!(
+        gen_greedy_mesh(1.0, &view, MeshPass::Opaque).count_vertices(),
+        4
+    );
+}
+
+#[test]
+fn greedy_mesh_looks_like_naive_mesh_across_chunks() {
+    let chunks: Vec<Chunk> = get_surrounding_chunks(0, 4, 0, 1)
+        .into_iter()
+        .map(|position| generate_chunk(position.x, position.y, position.z))
+        .collect();
+    let chunk_map: HashMap<IVec3, &Chunk> = chunks
+        .iter()
+        .map(|chunk| (chunk.position(), chunk))
+        .collect();
+    for chunk in chunks.iter() {
+        let view = ChunkView::new(chunk, &chunk_map);
+        for pass in MeshPass::ALL {
+            assert_eq!(
+                rendered_faces_greedy(&view, pass),
+                rendered_faces_naive(&view, pass)
+            );
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use bevy::prelude::*;
+use bevy_renet::renet::ClientId;
+use voxels::world::{
+    block::Block,
+    chunk::{Chunk, ChunkRegistry},
+    element::Element,
+    server::{
+        chunk::{generate_chunk, load_chunk, LoadedChunks},
+        edit::{apply_block_edits, EditedChunks, MAX_EDITED_BYTES},
+        events::{
+            BlockEditEvent, ChunkCreatedEvent, ChunkUpdatedEvent, EditSource, PrepareChunkLoadEvent,
+        },
+        history::{BlockChange, EditHistory},
+    },
+};
+
+const ALICE: ClientId = ClientId::from_raw(1);
+const BOB: ClientId = ClientId::from_raw(2);
+
+fn change(x: i32, old: Element, new: Element) -> BlockChange {
+    BlockChange {
+        position: IVec3::new(x, 70, 0),
+        old,
+        new,
+    }
+}
+
+#[test]
+fn undo_and_redo_replay_operations() {
+    let mut history = EditHistory::default();
+    history.record(
+        EditSource::Player(ALICE),
+        vec![
+            change(0, Element::Air, Element::Stone),
+            change(1, Element::Grass, Element::Dirt),
+        ],
+    );
+    assert_eq!(history.undo_len(ALICE), 1);
+
+    // undo restores the old blocks, the last change first
+    assert_eq!(
+        history.take_undo(ALICE),
+        Some(vec![
+            (IVec3::new(1, 70, 0), Element::Grass),
+            (IVec3::new(0, 70, 0), Element::Air),
+        ])
+    );
+    assert_eq!(history.take_undo(ALICE), None);
+    // a redo right after the undo finds it, before the undo was even applied
+    assert_eq!(
+        history.take_redo(ALICE),
+        Some(vec![
+            (IVec3::new(0, 70, 0), Element::Stone),
+            (IVec3::new(1, 70, 0), Element::Dirt),
+        ])
+    );
+    assert_eq!(history.take_redo(ALICE), None);
+    assert_eq!(history.undo_len(ALICE), 1);
+
+    // applying undos and redos does not record them again
+    history.record(
+        EditSource::Undo(ALICE),
+        vec![change(0, Element::Stone, Element::Air)],
+    );
+    history.record(
+        EditSource::Server,
+        vec![change(0, Element::Stone, Element::Air)],
+    );
+    assert_eq!(history.undo_len(ALICE), 1);
+
+    // a new edit forgets what could be redone
+    history.take_undo(ALICE);
+    assert_eq!(history.redo_len(ALICE), 1);
+    history.record(
+        EditSource::Player(ALICE),
+        vec![change(2, Element::Air, Element::Glass)],
+    );
+    assert_eq!(history.redo_len(ALICE), 0);
+    assert_eq!(history.take_redo(ALICE), None);
+}
+
+#[test]
+fn players_have_their_own_history() {
+    let mut history = EditHistory::default();
+    history.record(
+        EditSource::Player(ALICE),
+        vec![change(0, Element::Air, Element::Stone)],
+    );
+    history.record(
+        EditSource::Player(BOB),
+        vec![change(1, Element::Air, Element::Dirt)],
+    );
+    assert_eq!(
+        history.take_undo(BOB),
+        Some(vec![(IVec3::new(1, 70, 0), Element::Air)])
+    );
+    assert_eq!(history.take_undo(BOB), None);
+    // bob's new edit does not clear what alice could redo
+    history.take_undo(ALICE);
+    history.record(
+        EditSource::Player(BOB),
+        vec![change(2, Element::Air, Element::Glass)],
+    );
+    assert_eq!(history.redo_len(ALICE), 1);
+    assert_eq!(history.undo_len(ClientId::from_raw(3)), 0);
+    assert_eq!(history.take_undo(ClientId::from_raw(3)), None);
+}
+
+#[test]
+fn history_stays_within_its_budget() {
+    let mut history = EditHistory::default();
+    history.max_bytes = 3 * std::mem::size_of::<BlockChange>();
+    let operation = |x| {
+        vec![
+            change(x, Element::Air, Element::Stone),
+            change(x + 1, Element::Air, Element::Stone),
+        ]
+    };
+    history.record(EditSource::Player(ALICE), operation(0));
+    // the oldest operation makes room for the new one
+    history.record(EditSource::Player(ALICE), operation(10));
+    assert_eq!(history.undo_len(ALICE), 1);
+    assert_eq!(
+        history.take_undo(ALICE).unwrap()[0].0,
+        IVec3::new(11, 70, 0)
+    );
+
+    // an operation larger than the whole budget can not be undone
+    history.take_redo(ALICE);
+    history.record(
+        EditSource::Player(ALICE),
+        (0..4)
+            .map(|x| change(x, Element::Air, Element::Dirt))
+            .collect(),
+    );
+    assert_eq!(history.undo_len(ALICE), 1);
+    assert_eq!(history.redo_len(ALICE), 0);
+}
+
+fn edit_app() -> App {
+    let mut app = App::new();
+    app.init_resource::<ChunkRegistry>()
+        .init_resource::<EditHistory>()
+        .init_resource::<EditedChunks>()
+        .init_resource::<LoadedChunks>()
+        .add_event::<BlockEditEvent>()
+        .add_event::<ChunkUpdatedEvent>()
+        .add_event::<PrepareChunkLoadEvent>()
+        .add_event::<ChunkCreatedEvent>()
+        .add_systems(Update, (apply_block_edits, load_chunk).chain());
+    app
+}
+
+fn loaded_element(app: &mut App, [x, y, z]: [usize; 3]) -> Option<Element> {
+    let mut query = app.world.query::<&Chunk>();
+    let chunk = query.iter(&app.world).next()?;
+    Some(chunk.blocks[x][y][z].element)
+}
+
+#[test]
+fn edits_wait_for_their_chunk_to_load() {
+    let mut app = edit_app();
+    // block 3, 6, 5 of the chunk at 0, 4, 0
+    let position = IVec3::new(3, 70, 5);
+    let generated = generate_chunk(0.0, 4.0, 0.0);
+    let old = generated.blocks[3][6][5].element;
+    app.world.send_event(BlockEditEvent {
+        changes: vec![(position, Element::Glass)],
+        source: EditSource::Player(ALICE),
+    });
+    app.update();
+    assert_eq!(loaded_element(&mut app, [3, 6, 5]), None);
+    assert!(app.world.resource::<Events<ChunkUpdatedEvent>>().is_empty());
+    assert_eq!(app.world.resource::<EditHistory>().undo_len(ALICE), 1);
+
+    // the chunk is generated as usual, the edited copy is spawned instead
+    app.world
+        .send_event(PrepareChunkLoadEvent { chunk: generated });
+    app.update();
+    assert_eq!(loaded_element(&mut app, [3, 6, 5]), Some(Element::Glass));
+    assert!(app.world.resource::<EditedChunks>().is_empty());
+
+    // and the edit can be undone like any other
+    let changes = app
+        .world
+        .resource_mut::<EditHistory>()
+        .take_undo(ALICE)
+        .unwrap();
+    assert_eq!(changes, vec![(position, old)]);
+    app.world.send_event(BlockEditEvent {
+        changes,
+        source: EditSource::Undo(ALICE),
+    });
+    app.update();
+    assert_eq!(loaded_element(&mut app, [3, 6, 5]), Some(old));
+    assert_eq!(app.world.resource::<Events<ChunkUpdatedEvent>>().len(), 1);
+}
+
+#[test]
+fn unloaded_chunks_keep_their_edits() {
+    let mut edited_chunks = EditedChunks::default();
+    let mut chunk = generate_chunk(0.0, 4.0, 0.0);
+    // untouched chunks have nothing to keep
+    assert!(edited_chunks.store(&chunk));
+    assert!(edited_chunks.is_empty());
+
+    chunk.blocks[3][6][5] = Block::new(Element::Glass);
+    edited_chunks.mark_modified(chunk.position());
+    // no room, the chunk has to stay loaded
+    edited_chunks.max_bytes = 0;
+    assert!(!edited_chunks.store(&chunk));
+    edited_chunks.max_bytes = MAX_EDITED_BYTES;
+    assert!(edited_chunks.store(&chunk));
+    assert!(!edited_chunks.is_empty());
+
+    let mut reloaded = generate_chunk(0.0, 4.0, 0.0);
+    edited_chunks.apply(&mut reloaded);
+    assert_eq!(reloaded.blocks, chunk.blocks);
+    assert!(edited_chunks.is_empty());
+}
+
+#[test]
+fn waiting_edits_stay_within_the_world_and_their_budget() {
+    let mut app = edit_app();
+    let send = |app: &mut App, changes: Vec<(IVec3, Element)>, source: EditSource| {
+        app.world.send_event(BlockEditEvent { changes, source });
+        app.update();
+    };
+    let edit = |app: &mut App, changes| send(app, changes, EditSource::Player(ALICE));
+
+    // above and below the world nothing is kept or recorded
+    edit(
+        &mut app,
+        vec![
+            (IVec3::new(0, 100_000, 0), Element::Glass),
+            (IVec3::new(0, -1, 0), Element::Glass),
+        ],
+    );
+    assert!(app.world.resource::<EditedChunks>().is_empty());
+    assert_eq!(app.world.resource::<EditHistory>().undo_len(ALICE), 0);
+
+    // only the changed block is kept, not its chunk
+    edit(&mut app, vec![(IVec3::new(0, 70, 0), Element::Glass)]);
+    let block_bytes = app.world.resource::<EditedChunks>().bytes();
+    assert!(block_bytes > 0 && block_bytes < 64);
+
+    // past the budget further blocks are dropped and not recorded either
+    app.world.resource_mut::<EditedChunks>().max_bytes = 2 * block_bytes;
+    edit(
+        &mut app,
+        (1..4)
+            .map(|x| (IVec3::new(x * 100, 70, 0), Element::Glass))
+            .collect(),
+    );
+    assert_eq!(
+        app.world.resource::<EditedChunks>().bytes(),
+        2 * block_bytes
+    );
+    let changes = app
+        .world
+        .resource_mut::<EditHistory>()
+        .take_undo(ALICE)
+        .unwrap();
+    assert_eq!(changes.len(), 1);
+
+    // undoing back to the generated blocks frees them again
+    send(&mut app, changes, EditSource::Undo(ALICE));
+    assert_eq!(app.world.resource::<EditedChunks>().bytes(), block_bytes);
+    let changes = app
+        .world
+        .resource_mut::<EditHistory>()
+        .take_undo(ALICE)
+        .unwrap();
+    send(&mut app, changes, EditSource::Undo(ALICE));
+    assert!(app.world.resource::<EditedChunks>().is_empty());
+}
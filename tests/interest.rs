@@ -0,0 +1,198 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use bevy_renet::renet::{ClientId, RenetClient, RenetServer, ServerEvent};
+use voxels::{
+    net::{ClientMessage, ClientPlayer, ServerChannel, ServerMessage},
+    player::server::{handshake::receive_handshakes, setup::SPAWN_POSITION},
+    world::{
+        chunk::{Chunk, ChunkQueue, ChunkRegistry, CHUNK_VERT},
+        server::{
+            chunk::{load_chunk, load_chunk_from_queue, LoadedChunks},
+            edit::EditedChunks,
+            events::{ChunkCreatedEvent, PrepareChunkLoadEvent},
+            interest::{chunk_at, chunks_in_view, update_chunk_interest, ChunkInterest},
+        },
+        wire::ChunkTransferStats,
+    },
+};
+
+mod common;
+
+use common::{connect, exchange};
+
+#[test]
+fn moving_unloads_what_left_the_view() {
+    let mut interest = ChunkInterest::default();
+    let start = IVec3::new(0, 4, 0);
+    assert_eq!(interest.update(start, 1), Some(Vec::new()));
+    // nothing changes while the player stays in the same chunk
+    assert_eq!(interest.update(start, 1), None);
+
+    let missing = interest.missing();
+    assert_eq!(missing.len(), 5 * CHUNK_VERT as usize);
+    // the column the player stands in comes first, starting at its height
+    assert_eq!(missing[0], start);
+    assert!(missing[..CHUNK_VERT as usize]
+        .iter()
+        .all(|position| position.x == 0 && position.z == 0));
+
+    for position in missing {
+        interest.mark_sent(position);
+    }
+    assert!(interest.missing().is_empty());
+
+    let unloaded = interest.update(IVec3::new(1, 4, 0), 1).unwrap();
+    let expected: Vec<IVec3> = [
+        IVec3::new(-1, 0, 0),
+        IVec3::new(0, 0, -1),
+        IVec3::new(0, 0, 1),
+    ]
+    .into_iter()
+    .flat_map(|column| (0..CHUNK_VERT).map(move |y| column + IVec3::Y * y))
+    .collect();
+    assert_eq!(
+        unloaded.iter().copied().collect::<HashSet<_>>(),
+        expected.iter().copied().collect::<HashSet<_>>()
+    );
+    assert!(!interest.has_sent(IVec3::new(-1, 0, 0)));
+    assert!(interest.has_sent(IVec3::new(0, 0, 0)));
+    let missing: HashSet<IVec3> = interest.missing().into_iter().collect();
+    assert_eq!(missing.len(), 3 * CHUNK_VERT as usize);
+    assert!(missing.contains(&IVec3::new(2, 0, 0)));
+
+    // a larger view distance only adds chunks
+    assert_eq!(interest.update(IVec3::new(1, 4, 0), 2), Some(Vec::new()));
+    // after a reset everything in view is missing again and the area is rebuilt
+    interest.reset();
+    assert_eq!(interest.missing().len(), 13 * CHUNK_VERT as usize);
+    assert_eq!(interest.update(IVec3::new(1, 4, 0), 2), Some(Vec::new()));
+}
+
+fn interest_app() -> App {
+    let mut app = common::server_app();
+    app.init_resource::<ChunkRegistry>()
+        .insert_resource(ChunkQueue { chunks: Vec::new() })
+        .init_resource::<ChunkTransferStats>()
+        .init_resource::<EditedChunks>()
+        .init_resource::<LoadedChunks>()
+        .add_event::<PrepareChunkLoadEvent>()
+        .add_event::<ChunkCreatedEvent>()
+        .add_systems(
+            Update,
+            (
+                receive_handshakes,
+                load_chunk_from_queue,
+                load_chunk,
+                apply_deferred,
+                update_chunk_interest,
+            )
+                .chain(),
+        );
+    app
+}
+
+const CLIENT: u64 = 1;
+
+fn join(app: &mut App) -> RenetClient {
+    let mut client = connect(app, CLIENT);
+    let hello = ClientMessage::Hello {
+        version: voxels::net::PROTOCOL_VERSION,
+        name: "steve".to_string(),
+        view_distance: 1,
+    };
+    client.send_message(hello.channel(), hello.encode().unwrap());
+    client
+}
+
+#[derive(Default)]
+struct Received {
+    chunks: Vec<IVec3>,
+    unloaded: Vec<IVec3>,
+}
+
+// runs the server until it has nothing left to send and collects what reached the client
+fn stream(app: &mut App, client: &mut RenetClient) -> Received {
+    let mut received = Received::default();
+    for _ in 0..100 {
+        exchange(app, &mut [(CLIENT, &mut *client)]);
+        while let Some(bytes) = client.receive_message(ServerChannel::Chunks) {
+            match ServerMessage::decode(&bytes).unwrap() {
+                ServerMessage::Chunk(payload) => received.chunks.push(payload.position()),
+                ServerMessage::UnloadChunks(positions) => received.unloaded.extend(positions),
+                message => panic!("unexpected message {:?}", message),
+            }
+        }
+    }
+    received
+}
+
+fn move_player(app: &mut App, translation: Vec3) {
+    let mut query = app.world.query::<(&ClientPlayer, &mut Transform)>();
+    for (_, mut transform) in query.iter_mut(&mut app.world) {
+        transform.translation = translation;
+    }
+}
+
+fn as_set(positions: &[IVec3]) -> HashSet<IVec3> {
+    positions.iter().copied().collect()
+}
+
+#[test]
+fn clients_only_get_the_chunks_they_see() {
+    let mut app = interest_app();
+    let mut client = join(&mut app);
+    let start = chunks_in_view(chunk_at(SPAWN_POSITION), 1);
+
+    // every chunk in view arrives once, nothing outside of it
+    let received = stream(&mut app, &mut client);
+    assert_eq!(received.chunks.len(), start.len());
+    assert_eq!(as_set(&received.chunks), start);
+    assert!(received.unloaded.is_empty());
+    // every generated chunk is in the index the interest looks chunks up in
+    let loaded = app.world.resource::<LoadedChunks>().0.clone();
+    let mut chunk_query = app.world.query::<(Entity, &Chunk)>();
+    assert_eq!(loaded.len(), chunk_query.iter(&app.world).count());
+    for (entity, chunk) in chunk_query.iter(&app.world) {
+        assert_eq!(loaded[&chunk.position()], entity);
+    }
+
+    // two chunks east the western chunks are forgotten and the eastern ones sent
+    let moved = SPAWN_POSITION + Vec3::X * 32.0;
+    move_player(&mut app, moved);
+    let received = stream(&mut app, &mut client);
+    let end = chunks_in_view(chunk_at(moved), 1);
+    assert_eq!(
+        as_set(&received.unloaded),
+        start.difference(&end).copied().collect()
+    );
+    assert_eq!(received.chunks.len(), end.difference(&start).count());
+    assert_eq!(
+        as_set(&received.chunks),
+        end.difference(&start).copied().collect()
+    );
+    // the server unloaded them as well, nobody else sees them
+    let loaded = &app.world.resource::<LoadedChunks>().0;
+    assert_eq!(loaded.keys().copied().collect::<HashSet<_>>(), end);
+    assert_eq!(chunk_query.iter(&app.world).count(), end.len());
+    assert_eq!(app.world.resource::<ChunkRegistry>().chunks, end);
+
+    // a client that comes back has nothing, it is sent its whole view again
+    let id = ClientId::from_raw(CLIENT);
+    let mut server = app.world.resource_mut::<RenetServer>();
+    server.remove_connection(id);
+    let events: Vec<ServerEvent> = std::iter::from_fn(|| server.get_event()).collect();
+    app.world.send_event_batch(events);
+    app.update();
+    assert!(app
+        .world
+        .query::<&ChunkInterest>()
+        .iter(&app.world)
+        .next()
+        .is_none());
+
+    let mut client = join(&mut app);
+    let received = stream(&mut app, &mut client);
+    assert_eq!(received.chunks.len(), start.len());
+    assert_eq!(as_set(&received.chunks), start);
+}
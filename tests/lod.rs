@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+
+use bevy::math::{IVec2, IVec3, Vec3};
+use voxels::world::{
+    chunk::{ChunkView, DEFAULT_VIEW_DISTANCE},
+    client::{
+        greedy_mesh::gen_greedy_mesh,
+        lod::{camera_column, downsample_chunk, LodSettings, FULL_DETAIL_RADIUS, MAX_LOD_LEVEL},
+        mesh_utils::MeshPass,
+    },
+    element::Element,
+    server::chunk::generate_chunk,
+};
+
+mod common;
+
+use common::build_chunk;
+
+#[test]
+fn levels_follow_the_rings_around_the_camera() {
+    let settings = LodSettings {
+        ring_radii: [4, 8, 16],
+    };
+    let column = camera_column(Vec3::new(-20.0, 70.0, 40.0));
+    assert_eq!(column, IVec2::new(-2, 2));
+    let level = |x, z| settings.level(column, IVec3::new(column.x + x, 5, column.y + z));
+    assert_eq!(level(0, 0), 0);
+    assert_eq!(level(4, 0), 0);
+    assert_eq!(level(4, 4), 1);
+    assert_eq!(level(0, -12), 2);
+    assert_eq!(level(40, 0), MAX_LOD_LEVEL);
+}
+
+#[test]
+fn every_level_is_used_within_the_default_view_distance() {
+    let settings = LodSettings::default();
+    let levels: HashSet<u32> = (0..=DEFAULT_VIEW_DISTANCE)
+        .map(|distance| settings.level(IVec2::ZERO, IVec3::new(distance, 4, 0)))
+        .collect();
+    assert_eq!(levels, (0..=MAX_LOD_LEVEL).collect());
+    assert_eq!(settings.level(IVec2::ZERO, IVec3::new(2, 4, 3)), 0);
+
+    // no ring reaches past the view distance, a short one is all full detail
+    for view_distance in 1..=32 {
+        let settings = LodSettings::for_view_distance(view_distance);
+        let last = settings.ring_radii[MAX_LOD_LEVEL as usize - 1];
+        assert!(last <= view_distance.max(FULL_DETAIL_RADIUS));
+        assert!(settings
+            .ring_radii
+            .windows(2)
+            .all(|pair| pair[0] <= pair[1]));
+    }
+    assert_eq!(
+        LodSettings::for_view_distance(3).ring_radii,
+        [FULL_DETAIL_RADIUS; MAX_LOD_LEVEL as usize]
+    );
+}
+
+#[test]
+fn level_zero_keeps_the_chunk() {
+    let chunk = generate_chunk(0.0, 4.0, 0.0);
+    assert_eq!(downsample_chunk(&chunk, 0), chunk);
+}
+
+#[test]
+fn cubes_take_their_most_common_element() {
+    // stone below y 4, a dirt layer from 4 to 6 and a single grass block per 2 wide cube above
+    let chunk = build_chunk(|x, y, z| match y {
+        0..=3 => Element::Stone,
+        4..=5 if x % 2 == 0 => Element::Dirt,
+        4..=5 => Element::Stone,
+        6 if x % 2 == 0 && z % 2 == 0 => Element::Grass,
+        _ => Element::Air,
+    });
+    let downsampled = downsample_chunk(&chunk, 1);
+    assert_eq!(downsampled.blocks[1][3][1].element, Element::Stone);
+    // dirt and stone are tied, either is fine but the cube has to stay filled
+    assert_ne!(downsampled.blocks[0][4][0].element, Element::Air);
+    assert_eq!(downsampled.blocks[0][4][0], downsampled.blocks[1][5][1]);
+    // one block out of eight is not enough to keep the cube
+    assert_eq!(downsampled.blocks[0][6][0].element, Element::Air);
+
+    let coarse = downsample_chunk(&chunk, 3);
+    assert_eq!(coarse.blocks[7][0][7].element, Element::Stone);
+    assert_eq!(coarse.blocks[0][8][0].element, Element::Air);
+}
+
+#[test]
+fn distant_chunks_need_fewer_vertices() {
+    let chunk = generate_chunk(2.0, 4.0, -1.0);
+    let vertices = |level| {
+        let downsampled = downsample_chunk(&chunk, level);
+        gen_greedy_mesh(
+            1.0,
+            &ChunkView::without_neighbours(&downsampled),
+            MeshPass::Opaque,
+        )
+        .count_vertices()
+    };
+    let counts: Vec<usize> = (0..=MAX_LOD_LEVEL).map(vertices).collect();
+    for pair in counts.windows(2) {
+        assert!(pair[1] <= pair[0], "{:?}", counts);
+    }
+    assert!(counts[MAX_LOD_LEVEL as usize] < counts[0], "{:?}", counts);
+}
@@ -0,0 +1,141 @@
+use bevy::{
+    math::{IVec3, Vec2, Vec3},
+    render::mesh::Mesh,
+};
+use voxels::world::{
+    block::{decode_tiled_uv, get_texture, quad_vertices, Block, BlockFace, BlockTexture},
+    chunk::ChunkView,
+    client::{
+        greedy_mesh::gen_greedy_mesh,
+        mesh_utils::{gen_mesh, ChunkMeshBuilder, MeshPass},
+        packed::ChunkVertexFormat,
+    },
+    element::Element,
+    server::chunk::generate_chunk,
+};
+
+mod common;
+
+use common::{chunk_with, indices, positions, uvs};
+
+#[test]
+fn faces_are_written_as_indexed_quads() {
+    let mut builder = ChunkMeshBuilder::new(ChunkVertexFormat::Standard);
+    let block = Block::new(Element::Stone);
+    builder.push_face(
+        1.0,
+        BlockFace::North,
+        Vec3::new(1.0, 2.0, 3.0),
+        block,
+        [3; 4],
+    );
+    builder.push_face(1.0, BlockFace::Top, Vec3::ZERO, block, [3; 4]);
+    assert_eq!(builder.vertex_count(), 8);
+
+    let mesh = builder.build();
+    assert!(builder.is_empty());
+    assert_eq!(indices(&mesh), vec![0, 1, 2, 2, 3, 0, 4, 5, 6, 6, 7, 4]);
+    let (corners, _) = quad_vertices(1.0, BlockFace::North, Vec3::new(1.0, 2.0, 3.0));
+    assert_eq!(positions(&mesh)[..4], corners);
+    assert_eq!(
+        uvs(&mesh)[..4],
+        get_texture(block.texture(BlockFace::North).atlas_rect())
+    );
+}
+
+#[test]
+fn reused_builder_matches_a_new_one() {
+    let chunks = [
+        generate_chunk(0.0, 4.0, 0.0),
+        generate_chunk(1.0, 3.0, -2.0),
+        generate_chunk(0.0, 4.0, 0.0),
+    ];
+    let mut builder = ChunkMeshBuilder::new(ChunkVertexFormat::Standard);
+    for chunk in chunks.iter() {
+        let view = ChunkView::without_neighbours(chunk);
+
+        builder.add_chunk(1.0, &view, MeshPass::Opaque);
+        let reused = builder.build();
+        let fresh = gen_mesh(1.0, &view, MeshPass::Opaque);
+        assert_eq!(positions(&reused), positions(&fresh));
+        assert_eq!(indices(&reused), indices(&fresh));
+
+        builder.add_greedy_chunk(1.0, &view, MeshPass::Opaque);
+        let reused = builder.build();
+        let fresh = gen_greedy_mesh(1.0, &view, MeshPass::Opaque);
+        assert_eq!(positions(&reused), positions(&fresh));
+        assert_eq!(indices(&reused), indices(&fresh));
+    }
+}
+
+#[test]
+fn grass_uses_a_texture_per_face_group() {
+    let grass = Block::new(Element::Grass);
+    let textures = grass.textures();
+    assert_ne!(textures.top, textures.bottom);
+    assert_ne!(textures.top, textures.north);
+    assert_eq!(
+        textures.bottom,
+        Block::new(Element::Dirt).texture(BlockFace::Top)
+    );
+    for side in [
+        BlockFace::North,
+        BlockFace::South,
+        BlockFace::East,
+        BlockFace::West,
+    ] {
+        assert_eq!(grass.texture(side), textures.north);
+    }
+    let stone = Block::new(Element::Stone);
+    assert_eq!(
+        stone.texture(BlockFace::Top),
+        stone.texture(BlockFace::North)
+    );
+}
+
+#[test]
+fn meshers_pick_the_texture_of_each_face() {
+    let chunk = chunk_with(&[(IVec3::splat(4), Element::Grass)]);
+    let view = ChunkView::without_neighbours(&chunk);
+    let grass = Block::new(Element::Grass);
+    // which face a quad belongs to, from where its centre sits in the block
+    let side_of = |corners: &[Vec3]| {
+        let centre = corners.iter().sum::<Vec3>() / 4.0 - Vec3::splat(4.5);
+        match (centre * 2.0).round().as_ivec3() {
+            IVec3::Z => BlockFace::North,
+            IVec3::NEG_Z => BlockFace::South,
+            IVec3::X => BlockFace::East,
+            IVec3::NEG_X => BlockFace::West,
+            IVec3::Y => BlockFace::Top,
+            _ => BlockFace::Bottom,
+        }
+    };
+    // the texture at the centre of a quad
+    let atlas_texture = |uv: Vec2| {
+        *BlockTexture::ALL
+            .iter()
+            .find(|texture| texture.atlas_rect().contains(uv))
+            .unwrap()
+    };
+    let tiled_texture = |uv: Vec2| BlockTexture::ALL[decode_tiled_uv(uv).0 as usize];
+
+    let meshes: [(Mesh, &dyn Fn(Vec2) -> BlockTexture); 2] = [
+        (gen_mesh(1.0, &view, MeshPass::Opaque), &atlas_texture),
+        (
+            gen_greedy_mesh(1.0, &view, MeshPass::Opaque),
+            &tiled_texture,
+        ),
+    ];
+    for (mesh, texture_at) in meshes {
+        let (corners, texture_coordinates) = (positions(&mesh), uvs(&mesh));
+        assert_eq!(corners.len(), 24);
+        for quad in 0..6 {
+            let side = side_of(&corners[quad * 4..quad * 4 + 4]);
+            let centre = texture_coordinates[quad * 4..quad * 4 + 4]
+                .iter()
+                .sum::<Vec2>()
+                / 4.0;
+            assert_eq!(texture_at(centre), grass.texture(side), "{:?}", side);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use bevy::math::IVec3;
+use voxels::world::{
+    chunk::{chunk_neighbourhood, Chunk, ChunkView},
+    client::{
+        lod::downsample_chunk,
+        mesh_utils::{gen_mesh, ChunkMeshBuilder, MeshPass},
+        packed::ChunkVertexFormat,
+        remesh::ChunkMeshJob,
+    },
+    server::chunk::generate_chunk,
+};
+
+mod common;
+
+use common::{packed_vertices, positions};
+
+fn neighbourhood(position: IVec3) -> Vec<Chunk> {
+    chunk_neighbourhood()
+        .chain([IVec3::ZERO])
+        .map(|offset| position + offset)
+        .map(|position| generate_chunk(position.x as f32, position.y as f32, position.z as f32))
+        .collect()
+}
+
+fn job(position: IVec3, chunks: &[Chunk], format: ChunkVertexFormat) -> ChunkMeshJob {
+    ChunkMeshJob {
+        position,
+        chunks: chunks
+            .iter()
+            .map(|chunk| (chunk.position(), (*chunk, 0)))
+            .collect(),
+        format,
+    }
+}
+
+// standard meshes can not repeat textures across merged faces, they are meshed face by face
+#[test]
+fn standard_jobs_mesh_every_face() {
+    let position = IVec3::new(1, 4, -2);
+    let chunks = neighbourhood(position);
+    let meshes = job(position, &chunks, ChunkVertexFormat::Standard)
+        .run()
+        .unwrap();
+    assert_eq!(meshes.level, 0);
+
+    let chunk_map: HashMap<IVec3, &Chunk> = chunks
+        .iter()
+        .map(|chunk| (chunk.position(), chunk))
+        .collect();
+    let view = ChunkView::new(chunk_map[&position], &chunk_map);
+    assert_eq!(
+        positions(&meshes.opaque),
+        positions(&gen_mesh(1.0, &view, MeshPass::Opaque))
+    );
+    assert_eq!(
+        positions(&meshes.cutout),
+        positions(&gen_mesh(1.0, &view, MeshPass::Cutout))
+    );
+    assert_eq!(
+        positions(&meshes.transparent),
+        positions(&gen_mesh(1.0, &view, MeshPass::Transparent))
+    );
+}
+
+#[test]
+fn packed_jobs_merge_faces() {
+    let position = IVec3::new(1, 4, -2);
+    let chunks = neighbourhood(position);
+    let meshes = job(position, &chunks, ChunkVertexFormat::Packed)
+        .run()
+        .unwrap();
+
+    let chunk_map: HashMap<IVec3, &Chunk> = chunks
+        .iter()
+        .map(|chunk| (chunk.position(), chunk))
+        .collect();
+    let view = ChunkView::new(chunk_map[&position], &chunk_map);
+    let mut builder = ChunkMeshBuilder::new(ChunkVertexFormat::Packed);
+    builder.add_greedy_chunk(1.0, &view, MeshPass::Opaque);
+    assert_eq!(
+        packed_vertices(&meshes.opaque),
+        packed_vertices(&builder.build())
+    );
+    builder.add_greedy_chunk(1.0, &view, MeshPass::Cutout);
+    assert_eq!(
+        packed_vertices(&meshes.cutout),
+        packed_vertices(&builder.build())
+    );
+    builder.add_greedy_chunk(1.0, &view, MeshPass::Transparent);
+    assert_eq!(
+        packed_vertices(&meshes.transparent),
+        packed_vertices(&builder.build())
+    );
+
+    assert_eq!(
+        ChunkVertexFormat::from_string("standard"),
+        Some(ChunkVertexFormat::Standard)
+    );
+    assert_eq!(
+        ChunkVertexFormat::from_string("packed"),
+        Some(ChunkVertexFormat::Packed)
+    );
+    assert_eq!(ChunkVertexFormat::from_string("fast"), None);
+}
+
+#[test]
+fn jobs_ignore_neighbours_at_another_level() {
+    let position = IVec3::new(0, 4, 0);
+    let chunks = neighbourhood(position);
+    let job = ChunkMeshJob {
+        position,
+        chunks: chunks
+            .iter()
+            .map(|chunk| {
+                let level = if chunk.position() == position { 1 } else { 2 };
+                (chunk.position(), (*chunk, level))
+            })
+            .collect(),
+        format: ChunkVertexFormat::Standard,
+    };
+    let meshes = job.run().unwrap();
+    assert_eq!(meshes.level, 1);
+
+    let downsampled = downsample_chunk(&chunks[chunks.len() - 1], 1);
+    let alone = gen_mesh(
+        1.0,
+        &ChunkView::without_neighbours(&downsampled),
+        MeshPass::Opaque,
+    );
+    assert_eq!(positions(&meshes.opaque), positions(&alone));
+}
+
+#[test]
+fn jobs_without_their_chunk_produce_nothing() {
+    let job = ChunkMeshJob {
+        position: IVec3::ZERO,
+        chunks: HashMap::new(),
+        format: ChunkVertexFormat::Standard,
+    };
+    assert!(job.run().is_none());
+}
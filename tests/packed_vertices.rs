@@ -0,0 +1,125 @@
+use bevy::{
+    math::{IVec3, UVec2, UVec3, Vec2, Vec3, Vec4},
+    render::mesh::Mesh,
+};
+use voxels::world::{
+    block::{Block, TILE_STRIDE},
+    chunk::{Chunk, ChunkView},
+    client::{
+        mesh_utils::{ChunkMeshBuilder, MeshPass, AO_BRIGHTNESS},
+        packed::{normal_index, ChunkVertexFormat, PackedVertex, FULL_LIGHT, PACKED_NORMALS},
+    },
+    element::{Element, TINTS},
+    server::chunk::generate_chunk,
+};
+
+mod common;
+
+use common::{colors, indices, normals, packed_vertices, positions, uvs};
+
+#[test]
+fn vertices_survive_packing() {
+    let vertices = [
+        PackedVertex::default(),
+        PackedVertex {
+            position: UVec3::new(256, 3, 17),
+            normal: 7,
+            uv: UVec2::new(16, 255),
+            layer: 9,
+            ao: 2,
+            light: FULL_LIGHT,
+            tint: 3,
+        },
+        // the largest value of every field
+        PackedVertex {
+            position: UVec3::splat(511),
+            normal: 15,
+            uv: UVec2::splat(511),
+            layer: 63,
+            ao: 3,
+            light: 15,
+            tint: 3,
+        },
+    ];
+    for vertex in vertices {
+        assert_eq!(PackedVertex::unpack(vertex.pack()), vertex);
+    }
+    // fields do not spill into their neighbours
+    let ao_only = PackedVertex {
+        ao: 3,
+        ..PackedVertex::default()
+    };
+    assert_eq!(ao_only.pack(), [0, 3 << 24]);
+
+    let vertex = PackedVertex::new(
+        Vec3::new(16.0, 0.5, 7.0 / 16.0),
+        Vec3::NEG_Y,
+        Vec2::new(3.0, 1.0),
+    );
+    assert_eq!(vertex.local_position(), Vec3::new(16.0, 0.5, 7.0 / 16.0));
+    assert_eq!(vertex.texture_position(), Vec2::new(3.0, 1.0));
+    assert_eq!(PACKED_NORMALS[vertex.normal as usize], Vec3::NEG_Y);
+    assert_eq!(vertex.light, FULL_LIGHT);
+}
+
+#[test]
+fn packed_is_the_default_format() {
+    assert_eq!(ChunkVertexFormat::default(), ChunkVertexFormat::Packed);
+}
+
+#[test]
+fn normals_pick_the_closest_direction() {
+    for (index, normal) in PACKED_NORMALS.iter().enumerate() {
+        assert_eq!(normal_index(*normal), index as u32);
+    }
+    assert_eq!(normal_index(Vec3::new(0.1, 0.9, 0.0)), 2);
+    assert_eq!(normal_index(Vec3::new(1.0, 0.0, -1.0).normalize()), 8);
+}
+
+#[test]
+fn packed_meshes_match_standard_meshes() {
+    let mut chunk: Chunk = generate_chunk(1.0, 4.0, -2.0);
+    // model blocks for the diagonal normals and partial faces
+    for (position, element) in [
+        (IVec3::new(3, 15, 3), Element::Flower),
+        (IVec3::new(5, 15, 9), Element::StoneStairs),
+        (IVec3::new(12, 15, 1), Element::Torch),
+        (IVec3::new(8, 15, 8), Element::Glass),
+    ] {
+        chunk.blocks[position.x as usize][position.y as usize][position.z as usize] =
+            Block::new(element);
+    }
+    let view = ChunkView::without_neighbours(&chunk);
+    let origin = chunk.world_origin().as_vec3();
+
+    for pass in MeshPass::ALL {
+        let mut standard_builder = ChunkMeshBuilder::new(ChunkVertexFormat::Standard);
+        standard_builder.add_greedy_chunk(1.0, &view, pass);
+        let standard = standard_builder.build();
+        let mut packed_builder = ChunkMeshBuilder::new(ChunkVertexFormat::Packed);
+        packed_builder.add_greedy_chunk(1.0, &view, pass);
+        let packed_mesh = packed_builder.build();
+        assert!(packed_mesh.attribute(Mesh::ATTRIBUTE_POSITION).is_none());
+
+        let vertices = packed_vertices(&packed_mesh);
+        let positions = positions(&standard);
+        let normals = normals(&standard);
+        let (uvs, colors) = (uvs(&standard), colors(&standard));
+        assert!(!vertices.is_empty());
+        assert_eq!(vertices.len(), positions.len());
+        assert_eq!(indices(&packed_mesh), indices(&standard));
+
+        for (index, vertex) in vertices.iter().enumerate() {
+            assert_eq!(vertex.local_position() + origin, positions[index]);
+            assert!(PACKED_NORMALS[vertex.normal as usize].abs_diff_eq(normals[index], 1e-6));
+            // what assets/shaders/chunk_vertex.wgsl hands to the fragment shader
+            let origin = Vec2::new(vertex.layer as f32 * TILE_STRIDE + 1.0, 0.0);
+            let uv = origin + vertex.texture_position();
+            assert!(uv.abs_diff_eq(uvs[index], 1e-4), "{} {}", uv, uvs[index]);
+            let tint = Vec4::from(TINTS[vertex.tint as usize]);
+            let brightness = AO_BRIGHTNESS[vertex.ao as usize];
+            let color = (tint.truncate() * brightness).extend(tint.w);
+            assert!(color.abs_diff_eq(colors[index], 1e-6));
+        }
+    }
+}
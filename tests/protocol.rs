@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use bevy::{math::Vec3, prelude::*};
+use bevy_renet::renet::{ClientId, RenetClient, RenetServer};
+use voxels::{
+    net::{
+        accept_hello, ClientMessage, ProtocolError, ServerChannel, ServerMessage, PROTOCOL_VERSION,
+    },
+    player::{
+        client::handshake::ClientProfile,
+        server::handshake::{
+            disconnect_rejected_clients, receive_handshakes, ConnectedPlayers, REJECT_GRACE,
+        },
+    },
+};
+
+mod common;
+
+use common::{connect, exchange};
+
+#[test]
+fn messages_carry_the_protocol_version() {
+    let messages = [
+        ClientMessage::Hello {
+            version: PROTOCOL_VERSION,
+            name: "steve".to_string(),
+            view_distance: 6,
+        },
+        ClientMessage::Move(Vec3::new(0.0, 0.0, -1.0)),
+        ClientMessage::Command("/chunk radius 4".to_string()),
+    ];
+    for message in messages {
+        let bytes = message.encode().unwrap();
+        assert_eq!(bytes[..2], PROTOCOL_VERSION.to_le_bytes());
+        assert_eq!(ClientMessage::decode(&bytes).unwrap(), message);
+    }
+    let rejected = ServerMessage::Rejected {
+        reason: "full".to_string(),
+    };
+    assert_eq!(
+        ServerMessage::decode(&rejected.encode().unwrap()).unwrap(),
+        rejected
+    );
+
+    // a message from another version is not read at all
+    let mut bytes = ServerMessage::PlayerPosition(Vec3::ONE).encode().unwrap();
+    bytes[..2].copy_from_slice(&(PROTOCOL_VERSION + 1).to_le_bytes());
+    let error = ServerMessage::decode(&bytes).unwrap_err();
+    assert!(matches!(
+        error,
+        ProtocolError::VersionMismatch { ours, theirs }
+            if ours == PROTOCOL_VERSION && theirs == PROTOCOL_VERSION + 1
+    ));
+    assert!(error
+        .to_string()
+        .contains(&format!("protocol version {}", PROTOCOL_VERSION + 1)));
+
+    assert!(matches!(
+        ClientMessage::decode(&[1]),
+        Err(ProtocolError::MissingHeader)
+    ));
+    let mut garbage = PROTOCOL_VERSION.to_le_bytes().to_vec();
+    garbage.extend([200, 1, 2]);
+    assert!(matches!(
+        ClientMessage::decode(&garbage),
+        Err(ProtocolError::Encoding(_))
+    ));
+}
+
+#[test]
+fn hellos_are_checked() {
+    assert_eq!(accept_hello(PROTOCOL_VERSION, "steve", 2, 8, false), Ok(2));
+    // the view distance is limited to what the server streams
+    assert_eq!(accept_hello(PROTOCOL_VERSION, "steve", 32, 8, false), Ok(8));
+    assert_eq!(accept_hello(PROTOCOL_VERSION, "steve", -3, 8, false), Ok(1));
+
+    let reason = accept_hello(PROTOCOL_VERSION + 1, "steve", 2, 8, false).unwrap_err();
+    assert!(reason.contains("protocol version"), "{}", reason);
+    for name in ["", "a_very_long_player_name", "two words"] {
+        assert!(accept_hello(PROTOCOL_VERSION, name, 2, 8, false).is_err());
+    }
+    let reason = accept_hello(PROTOCOL_VERSION, "steve", 2, 8, true).unwrap_err();
+    assert_eq!(reason, "steve is already playing");
+
+    // clients without a name get a valid one of their own, the client id is a timestamp
+    let first = ClientProfile::for_client(1_700_000_000_123);
+    let second = ClientProfile::for_client(1_700_000_000_456);
+    assert_ne!(first.name, second.name);
+    for profile in [first, second] {
+        assert!(accept_hello(PROTOCOL_VERSION, &profile.name, 2, 8, false).is_ok());
+    }
+    let largest = ClientProfile::for_client(u64::MAX);
+    assert!(accept_hello(PROTOCOL_VERSION, &largest.name, 2, 8, false).is_ok());
+}
+
+fn handshake_app() -> App {
+    let mut app = common::server_app();
+    app.init_resource::<Time>().add_systems(
+        Update,
+        (receive_handshakes, disconnect_rejected_clients).chain(),
+    );
+    app
+}
+
+fn hello(client: &mut RenetClient, version: u16, name: &str) {
+    let message = ClientMessage::Hello {
+        version,
+        name: name.to_string(),
+        view_distance: 10,
+    };
+    client.send_message(message.channel(), message.encode().unwrap());
+}
+
+fn reply(client: &mut RenetClient) -> ServerMessage {
+    let bytes = client.receive_message(ServerChannel::Handshake).unwrap();
+    ServerMessage::decode(&bytes).unwrap()
+}
+
+#[test]
+fn the_handshake_accepts_or_rejects_clients() {
+    let mut app = handshake_app();
+    let mut first = connect(&mut app, 1);
+    let mut second = connect(&mut app, 2);
+    let mut outdated = connect(&mut app, 3);
+    hello(&mut first, PROTOCOL_VERSION, "steve");
+    exchange(&mut app, &mut [(1, &mut first)]);
+    hello(&mut second, PROTOCOL_VERSION, "steve");
+    hello(&mut outdated, PROTOCOL_VERSION.wrapping_sub(1), "alex");
+    exchange(&mut app, &mut [(2, &mut second), (3, &mut outdated)]);
+
+    assert_eq!(
+        reply(&mut first),
+        ServerMessage::Welcome { view_distance: 4 }
+    );
+    assert_eq!(
+        reply(&mut second),
+        ServerMessage::Rejected {
+            reason: "steve is already playing".to_string()
+        }
+    );
+    let ServerMessage::Rejected { reason } = reply(&mut outdated) else {
+        panic!("the outdated client was not rejected");
+    };
+    assert!(reason.contains("protocol version"), "{}", reason);
+
+    let players = app.world.resource::<ConnectedPlayers>();
+    assert_eq!(players.ids(), vec![ClientId::from_raw(1)]);
+    assert_eq!(players.0[&ClientId::from_raw(1)].view_distance, 4);
+
+    // rejected clients are disconnected once the reason had time to arrive
+    app.world
+        .resource_mut::<Time>()
+        .advance_by(REJECT_GRACE + Duration::from_millis(1));
+    app.update();
+    let server = app.world.resource::<RenetServer>();
+    assert!(server.is_connected(ClientId::from_raw(1)));
+    assert!(!server.is_connected(ClientId::from_raw(2)));
+    assert!(!server.is_connected(ClientId::from_raw(3)));
+}
@@ -0,0 +1,188 @@
+use bevy::prelude::*;
+use bevy_renet::renet::ClientId;
+use voxels::{
+    command_system::{
+        builder_commands::{
+            copy_command, selection_command, selection_size, Clipboards, Selections,
+        },
+        events::CommandDispatchEvent,
+        schematic::{Axis, Schematic, SchematicError},
+    },
+    net::ClientPlayer,
+    world::{element::Element, server::chunk::LoadedChunks},
+};
+
+fn sample_schematic() -> Schematic {
+    Schematic {
+        blocks: vec![
+            (IVec3::new(0, 0, 0), Element::Stone),
+            (IVec3::new(2, 1, 0), Element::Glass),
+            (IVec3::new(1, -1, 3), Element::Air),
+        ],
+    }
+}
+
+fn offsets(schematic: &Schematic) -> Vec<IVec3> {
+    schematic.blocks.iter().map(|(offset, _)| *offset).collect()
+}
+
+#[test]
+fn rotation_turns_around_the_vertical_axis() {
+    let mut schematic = sample_schematic();
+    schematic.rotate(1);
+    assert_eq!(
+        offsets(&schematic),
+        vec![
+            IVec3::new(0, 0, 0),
+            IVec3::new(0, 1, 2),
+            IVec3::new(-3, -1, 1)
+        ]
+    );
+    // the elements stay with their blocks
+    assert_eq!(schematic.blocks[1].1, Element::Glass);
+
+    // four quarter turns, or a negative turn undoing a positive one, change nothing
+    let mut full_turn = sample_schematic();
+    full_turn.rotate(4);
+    assert_eq!(full_turn, sample_schematic());
+    schematic.rotate(-1);
+    assert_eq!(schematic, sample_schematic());
+    let mut half_turn = sample_schematic();
+    half_turn.rotate(2);
+    let mut twice = sample_schematic();
+    twice.rotate(1);
+    twice.rotate(1);
+    assert_eq!(half_turn, twice);
+}
+
+#[test]
+fn mirroring_flips_one_axis() {
+    for (axis, flip) in [
+        (Axis::X, IVec3::new(-1, 1, 1)),
+        (Axis::Y, IVec3::new(1, -1, 1)),
+        (Axis::Z, IVec3::new(1, 1, -1)),
+    ] {
+        let mut schematic = sample_schematic();
+        schematic.mirror(axis);
+        let expected: Vec<IVec3> = offsets(&sample_schematic())
+            .into_iter()
+            .map(|offset| offset * flip)
+            .collect();
+        assert_eq!(offsets(&schematic), expected);
+        schematic.mirror(axis);
+        assert_eq!(schematic, sample_schematic());
+    }
+}
+
+#[test]
+fn schematics_round_trip() {
+    let schematic = sample_schematic();
+    let mut bytes = Vec::new();
+    schematic.write(&mut bytes).unwrap();
+    assert_eq!(Schematic::read(bytes.as_slice()).unwrap(), schematic);
+
+    assert!(Schematic::read(&b"not a schematic"[..]).is_err());
+    let mut other = Vec::new();
+    let mut encoder = flate2::write::GzEncoder::new(&mut other, flate2::Compression::default());
+    std::io::Write::write_all(&mut encoder, b"NOPE\x01\x00\x00\x00").unwrap();
+    encoder.finish().unwrap();
+    assert!(matches!(
+        Schematic::read(other.as_slice()),
+        Err(SchematicError::InvalidHeader)
+    ));
+}
+
+#[test]
+fn selections_use_the_position_of_the_sender() {
+    let mut app = App::new();
+    app.init_resource::<Selections>()
+        .add_event::<CommandDispatchEvent>()
+        .add_systems(Update, selection_command);
+    for (id, translation) in [
+        (1, Vec3::new(0.5, 70.2, 0.5)),
+        (2, Vec3::new(-8.5, 64.0, 30.0)),
+    ] {
+        app.world.spawn((
+            Transform::from_translation(translation),
+            ClientPlayer {
+                id: ClientId::from_raw(id),
+            },
+        ));
+    }
+    app.world.send_event(CommandDispatchEvent {
+        command: "/pos1".to_string(),
+        client_id: Some(ClientId::from_raw(2)),
+    });
+    app.world.send_event(CommandDispatchEvent {
+        command: "/pos2".to_string(),
+        client_id: Some(ClientId::from_raw(1)),
+    });
+    app.update();
+
+    let selections = app.world.resource::<Selections>();
+    assert_eq!(
+        selections.0[&ClientId::from_raw(2)].first,
+        Some(IVec3::new(-9, 64, 30))
+    );
+    assert_eq!(
+        selections.0[&ClientId::from_raw(1)].second,
+        Some(IVec3::new(0, 70, 0))
+    );
+}
+
+#[test]
+fn selection_sizes_do_not_overflow() {
+    assert_eq!(
+        selection_size(IVec3::new(-1, 0, 5), IVec3::new(1, 2, 5)),
+        Some(IVec3::new(3, 3, 1))
+    );
+    assert!(selection_size(IVec3::ZERO, IVec3::splat(255)).is_some());
+    assert_eq!(selection_size(IVec3::ZERO, IVec3::new(256, 255, 255)), None);
+    // corners at both ends of the i32 range
+    assert_eq!(
+        selection_size(IVec3::new(i32::MIN, 0, 0), IVec3::new(i32::MAX, 0, 0)),
+        None
+    );
+    assert_eq!(
+        selection_size(IVec3::splat(i32::MIN), IVec3::splat(i32::MAX)),
+        None
+    );
+}
+
+#[test]
+fn far_away_selections_are_not_copied() {
+    let mut app = App::new();
+    app.init_resource::<Selections>()
+        .init_resource::<Clipboards>()
+        .init_resource::<LoadedChunks>()
+        .add_event::<CommandDispatchEvent>()
+        .add_systems(Update, (selection_command, copy_command).chain());
+    let id = ClientId::from_raw(1);
+    app.world.spawn((
+        Transform::from_translation(Vec3::new(0.5, 70.0, 0.5)),
+        ClientPlayer { id },
+    ));
+    let mut copy = |first: IVec3, second: IVec3| {
+        for command in [
+            format!("/pos1 {} {} {}", first.x, first.y, first.z),
+            format!("/pos2 {} {} {}", second.x, second.y, second.z),
+            "/copy".to_string(),
+        ] {
+            app.world.send_event(CommandDispatchEvent {
+                command,
+                client_id: Some(id),
+            });
+        }
+        app.update();
+        app.world.resource_mut::<Clipboards>().0.remove(&id)
+    };
+
+    let clipboard = copy(IVec3::new(0, 70, 0), IVec3::new(1, 70, 2)).unwrap();
+    assert_eq!(clipboard.blocks.len(), 2 * 3);
+    assert_eq!(copy(IVec3::splat(i32::MIN), IVec3::splat(i32::MAX)), None);
+    // small, but too far from the player for its offsets
+    assert_eq!(
+        copy(IVec3::splat(i32::MIN), IVec3::splat(i32::MIN + 1)),
+        None
+    );
+}
@@ -0,0 +1,134 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use voxels::world::{
+    chunk::Chunk,
+    element::Element,
+    shapes::{
+        cylinder_positions, parse_height, parse_radius, region_positions, replaced_blocks,
+        sphere_positions, MAX_SHAPE_HEIGHT, MAX_SHAPE_RADIUS,
+    },
+};
+
+mod common;
+
+use common::build_chunk;
+
+fn bounds(positions: &[IVec3]) -> (IVec3, IVec3) {
+    positions
+        .iter()
+        .fold((IVec3::MAX, IVec3::MIN), |(min, max), position| {
+            (min.min(*position), max.max(*position))
+        })
+}
+
+#[test]
+fn heights_have_their_own_limit() {
+    assert_eq!(parse_radius("8"), Some(8));
+    assert_eq!(parse_radius("0"), None);
+    assert_eq!(parse_radius(&(MAX_SHAPE_RADIUS + 1).to_string()), None);
+    // a cylinder can be taller than any sphere is wide
+    let tall = (MAX_SHAPE_RADIUS + 1).to_string();
+    assert_eq!(parse_height(&tall), Some(MAX_SHAPE_RADIUS + 1));
+    assert_eq!(
+        parse_height(&MAX_SHAPE_HEIGHT.to_string()),
+        Some(MAX_SHAPE_HEIGHT)
+    );
+    assert_eq!(parse_height(&(MAX_SHAPE_HEIGHT + 1).to_string()), None);
+    assert_eq!(parse_height("-3"), None);
+}
+
+#[test]
+fn regions_cover_both_corners() {
+    let (min, max) = (IVec3::new(-2, 10, 3), IVec3::new(1, 12, 3));
+    let positions = region_positions(min, max).unwrap();
+    assert_eq!(positions.len(), 4 * 3);
+    assert_eq!(bounds(&positions), (min, max));
+    assert_eq!(
+        positions.iter().collect::<HashSet<_>>().len(),
+        positions.len()
+    );
+
+    assert!(region_positions(IVec3::ZERO, IVec3::splat(1000)).is_none());
+    // corners from a command can lie at both ends of the i32 range
+    assert!(region_positions(IVec3::splat(i32::MIN), IVec3::splat(i32::MAX)).is_none());
+    assert!(region_positions(IVec3::new(i32::MIN, 0, 0), IVec3::new(i32::MAX, 0, 0)).is_none());
+}
+
+#[test]
+fn spheres_stay_within_their_radius() {
+    let center = IVec3::new(5, 70, -5);
+    // the center, its 6 face neighbours and 12 edge neighbours
+    let solid = sphere_positions(center, 1, false);
+    assert_eq!(solid.len(), 19);
+    let hollow = sphere_positions(center, 1, true);
+    assert_eq!(hollow.len(), 18);
+    assert!(!hollow.contains(&center));
+
+    for radius in [2, 5] {
+        let solid = sphere_positions(center, radius, false);
+        assert_eq!(
+            bounds(&solid),
+            (center - IVec3::splat(radius), center + IVec3::splat(radius))
+        );
+        let hollow = sphere_positions(center, radius, true);
+        assert!(hollow.len() < solid.len());
+        let solid: HashSet<IVec3> = solid.into_iter().collect();
+        assert!(hollow.iter().all(|position| solid.contains(position)));
+    }
+}
+
+#[test]
+fn cylinders_stand_on_their_base() {
+    let base = IVec3::new(0, 64, 0);
+    // the 3x3 columns of radius 1, three blocks high
+    let solid = cylinder_positions(base, 1, 3, false);
+    assert_eq!(solid.len(), 27);
+    assert_eq!(
+        bounds(&solid),
+        (base - IVec3::new(1, 0, 1), base + IVec3::new(1, 2, 1))
+    );
+    // hollow cylinders lose their middle column
+    let hollow = cylinder_positions(base, 1, 3, true);
+    assert_eq!(hollow.len(), 24);
+    assert!(hollow
+        .iter()
+        .all(|position| position.x != 0 || position.z != 0));
+
+    let tall = cylinder_positions(base, 4, 200, false);
+    assert_eq!(bounds(&tall).1.y, base.y + 199);
+}
+
+#[test]
+fn replace_only_changes_matching_blocks() {
+    let chunk = build_chunk(|x, y, z| match (x, y, z) {
+        (1, 2, 3) | (4, 4, 4) | (10, 10, 10) => Element::Dirt,
+        _ => Element::Stone,
+    });
+    let chunks: HashMap<IVec3, &Chunk> = [(chunk.position(), &chunk)].into_iter().collect();
+
+    let changes = replaced_blocks(
+        IVec3::ZERO,
+        IVec3::splat(5),
+        &chunks,
+        Element::Dirt,
+        Element::Grass,
+    )
+    .unwrap();
+    assert_eq!(
+        changes.into_iter().collect::<HashSet<_>>(),
+        HashSet::from([
+            (IVec3::new(1, 2, 3), Element::Grass),
+            (IVec3::new(4, 4, 4), Element::Grass),
+        ])
+    );
+    assert!(replaced_blocks(
+        IVec3::ZERO,
+        IVec3::splat(5),
+        &chunks,
+        Element::Water,
+        Element::Air
+    )
+    .unwrap()
+    .is_empty());
+}
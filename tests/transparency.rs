@@ -0,0 +1,109 @@
+use bevy::{math::IVec3, render::mesh::Mesh};
+use voxels::world::{
+    block::BlockFace,
+    chunk::{Chunk, ChunkView},
+    client::{
+        greedy_mesh::gen_greedy_mesh,
+        mesh_utils::{gen_mesh, MeshPass},
+    },
+    element::Element,
+};
+
+mod common;
+
+use common::chunk_with;
+
+fn quads(chunk: &Chunk, pass: MeshPass) -> usize {
+    let mesh: Mesh = gen_mesh(1.0, &ChunkView::without_neighbours(chunk), pass);
+    mesh.count_vertices() / 4
+}
+
+#[test]
+fn transparent_elements_are_declared() {
+    for element in [Element::Glass, Element::Water] {
+        assert!(element.is_transparent());
+        assert!(!element.is_opaque());
+        assert!(element.tint()[3] < 1.0);
+        assert_eq!(MeshPass::of(element), Some(MeshPass::Transparent));
+    }
+    // alpha tested blocks are see through where their texture is, not where their tint is
+    for element in [Element::Leaves, Element::Flower, Element::Torch] {
+        assert!(element.is_transparent());
+        assert!(!element.is_opaque());
+        assert_eq!(element.tint()[3], 1.0);
+        assert_eq!(MeshPass::of(element), Some(MeshPass::Cutout));
+    }
+    for element in [Element::Dirt, Element::Grass, Element::Stone] {
+        assert!(element.is_opaque());
+        assert_eq!(element.tint()[3], 1.0);
+    }
+    assert!(!Element::Air.is_opaque() && !Element::Air.is_transparent());
+}
+
+#[test]
+fn transparent_blocks_go_into_their_own_mesh() {
+    let chunk = chunk_with(&[
+        (IVec3::new(1, 1, 1), Element::Stone),
+        (IVec3::new(5, 5, 5), Element::Glass),
+    ]);
+    assert_eq!(quads(&chunk, MeshPass::Opaque), 6);
+    assert_eq!(quads(&chunk, MeshPass::Transparent), 6);
+}
+
+#[test]
+fn cutout_blocks_go_into_their_own_mesh() {
+    let chunk = chunk_with(&[
+        (IVec3::new(1, 1, 1), Element::Stone),
+        (IVec3::new(5, 5, 5), Element::Glass),
+        (IVec3::new(2, 1, 1), Element::Leaves),
+    ]);
+    // the stone face against the leaves stays, the leaf face against the stone is hidden
+    assert_eq!(quads(&chunk, MeshPass::Opaque), 6);
+    assert_eq!(quads(&chunk, MeshPass::Cutout), 5);
+    assert_eq!(quads(&chunk, MeshPass::Transparent), 6);
+}
+
+#[test]
+fn faces_between_the_same_transparent_element_are_culled() {
+    let mut blocks = Vec::new();
+    for x in 0..2 {
+        for y in 0..2 {
+            for z in 0..2 {
+                blocks.push((IVec3::new(x, y, z) + IVec3::splat(4), Element::Water));
+            }
+        }
+    }
+    let chunk = chunk_with(&blocks);
+    // only the outside of the 2x2x2 cube
+    assert_eq!(quads(&chunk, MeshPass::Transparent), 6 * 4);
+    let greedy = gen_greedy_mesh(
+        1.0,
+        &ChunkView::without_neighbours(&chunk),
+        MeshPass::Transparent,
+    );
+    assert_eq!(greedy.count_vertices(), 6 * 4);
+}
+
+#[test]
+fn faces_behind_transparent_blocks_stay_visible() {
+    let chunk = chunk_with(&[
+        (IVec3::new(4, 4, 4), Element::Stone),
+        (IVec3::new(5, 4, 4), Element::Glass),
+    ]);
+    // the stone face towards the glass is drawn, the glass face towards the stone is not
+    assert_eq!(quads(&chunk, MeshPass::Opaque), 6);
+    assert_eq!(quads(&chunk, MeshPass::Transparent), 5);
+}
+
+#[test]
+fn different_transparent_elements_show_their_shared_faces() {
+    let chunk = chunk_with(&[
+        (IVec3::new(4, 4, 4), Element::Water),
+        (IVec3::new(5, 4, 4), Element::Glass),
+    ]);
+    assert_eq!(quads(&chunk, MeshPass::Transparent), 12);
+    assert!(Element::Water.shows_face_against(Element::Glass, BlockFace::East));
+    assert!(!Element::Water.shows_face_against(Element::Water, BlockFace::East));
+    assert!(!Element::Glass.shows_face_against(Element::Stone, BlockFace::East));
+    assert!(Element::Stone.shows_face_against(Element::Leaves, BlockFace::East));
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use bevy::math::IVec3;
+use voxels::world::{
+    block::BlockFace,
+    client::visibility::{chunk_connections, visible_chunks, ChunkConnections},
+    element::Element,
+};
+
+mod common;
+
+use common::build_chunk;
+
+#[test]
+fn open_space_connects_the_faces_it_touches() {
+    assert_eq!(
+        chunk_connections(&build_chunk(|_, _, _| Element::Air)),
+        ChunkConnections::ALL
+    );
+    assert_eq!(
+        chunk_connections(&build_chunk(|_, _, _| Element::Stone)),
+        ChunkConnections::NONE
+    );
+
+    // a tunnel from west to east through solid stone
+    let tunnel = chunk_connections(&build_chunk(|_, y, z| {
+        if y == 8 && z == 8 {
+            Element::Air
+        } else {
+            Element::Stone
+        }
+    }));
+    assert!(tunnel.connects(BlockFace::West, BlockFace::East));
+    assert!(tunnel.connects(BlockFace::East, BlockFace::West));
+    assert!(!tunnel.connects(BlockFace::West, BlockFace::Top));
+    assert!(!tunnel.connects(BlockFace::North, BlockFace::South));
+
+    // glass and leaves can be seen through, a pocket inside the stone touches no face
+    let glass_wall = chunk_connections(&build_chunk(|x, _, _| {
+        if x == 4 {
+            Element::Glass
+        } else {
+            Element::Air
+        }
+    }));
+    assert_eq!(glass_wall, ChunkConnections::ALL);
+    let pocket = chunk_connections(&build_chunk(|x, y, z| {
+        if (4..8).contains(&x) && (4..8).contains(&y) && (4..8).contains(&z) {
+            Element::Air
+        } else {
+            Element::Stone
+        }
+    }));
+    assert_eq!(pocket, ChunkConnections::NONE);
+}
+
+#[test]
+fn the_walk_stops_at_solid_chunks() {
+    // a row of open chunks with a solid one in the middle
+    let chunks: HashMap<IVec3, ChunkConnections> = (-2..=4)
+        .map(|x| {
+            let connections = if x == 2 {
+                ChunkConnections::NONE
+            } else {
+                ChunkConnections::ALL
+            };
+            (IVec3::new(x, 0, 0), connections)
+        })
+        .collect();
+    let visible = visible_chunks(
+        IVec3::ZERO,
+        |position| chunks.get(&position).copied(),
+        |_| true,
+    );
+    // the solid chunk itself is seen, the chunks behind it are not
+    for x in -2..=2 {
+        assert!(visible.contains(&IVec3::new(x, 0, 0)), "{}", x);
+    }
+    assert!(!visible.contains(&IVec3::new(3, 0, 0)));
+    assert!(!visible.contains(&IVec3::new(4, 0, 0)));
+
+    // nothing outside the frustum is visited, and nothing behind it
+    let visible = visible_chunks(
+        IVec3::ZERO,
+        |position| chunks.get(&position).copied(),
+        |position| position.x != -1,
+    );
+    assert!(!visible.contains(&IVec3::new(-1, 0, 0)));
+    assert!(!visible.contains(&IVec3::new(-2, 0, 0)));
+}
+
+#[test]
+fn the_walk_never_turns_back() {
+    // a chunk behind a wall can only be reached by walking around it and back towards the camera
+    //   z=1:  . . .
+    //   z=0:  C # T
+    let mut chunks = HashMap::new();
+    for x in 0..3 {
+        chunks.insert(IVec3::new(x, 0, 1), ChunkConnections::ALL);
+    }
+    chunks.insert(IVec3::new(0, 0, 0), ChunkConnections::ALL);
+    chunks.insert(IVec3::new(1, 0, 0), ChunkConnections::NONE);
+    chunks.insert(IVec3::new(2, 0, 0), ChunkConnections::ALL);
+    let visible = visible_chunks(
+        IVec3::ZERO,
+        |position| chunks.get(&position).copied(),
+        |_| true,
+    );
+    assert!(visible.contains(&IVec3::new(2, 0, 1)));
+    assert!(!visible.contains(&IVec3::new(2, 0, 0)));
+
+    // a chunk that only connects two of its faces lets the walk through in that direction only
+    let mut corridor = ChunkConnections::NONE;
+    corridor.connect(BlockFace::West, BlockFace::East);
+    let chunks = HashMap::from([
+        (IVec3::ZERO, ChunkConnections::ALL),
+        (IVec3::X, corridor),
+        (IVec3::new(2, 0, 0), ChunkConnections::ALL),
+        (IVec3::new(1, 1, 0), ChunkConnections::ALL),
+    ]);
+    let visible = visible_chunks(
+        IVec3::ZERO,
+        |position| chunks.get(&position).copied(),
+        |_| true,
+    );
+    assert!(visible.contains(&IVec3::new(2, 0, 0)));
+    assert!(!visible.contains(&IVec3::new(1, 1, 0)));
+}
@@ -0,0 +1,191 @@
+use bevy::math::{IVec3, UVec3};
+use voxels::world::{
+    element::Element,
+    vox::{
+        blocks_to_vox, read_vox, vox_to_blocks, write_vox, VoxError, VoxFile, VoxMapping, VoxModel,
+        Voxel,
+    },
+};
+
+fn sample_vox() -> VoxFile {
+    let mut palette = [[0, 0, 0, 255]; 256];
+    palette[0] = [134, 96, 67, 255];
+    palette[1] = [89, 145, 57, 255];
+    palette[9] = [120, 120, 130, 255];
+    VoxFile {
+        models: vec![VoxModel {
+            size: UVec3::new(2, 3, 4),
+            voxels: vec![
+                Voxel {
+                    x: 0,
+                    y: 0,
+                    z: 0,
+                    color_index: 1,
+                },
+                Voxel {
+                    x: 1,
+                    y: 2,
+                    z: 3,
+                    color_index: 2,
+                },
+                Voxel {
+                    x: 1,
+                    y: 0,
+                    z: 1,
+                    color_index: 10,
+                },
+            ],
+        }],
+        palette,
+    }
+}
+
+#[test]
+fn vox_file_round_trips() {
+    let vox = sample_vox();
+    let mut bytes = Vec::new();
+    write_vox(&mut bytes, &vox).unwrap();
+    let read = read_vox(&mut bytes.as_slice()).unwrap();
+    assert_eq!(read, vox);
+}
+
+#[test]
+fn unknown_chunks_are_skipped() {
+    let mut bytes = Vec::new();
+    write_vox(&mut bytes, &sample_vox()).unwrap();
+    // insert an empty nTRN chunk in front of SIZE and grow MAIN's children size
+    let ntrn = [b"nTRN".as_slice(), &0i32.to_le_bytes(), &0i32.to_le_bytes()].concat();
+    let children_size = i32::from_le_bytes(bytes[16..20].try_into().unwrap());
+    bytes[16..20].copy_from_slice(&(children_size + ntrn.len() as i32).to_le_bytes());
+    bytes.splice(20..20, ntrn);
+    let read = read_vox(&mut bytes.as_slice()).unwrap();
+    assert_eq!(read, sample_vox());
+}
+
+#[test]
+fn invalid_header_is_rejected() {
+    let bytes = b"NOPE\x96\x00\x00\x00";
+    assert!(matches!(
+        read_vox(&mut bytes.as_slice()),
+        Err(VoxError::InvalidHeader)
+    ));
+}
+
+#[test]
+fn truncated_file_is_rejected() {
+    let mut bytes = Vec::new();
+    write_vox(&mut bytes, &sample_vox()).unwrap();
+    bytes.truncate(bytes.len() / 2);
+    // MAIN claims more children than are left
+    assert!(matches!(
+        read_vox(&mut bytes.as_slice()),
+        Err(VoxError::InvalidSize(_))
+    ));
+}
+
+#[test]
+fn negative_and_oversized_counts_are_rejected() {
+    let mut bytes = Vec::new();
+    write_vox(&mut bytes, &sample_vox()).unwrap();
+    // the voxel count of the first XYZI chunk, right after the 12 byte SIZE content
+    let count = 56..60;
+    assert_eq!(&bytes[44..48], b"XYZI");
+    for size in [-1, i32::MAX] {
+        let mut bytes = bytes.clone();
+        bytes[count.clone()].copy_from_slice(&size.to_le_bytes());
+        assert!(matches!(
+            read_vox(&mut bytes.as_slice()),
+            Err(VoxError::InvalidSize(invalid)) if invalid == size
+        ));
+    }
+    // a negative content size of the MAIN chunk
+    bytes[12..16].copy_from_slice(&(-8i32).to_le_bytes());
+    assert!(matches!(
+        read_vox(&mut bytes.as_slice()),
+        Err(VoxError::InvalidSize(-8))
+    ));
+}
+
+#[test]
+fn palette_maps_to_closest_element() {
+    let vox = sample_vox();
+    let mapping = VoxMapping::default();
+    let blocks = vox_to_blocks(&vox, &mapping);
+    // magicavoxel is z-up, y and z are swapped
+    assert_eq!(
+        blocks,
+        vec![
+            (IVec3::new(0, 0, 0), Element::Dirt),
+            (IVec3::new(1, 3, 2), Element::Grass),
+            (IVec3::new(1, 1, 0), Element::Stone),
+        ]
+    );
+}
+
+#[test]
+fn explicit_mapping_overrides_colors() {
+    let mapping = VoxMapping::parse("# comment\n10 dirt\n\n1 grass").unwrap();
+    let blocks = vox_to_blocks(&sample_vox(), &mapping);
+    assert_eq!(blocks[0].1, Element::Grass);
+    assert_eq!(blocks[2].1, Element::Dirt);
+    assert!(VoxMapping::parse("0 dirt").is_err());
+    assert!(VoxMapping::parse("3 lava").is_err());
+}
+
+#[test]
+fn blocks_round_trip_through_vox() {
+    let size = IVec3::new(3, 5, 2);
+    let block_at = |position: IVec3| match (position.x + position.y + position.z) % 4 {
+        0 => Element::Air,
+        1 => Element::Dirt,
+        2 => Element::Grass,
+        _ => Element::Stone,
+    };
+    // stone takes the default index of dirt, dirt has to move out of its way
+    let mapping = VoxMapping::parse("1 stone").unwrap();
+    let vox = blocks_to_vox(size, &mapping, block_at).unwrap();
+    assert_eq!(vox.models[0].size, UVec3::new(3, 2, 5));
+
+    let mut indices: Vec<u8> = vox.models[0]
+        .voxels
+        .iter()
+        .map(|voxel| voxel.color_index)
+        .collect();
+    indices.sort();
+    indices.dedup();
+    assert_eq!(indices.len(), 3);
+    assert!(indices.contains(&1));
+
+    let mut bytes = Vec::new();
+    write_vox(&mut bytes, &vox).unwrap();
+    let read = read_vox(&mut bytes.as_slice()).unwrap();
+    let blocks = vox_to_blocks(&read, &mapping);
+    // also without the mapping, by the colours alone
+    let mut by_colour = vox_to_blocks(&read, &VoxMapping::default());
+
+    let mut expected = Vec::new();
+    for x in 0..size.x {
+        for y in 0..size.y {
+            for z in 0..size.z {
+                let position = IVec3::new(x, y, z);
+                if block_at(position) != Element::Air {
+                    expected.push((position, block_at(position)));
+                }
+            }
+        }
+    }
+    let mut blocks = blocks;
+    blocks.sort_by_key(|(position, _)| position.to_array());
+    expected.sort_by_key(|(position, _)| position.to_array());
+    assert_eq!(blocks, expected);
+    by_colour.sort_by_key(|(position, _)| position.to_array());
+    assert_eq!(by_colour, expected);
+}
+
+#[test]
+fn oversized_regions_are_rejected() {
+    let result = blocks_to_vox(IVec3::new(257, 1, 1), &VoxMapping::default(), |_| {
+        Element::Stone
+    });
+    assert!(matches!(result, Err(VoxError::ModelTooLarge(_))));
+}